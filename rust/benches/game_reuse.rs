@@ -0,0 +1,49 @@
+// File: benches/game_reuse.rs
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use dice_game::{GameMode, OptimizationLevel};
+use dice_game::game::Game;
+use dice_game::rng::game_seed;
+use dice_game::strategies::create_strategies;
+
+const NUM_GAMES: u64 = 10_000;
+
+fn game_reuse(c: &mut Criterion) {
+    let mut strategies = create_strategies().into_iter();
+    let (mut first, mut second) = (strategies.next().unwrap(), strategies.next().unwrap());
+    let levels = [
+        OptimizationLevel::Standard,
+        OptimizationLevel::FastPrecise,
+        OptimizationLevel::Fast,
+        OptimizationLevel::UltraOptimized,
+    ];
+
+    let mut group = c.benchmark_group("game_reuse");
+    group.throughput(Throughput::Elements(NUM_GAMES));
+    for level in levels {
+        let label = format!("{:?}", level);
+
+        group.bench_with_input(BenchmarkId::new("new_per_game", &label), &level, |b, &level| {
+            b.iter(|| {
+                for i in 0..NUM_GAMES {
+                    let mut game = Game::new(GameMode::Base, level, game_seed(42, i));
+                    criterion::black_box(game.play_pair([first.as_mut(), second.as_mut()]));
+                }
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("reset", &label), &level, |b, &level| {
+            let mut game = Game::new(GameMode::Base, level, 42);
+            b.iter(|| {
+                for i in 0..NUM_GAMES {
+                    game.reset(game_seed(42, i));
+                    criterion::black_box(game.play_pair([first.as_mut(), second.as_mut()]));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, game_reuse);
+criterion_main!(benches);
@@ -0,0 +1,48 @@
+// File: benches/multithreaded_scaling.rs
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use dice_game::{GameMode, OptimizationLevel, Strategy};
+use dice_game::control::SimulationControl;
+use dice_game::game::GameRules;
+use dice_game::simulation::{Simulator, SimulationConfig, SimulatorType};
+use dice_game::simulator_multithreded::MultithreadedSimulator;
+use dice_game::strategies::create_strategies;
+use std::sync::Arc;
+use std::thread;
+
+const NUM_GAMES: usize = 100_000;
+
+fn multithreaded_scaling(c: &mut Criterion) {
+    let strategies: Vec<Arc<dyn Strategy>> = create_strategies().into_iter().take(2).map(Arc::from).collect();
+    let config = SimulationConfig {
+        mode: GameMode::Base,
+        optimization: OptimizationLevel::Fast,
+        rules: GameRules::default(),
+        strategies,
+        num_games: NUM_GAMES,
+        first_game: 0,
+        seed: 42,
+        simulator_type: SimulatorType::Multithreaded,
+    };
+
+    let control = SimulationControl::new();
+
+    let max_threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let thread_counts = std::iter::successors(Some(1), |&n| Some(n * 2))
+        .take_while(|&n| n < max_threads)
+        .chain(std::iter::once(max_threads));
+
+    let mut group = c.benchmark_group("multithreaded_scaling");
+    group.throughput(Throughput::Elements(NUM_GAMES as u64));
+    group.sample_size(10);
+    for num_threads in thread_counts {
+        let simulator = MultithreadedSimulator::new(num_threads);
+        group.bench_with_input(BenchmarkId::from_parameter(num_threads), &num_threads, |b, _| {
+            b.iter(|| simulator.simulate(&config, &control))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, multithreaded_scaling);
+criterion_main!(benches);
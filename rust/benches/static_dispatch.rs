@@ -0,0 +1,41 @@
+// File: benches/static_dispatch.rs
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use dice_game::{GameMode, OptimizationLevel, Strategy};
+use dice_game::game::{Game, WeightedDice};
+use dice_game::rng::{game_seed, Xoroshiro128Plus};
+use dice_game::strategies::{HighestValueStrategy, HighestProbabilityStrategy};
+
+const NUM_GAMES: u64 = 10_000;
+
+fn static_dispatch(c: &mut Criterion) {
+    let (mut a, mut b) = (HighestValueStrategy, HighestProbabilityStrategy);
+
+    let mut group = c.benchmark_group("static_dispatch");
+    group.throughput(Throughput::Elements(NUM_GAMES));
+
+    group.bench_function("dynamic", |bench| {
+        let mut game = Game::new(GameMode::Base, OptimizationLevel::UltraOptimized, 42);
+        bench.iter(|| {
+            for i in 0..NUM_GAMES {
+                game.reset(game_seed(42, i));
+                criterion::black_box(game.play_pair([&mut a as &mut dyn Strategy, &mut b]));
+            }
+        })
+    });
+
+    group.bench_function("static", |bench| {
+        let mut game = Game::with_parts(GameMode::Base, Xoroshiro128Plus::new(42), WeightedDice::new());
+        bench.iter(|| {
+            for i in 0..NUM_GAMES {
+                game.reset(game_seed(42, i));
+                criterion::black_box(game.play_game_static(&mut a, &mut b));
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, static_dispatch);
+criterion_main!(benches);
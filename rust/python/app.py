@@ -16,8 +16,8 @@ def play():
     data = request.json
     game = Game()
     strategy = Strategy.from_name(data['strategy'])
-    game.set_player_strategy(strategy)
-    result = game.play()
+    opponent = Strategy.from_name(data.get('opponent', 'Highest Value'))
+    result = game.play_outcome(strategy, opponent)
     return jsonify(result)
 
 @app.route('/analyze', methods=['POST'])
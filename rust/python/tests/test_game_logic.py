@@ -0,0 +1,43 @@
+# File: python/tests/test_game_logic.py
+
+# Smoke tests for the game_logic extension; run `maturin develop` first, then `pytest python/tests`.
+
+import pytest
+import game_logic as gl
+
+
+class Greedy:
+    name = "Greedy"
+
+    def choose_move(self, board, score, round, roll):
+        tile = 1 << (roll - 1)
+        return tile if board & tile else 0
+
+
+class Broken:
+    def choose_move(self, board, score, round, roll):
+        raise RuntimeError("broken strategy")
+
+
+def test_game_play():
+    game = gl.Game(seed=1)
+    assert game.play("Highest Value", "Highest Probability") in (0, 1, None)
+
+    outcome = game.play_outcome(gl.Strategy.from_name("Highest Value"), "Highest Probability")
+    assert set(outcome) == {"winner", "scores", "num_rounds"}
+    assert len(outcome["scores"]) == 2
+
+
+def test_python_strategy_in_simulate():
+    reports = gl.simulate(["Highest Value", Greedy()], num_games=50, simulator="multithreaded", seed=3)
+    assert len(reports) == 1
+    report = reports[0]
+    assert report["games"] == 50
+    assert [s["name"] for s in report["strategies"]] == ["Highest Value", "Greedy"]
+
+
+def test_python_strategy_error_is_raised():
+    with pytest.raises(RuntimeError, match="broken strategy"):
+        gl.simulate(["Highest Value", Broken()], num_games=10, simulator="multithreaded")
+    with pytest.raises(RuntimeError, match="broken strategy"):
+        gl.Game().play(Broken(), "Highest Value")
@@ -0,0 +1,406 @@
+// File: src/cfr.rs
+
+use crate::{GameMode, GameState, Strategy};
+use crate::game::GameRules;
+use crate::rng::{game_seed, Xoroshiro128Plus};
+use crate::solver::{MixedPolicy, Model, Position, Solution, MAX_ROLL, MIN_ROLL};
+use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+#[cfg(feature = "serde")]
+use std::fs::File;
+#[cfg(feature = "serde")]
+use std::io::{self, BufReader, BufWriter, Write};
+#[cfg(feature = "serde")]
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+const NUM_ROLLS: usize = (MAX_ROLL - MIN_ROLL + 1) as usize;
+
+// CFR+ floors the cumulative regrets at zero and weights iteration t by t in the average.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "kebab-case"))]
+pub enum Variant {
+    Cfr,
+    CfrPlus,
+}
+
+impl FromStr for Variant {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().replace('_', "-").as_str() {
+            "cfr" => Ok(Variant::Cfr),
+            "cfr+" | "cfr-plus" | "cfrplus" => Ok(Variant::CfrPlus),
+            other => Err(format!("unknown variant '{}' (expected cfr or cfr-plus)", other)),
+        }
+    }
+}
+
+// Exploitability is measured after every `report_every` iterations and after the last one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CfrConfig {
+    pub rules: GameRules,
+    pub variant: Variant,
+    pub iterations: usize,
+    pub report_every: usize,
+}
+
+impl CfrConfig {
+    pub fn new(variant: Variant) -> Self {
+        CfrConfig { rules: GameRules::default(), variant, iterations: 100, report_every: 10 }
+    }
+}
+
+// Move weights for every extended-mode decision point, in the order of `Model::moves`, read as
+// probabilities once normalised. A point without weight is played uniformly.
+#[derive(Clone)]
+struct Weights {
+    offsets: Vec<usize>,
+    values: Vec<f64>,
+}
+
+impl Weights {
+    fn new(model: &Model) -> Self {
+        let mut offsets = vec![0];
+        for stage in 0..2 {
+            for board in 0..=model.full_board {
+                for roll in MIN_ROLL..=MAX_ROLL {
+                    let count = model.moves(Position { board, stage }, roll).len();
+                    offsets.push(offsets.last().unwrap() + count);
+                }
+            }
+        }
+        let values = vec![0.0; *offsets.last().unwrap()];
+        Weights { offsets, values }
+    }
+
+    fn range(&self, model: &Model, position: Position, roll: u8) -> std::ops::Range<usize> {
+        let key = (position.stage as usize * (model.full_board as usize + 1) + position.board as usize) * NUM_ROLLS + (roll - MIN_ROLL) as usize;
+        self.offsets[key]..self.offsets[key + 1]
+    }
+
+    fn probabilities(&self, model: &Model, position: Position, roll: u8) -> Vec<(u16, f64)> {
+        let moves = model.moves(position, roll);
+        let weights = &self.values[self.range(model, position, roll)];
+        let total: f64 = weights.iter().map(|w| w.max(0.0)).sum();
+        if total > 0.0 {
+            moves.iter().zip(weights).map(|(&m, w)| (m, w.max(0.0) / total)).collect()
+        } else {
+            moves.iter().map(|&m| (m, 1.0 / moves.len() as f64)).collect()
+        }
+    }
+}
+
+// A mixed strategy for both seats of the extended game, usually the average strategy of a
+// `CfrTrainer`. Stage 0 is the clearing seat and stage 1 the setting one.
+#[derive(Clone)]
+pub struct CfrPolicy {
+    pub rules: GameRules,
+    pub iterations: usize,
+    model: Model,
+    weights: Weights,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct SavedEntry {
+    position: Position,
+    roll: u8,
+    moves: Vec<u16>,
+    probabilities: Vec<f64>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct SavedPolicy {
+    rules: GameRules,
+    iterations: usize,
+    entries: Vec<SavedEntry>,
+}
+
+impl CfrPolicy {
+    pub fn uniform(rules: GameRules) -> Self {
+        let model = Model::new(GameMode::Extended, rules);
+        CfrPolicy { rules, iterations: 0, weights: Weights::new(&model), model }
+    }
+
+    pub fn model(&self) -> &Model {
+        &self.model
+    }
+
+    pub fn probabilities(&self, position: Position, roll: u8) -> Vec<(u16, f64)> {
+        self.weights.probabilities(&self.model, position, roll)
+    }
+
+    pub fn sample(&self, rng: &mut impl Rng, position: Position, roll: u8) -> u16 {
+        let probabilities = self.probabilities(position, roll);
+        let mut x = rng.gen::<f64>();
+        for &(m, p) in &probabilities {
+            if x < p {
+                return m;
+            }
+            x -= p;
+        }
+        probabilities.last().map_or(0, |&(m, _)| m)
+    }
+
+    // Values of this policy in seat 0 and in seat 1 against an opponent that best-responds to
+    // it, each from the policy's point of view. The two seats are solved on separate threads.
+    pub fn seat_values(&self) -> [f64; 2] {
+        let policy = |position, roll| self.probabilities(position, roll);
+        let evaluate = |profile: [Option<MixedPolicy<'_>>; 2]| Solution::evaluate_profile(self.model.clone(), profile).start_value();
+        let (first, second) = std::thread::scope(|scope| {
+            let first = scope.spawn(|| evaluate([Some(&policy), None]));
+            let second = evaluate([None, Some(&policy)]);
+            (first.join().unwrap(), second)
+        });
+        [first, -second]
+    }
+
+    // Decision points where only passing is legal are left out.
+    #[cfg(feature = "serde")]
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut entries = Vec::new();
+        for stage in 0..2 {
+            for board in 0..=self.model.full_board {
+                for roll in MIN_ROLL..=MAX_ROLL {
+                    let position = Position { board, stage };
+                    let (moves, probabilities) = self.probabilities(position, roll).into_iter().unzip::<_, _, Vec<_>, Vec<_>>();
+                    if moves.len() > 1 {
+                        entries.push(SavedEntry { position, roll, moves, probabilities });
+                    }
+                }
+            }
+        }
+        let saved = SavedPolicy { rules: self.rules, iterations: self.iterations, entries };
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut writer, &saved)?;
+        writer.flush()
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let saved: SavedPolicy = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+        let mut policy = CfrPolicy::uniform(saved.rules);
+        policy.iterations = saved.iterations;
+        for entry in saved.entries {
+            if !(MIN_ROLL..=MAX_ROLL).contains(&entry.roll)
+                || entry.position.stage > 1
+                || entry.position.board > policy.model.full_board
+                || policy.model.moves(entry.position, entry.roll) != entry.moves.as_slice()
+                || entry.probabilities.len() != entry.moves.len() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{}: entry for board {:#06x} and roll {} does not match the rules", path.display(), entry.position.board, entry.roll)));
+            }
+            let range = policy.weights.range(&policy.model, entry.position, entry.roll);
+            policy.weights.values[range].copy_from_slice(&entry.probabilities);
+        }
+        Ok(policy)
+    }
+}
+
+// Counterfactual regret minimisation over the extended game, whose decision points are the
+// (position, roll) pairs since both players see everything. Every iteration evaluates the
+// current profile exactly with the solver and updates the regrets of every decision point by
+// how much better each move would have done than the profile's mix. With perfect information
+// the opponent's reach only scales a point's regrets from one iteration to the next, and the
+// game can revisit positions, so the regrets and the average are left unweighted by reach.
+pub struct CfrTrainer {
+    pub config: CfrConfig,
+    pub iterations: usize,
+    model: Model,
+    regrets: Weights,
+    average: Weights,
+    values: Option<Solution>,
+}
+
+// The average strategy's exploitability after `iteration` iterations: the mean of what a best
+// response gains against it in each seat over the game value, so 0 at an equilibrium.
+// `seat_values` are as in `CfrPolicy::seat_values` and `exact_value` is seat 0's equilibrium value.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CfrReport {
+    pub variant: Variant,
+    pub iteration: usize,
+    pub seat_values: [f64; 2],
+    pub exact_value: f64,
+    pub exploitability: f64,
+}
+
+impl CfrTrainer {
+    pub fn new(config: CfrConfig) -> Self {
+        let model = Model::new(GameMode::Extended, config.rules);
+        let regrets = Weights::new(&model);
+        CfrTrainer { config, iterations: 0, average: regrets.clone(), regrets, model, values: None }
+    }
+
+    // Runs `config.iterations` iterations, calling `report` with a report every
+    // `config.report_every` of them and after the last.
+    pub fn train(&mut self, solution: &Solution, mut report: impl FnMut(CfrReport)) {
+        for i in 1..=self.config.iterations {
+            self.iterate();
+            if i == self.config.iterations || (self.config.report_every > 0 && i % self.config.report_every == 0) {
+                report(self.report(solution));
+            }
+        }
+    }
+
+    pub fn iterate(&mut self) {
+        self.iterations += 1;
+        let current = self.regrets.clone();
+        let policy = |position, roll| current.probabilities(&self.model, position, roll);
+        let values = match self.values.take() {
+            Some(values) => values.reevaluate([Some(&policy), Some(&policy)]),
+            None => Solution::evaluate_profile(self.model.clone(), [Some(&policy), Some(&policy)]),
+        };
+
+        let weight = match self.config.variant {
+            Variant::Cfr => 1.0,
+            Variant::CfrPlus => self.iterations as f64,
+        };
+        for stage in 0..2 {
+            for board in 1..=self.model.full_board {
+                for roll in MIN_ROLL..=MAX_ROLL {
+                    let position = Position { board, stage };
+                    let range = self.regrets.range(&self.model, position, roll);
+                    if range.len() < 2 {
+                        continue;
+                    }
+                    let probabilities = current.probabilities(&self.model, position, roll);
+                    let action_values: Vec<f64> = probabilities.iter().map(|&(m, _)| values.move_value(position, m)).collect();
+                    let expected: f64 = probabilities.iter().zip(&action_values).map(|(&(_, p), v)| p * v).sum();
+                    for (i, index) in range.enumerate() {
+                        self.average.values[index] += weight * probabilities[i].1;
+                        let regret = &mut self.regrets.values[index];
+                        *regret += action_values[i] - expected;
+                        if self.config.variant == Variant::CfrPlus {
+                            *regret = regret.max(0.0);
+                        }
+                    }
+                }
+            }
+        }
+        self.values = Some(values);
+    }
+
+    pub fn average_policy(&self) -> CfrPolicy {
+        CfrPolicy { rules: self.config.rules, iterations: self.iterations, model: self.model.clone(), weights: self.average.clone() }
+    }
+
+    // `solution` is the exact solution of the same game.
+    pub fn report(&self, solution: &Solution) -> CfrReport {
+        let seat_values = self.average_policy().seat_values();
+        let exact_value = solution.start_value();
+        CfrReport {
+            variant: self.config.variant,
+            iteration: self.iterations,
+            seat_values,
+            exact_value,
+            exploitability: -(seat_values[0] + seat_values[1]) / 2.0,
+        }
+    }
+}
+
+// Samples moves from a policy for one seat of the extended game: 0 clears tiles and 1 sets
+// them. As with `LearnedStrategy` the engine sets the seat at the start of each game, along
+// with the generator the moves are sampled from.
+#[derive(Clone)]
+pub struct CfrStrategy {
+    pub policy: Arc<CfrPolicy>,
+    pub seat: usize,
+    rng: Xoroshiro128Plus,
+}
+
+impl CfrStrategy {
+    pub fn new(policy: Arc<CfrPolicy>, seat: usize) -> Self {
+        CfrStrategy { policy, seat, rng: Xoroshiro128Plus::new(game_seed(0, seat as u64)) }
+    }
+}
+
+impl Strategy for CfrStrategy {
+    fn choose_move(&mut self, state: &GameState, roll: u8) -> u16 {
+        if !(MIN_ROLL..=MAX_ROLL).contains(&roll) {
+            return 0;
+        }
+        let position = Position { board: state.board & self.policy.model.full_board, stage: self.seat.min(1) as u8 };
+        self.policy.sample(&mut self.rng, position, roll)
+    }
+
+    fn name(&self) -> &'static str {
+        "CFR"
+    }
+
+    fn on_game_start(&mut self, seat: usize, rng: Xoroshiro128Plus) {
+        self.seat = seat;
+        self.rng = rng;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: GameRules = GameRules { board_size: 4, rounds: 5, dice: None };
+
+    fn trained(iterations: usize) -> CfrTrainer {
+        let mut trainer = CfrTrainer::new(CfrConfig { rules: RULES, iterations, ..CfrConfig::new(Variant::CfrPlus) });
+        let solution = Solution::solve(GameMode::Extended, RULES);
+        trainer.train(&solution, |_| {});
+        trainer
+    }
+
+    #[test]
+    fn cfr_plus_exploitability_falls() {
+        let solution = Solution::solve(GameMode::Extended, RULES);
+        let mut trainer = CfrTrainer::new(CfrConfig { rules: RULES, iterations: 50, report_every: 10, ..CfrConfig::new(Variant::CfrPlus) });
+        let mut reports = Vec::new();
+        trainer.iterate();
+        reports.push(trainer.report(&solution));
+        trainer.train(&solution, |report| reports.push(report));
+        let exploitability: Vec<f64> = reports.iter().map(|r| r.exploitability).collect();
+        assert!(exploitability.iter().all(|&e| e > -1e-9));
+        assert!(exploitability.windows(2).all(|pair| pair[1] < pair[0]), "{:?}", exploitability);
+        assert!(exploitability[exploitability.len() - 1] < 0.02);
+    }
+
+    #[test]
+    fn probabilities_sum_to_one() {
+        for policy in [CfrPolicy::uniform(RULES), trained(5).average_policy()] {
+            for stage in 0..2 {
+                for board in 0..=policy.model.full_board {
+                    for roll in MIN_ROLL..=MAX_ROLL {
+                        let probabilities = policy.probabilities(Position { board, stage }, roll);
+                        let total: f64 = probabilities.iter().map(|&(_, p)| p).sum();
+                        assert!((total - 1.0).abs() < 1e-9);
+                        assert!(probabilities.iter().all(|&(_, p)| p >= 0.0));
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn policies_round_trip_through_a_file() {
+        let policy = trained(5).average_policy();
+        let path = std::env::temp_dir().join(format!("dice_game_cfr_policy_{}.json", std::process::id()));
+        policy.save(&path).unwrap();
+        let loaded = CfrPolicy::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let loaded = loaded.unwrap();
+        assert_eq!((loaded.rules, loaded.iterations), (policy.rules, policy.iterations));
+        for stage in 0..2 {
+            for board in 0..=policy.model.full_board {
+                for roll in MIN_ROLL..=MAX_ROLL {
+                    let position = Position { board, stage };
+                    let (saved, read) = (policy.probabilities(position, roll), loaded.probabilities(position, roll));
+                    assert_eq!(saved.len(), read.len());
+                    assert!(saved.iter().zip(&read).all(|(a, b)| a.0 == b.0 && (a.1 - b.1).abs() < 1e-12));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+// File: src/checkpoint.rs
+
+use crate::simulation::SimulationStats;
+use crate::tournament::MatchResult;
+use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+// Where a long run keeps its progress and how many games it plays between saves.
+#[derive(Clone, Debug)]
+pub struct Checkpoint {
+    pub path: PathBuf,
+    pub interval: usize,
+}
+
+impl Checkpoint {
+    pub fn new<P: Into<PathBuf>>(path: P, interval: usize) -> Self {
+        Checkpoint { path: path.into(), interval: interval.max(1) }
+    }
+
+    // None when no checkpoint has been written yet.
+    pub fn load<T: DeserializeOwned>(&self) -> io::Result<Option<T>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        Ok(Some(serde_json::from_reader(BufReader::new(file))?))
+    }
+
+    // Writes to a temporary file first, so a crash while saving leaves the previous checkpoint intact.
+    pub fn save<T: Serialize>(&self, state: &T) -> io::Result<()> {
+        let temp = temp_path(&self.path);
+        {
+            let mut writer = BufWriter::new(File::create(&temp)?);
+            serde_json::to_writer(&mut writer, state)?;
+            writer.flush()?;
+        }
+        fs::rename(&temp, &self.path)
+    }
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+fn check_config(saved: &crate::SimulationConfig, config: &crate::SimulationConfig) -> io::Result<()> {
+    if saved == config {
+        Ok(())
+    } else {
+        Err(io::Error::new(io::ErrorKind::InvalidData, "checkpoint was written for a different configuration"))
+    }
+}
+
+// Counts for games `config.first_game..next_game` of a simulator run.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SimulationCheckpoint {
+    pub config: crate::SimulationConfig,
+    pub next_game: usize,
+    pub stats: SimulationStats,
+}
+
+impl SimulationCheckpoint {
+    pub fn new(config: crate::SimulationConfig, first_game: usize, stats: SimulationStats) -> Self {
+        SimulationCheckpoint { config, next_game: first_game, stats }
+    }
+
+    pub fn check(&self, config: &crate::SimulationConfig) -> io::Result<()> {
+        check_config(&self.config, config)
+    }
+}
+
+// A round-robin match that was interrupted after `next_game` games.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MatchProgress {
+    pub result: MatchResult,
+    pub next_game: usize,
+}
+
+// Finished pairings, in pairing order, plus the one in progress. `config.num_games` is the
+// number of games per pairing.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TournamentCheckpoint {
+    pub config: crate::SimulationConfig,
+    pub completed: Vec<MatchResult>,
+    pub current: Option<MatchProgress>,
+}
+
+impl TournamentCheckpoint {
+    pub fn new(config: crate::SimulationConfig) -> Self {
+        TournamentCheckpoint { config, completed: Vec::new(), current: None }
+    }
+
+    pub fn check(&self, config: &crate::SimulationConfig) -> io::Result<()> {
+        check_config(&self.config, config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::SimulationControl;
+    use crate::game::GameRules;
+    use crate::simulation::{run_checkpointed, SimulationConfig, SimulatorType};
+    use crate::strategies::{create_strategy, HighestValueStrategy, RandomStrategy};
+    use crate::tournament::run_simulation_checkpointed;
+    use crate::{GameMode, OptimizationLevel, Strategy};
+    use std::sync::Arc;
+
+    fn temp_checkpoint(name: &str, interval: usize) -> Checkpoint {
+        let path = std::env::temp_dir().join(format!("dice_game_{}_{}.json", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        Checkpoint::new(path, interval)
+    }
+
+    fn config(num_games: usize) -> SimulationConfig {
+        SimulationConfig {
+            mode: GameMode::Base,
+            optimization: OptimizationLevel::Standard,
+            rules: Default::default(),
+            strategies: vec![Arc::new(RandomStrategy::default()), Arc::new(HighestValueStrategy)],
+            num_games,
+            first_game: 0,
+            seed: 42,
+            simulator_type: SimulatorType::Multithreaded,
+        }
+    }
+
+    #[test]
+    fn resumed_simulation_matches_an_uninterrupted_one() {
+        let config = config(2_000);
+        let uninterrupted = temp_checkpoint("uninterrupted", 300);
+        run_checkpointed(&config, &SimulationControl::new(), &uninterrupted).unwrap();
+        let expected = uninterrupted.load::<SimulationCheckpoint>().unwrap().unwrap();
+
+        let resumed = temp_checkpoint("resumed", 300);
+        run_checkpointed(&config, &SimulationControl::new().with_max_games(700), &resumed).unwrap();
+        let partial = resumed.load::<SimulationCheckpoint>().unwrap().unwrap();
+        assert!(partial.next_game < config.num_games);
+        run_checkpointed(&config, &SimulationControl::new(), &resumed).unwrap();
+        let finished = resumed.load::<SimulationCheckpoint>().unwrap().unwrap();
+
+        assert_eq!(finished.next_game, config.num_games);
+        assert_eq!(serde_json::to_value(&finished.stats).unwrap(), serde_json::to_value(&expected.stats).unwrap());
+        let _ = fs::remove_file(&uninterrupted.path);
+        let _ = fs::remove_file(&resumed.path);
+    }
+
+    #[test]
+    fn checkpoint_for_another_config_is_rejected() {
+        let checkpoint = temp_checkpoint("other_config", 100);
+        run_checkpointed(&config(200), &SimulationControl::new(), &checkpoint).unwrap();
+        assert!(run_checkpointed(&config(300), &SimulationControl::new(), &checkpoint).is_err());
+        let _ = fs::remove_file(&checkpoint.path);
+    }
+
+    #[test]
+    fn resumed_tournament_matches_an_uninterrupted_one() {
+        let strategies: Vec<Box<dyn Strategy>> = ["Random", "Highest Value", "Balanced Value"]
+            .iter()
+            .map(|name| create_strategy(name).unwrap())
+            .collect();
+        let run = |checkpoint: &Checkpoint| {
+            run_simulation_checkpointed(GameMode::Base, OptimizationLevel::Standard, GameRules::default(), &strategies, 300, 9, checkpoint).unwrap()
+        };
+
+        let uninterrupted = temp_checkpoint("tournament_uninterrupted", 100);
+        let expected = run(&uninterrupted);
+
+        // Rewind the finished checkpoint to the start of the second pairing.
+        let resumed = temp_checkpoint("tournament_resumed", 100);
+        let mut state = uninterrupted.load::<TournamentCheckpoint>().unwrap().unwrap();
+        let second = state.completed[1].clone();
+        state.completed.truncate(1);
+        state.current = Some(MatchProgress { result: MatchResult { wins1: 0, wins2: 0, draws: 0, ..second }, next_game: 0 });
+        resumed.save(&state).unwrap();
+
+        assert_eq!(run(&resumed), expected);
+        let _ = fs::remove_file(&uninterrupted.path);
+        let _ = fs::remove_file(&resumed.path);
+    }
+}
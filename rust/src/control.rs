@@ -0,0 +1,223 @@
+// File: src/control.rs
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+// Games a worker plays between checks of the control handle.
+pub const CHECK_BATCH: usize = 256;
+
+const NOT_STOPPED: usize = 0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum StopReason {
+    Cancelled,
+    TimeLimit,
+    GameLimit,
+}
+
+impl StopReason {
+    fn code(self) -> usize {
+        match self {
+            StopReason::Cancelled => 1,
+            StopReason::TimeLimit => 2,
+            StopReason::GameLimit => 3,
+        }
+    }
+
+    fn from_code(code: usize) -> Option<Self> {
+        match code {
+            1 => Some(StopReason::Cancelled),
+            2 => Some(StopReason::TimeLimit),
+            3 => Some(StopReason::GameLimit),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Progress {
+    pub games_done: usize,
+    pub total_games: usize,
+    pub elapsed: Duration,
+    pub games_per_second: f64,
+    pub eta: Option<Duration>,
+}
+
+type ProgressCallback = Box<dyn Fn(&Progress) + Send + Sync>;
+
+// Shared between the caller and the simulator's workers. Keep it in an `Arc` to cancel a run
+// from another thread; workers poll it every `CHECK_BATCH` games and return what they have so far.
+pub struct SimulationControl {
+    cancelled: AtomicBool,
+    in_session: AtomicBool,
+    stop_reason: AtomicUsize,
+    games_done: AtomicUsize,
+    total_games: AtomicUsize,
+    started: Mutex<Option<Instant>>,
+    last_report_ms: AtomicU64,
+    max_games: Option<usize>,
+    time_limit: Option<Duration>,
+    report_interval: Duration,
+    on_progress: Option<ProgressCallback>,
+}
+
+impl Default for SimulationControl {
+    fn default() -> Self {
+        SimulationControl {
+            cancelled: AtomicBool::new(false),
+            in_session: AtomicBool::new(false),
+            stop_reason: AtomicUsize::new(NOT_STOPPED),
+            games_done: AtomicUsize::new(0),
+            total_games: AtomicUsize::new(0),
+            started: Mutex::new(None),
+            last_report_ms: AtomicU64::new(0),
+            max_games: None,
+            time_limit: None,
+            report_interval: Duration::from_secs(1),
+            on_progress: None,
+        }
+    }
+}
+
+impl SimulationControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_games(mut self, max_games: usize) -> Self {
+        self.max_games = Some(max_games);
+        self
+    }
+
+    pub fn with_time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    pub fn with_report_interval(mut self, interval: Duration) -> Self {
+        self.report_interval = interval;
+        self
+    }
+
+    pub fn on_progress<F: Fn(&Progress) + Send + Sync + 'static>(mut self, callback: F) -> Self {
+        self.on_progress = Some(Box::new(callback));
+        self
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn games_done(&self) -> usize {
+        self.games_done.load(Ordering::Relaxed)
+    }
+
+    pub fn stop_reason(&self) -> Option<StopReason> {
+        StopReason::from_code(self.stop_reason.load(Ordering::Relaxed))
+    }
+
+    // Called by a simulator before it hands out work. Returns how many games to play,
+    // which is `requested` capped by the game limit. Inside a session the clock, counters
+    // and limit are left alone and `requested` is returned unchanged.
+    pub fn start(&self, requested: usize) -> usize {
+        if self.in_session.load(Ordering::Relaxed) {
+            return requested;
+        }
+        self.stop_reason.store(NOT_STOPPED, Ordering::Relaxed);
+        let total = match self.max_games {
+            Some(max_games) if max_games < requested => {
+                self.set_stop_reason(StopReason::GameLimit);
+                max_games
+            },
+            _ => requested,
+        };
+        self.games_done.store(0, Ordering::Relaxed);
+        self.total_games.store(total, Ordering::Relaxed);
+        self.last_report_ms.store(0, Ordering::Relaxed);
+        *self.started.lock().unwrap() = Some(Instant::now());
+        total
+    }
+
+    // For runs split over several `simulate` calls (see `simulation::run_checkpointed`): progress,
+    // the time limit and the game limit then cover the whole session rather than each call.
+    pub fn start_session(&self, requested: usize) -> usize {
+        self.in_session.store(false, Ordering::Relaxed);
+        let total = self.start(requested);
+        self.in_session.store(true, Ordering::Relaxed);
+        total
+    }
+
+    pub fn end_session(&self) {
+        self.in_session.store(false, Ordering::Relaxed);
+    }
+
+    pub fn should_stop(&self) -> bool {
+        if self.cancelled.load(Ordering::Relaxed) {
+            self.set_stop_reason(StopReason::Cancelled);
+            return true;
+        }
+        if let Some(limit) = self.time_limit {
+            if self.elapsed() >= limit {
+                self.set_stop_reason(StopReason::TimeLimit);
+                return true;
+            }
+        }
+        false
+    }
+
+    // Workers report finished games in batches; the callback fires at most once per `report_interval`.
+    pub fn add_games(&self, games: usize) {
+        self.games_done.fetch_add(games, Ordering::Relaxed);
+        if self.on_progress.is_none() {
+            return;
+        }
+
+        let now_ms = self.elapsed().as_millis() as u64;
+        let last_ms = self.last_report_ms.load(Ordering::Relaxed);
+        if now_ms >= last_ms + self.report_interval.as_millis() as u64
+            && self.last_report_ms.compare_exchange(last_ms, now_ms, Ordering::Relaxed, Ordering::Relaxed).is_ok()
+        {
+            self.report();
+        }
+    }
+
+    // Final progress report, called once the simulator returns.
+    pub fn finish(&self) {
+        if self.on_progress.is_some() {
+            self.report();
+        }
+    }
+
+    pub fn progress(&self) -> Progress {
+        let games_done = self.games_done();
+        let total_games = self.total_games.load(Ordering::Relaxed);
+        let elapsed = self.elapsed();
+        let seconds = elapsed.as_secs_f64();
+        let games_per_second = if seconds > 0.0 { games_done as f64 / seconds } else { 0.0 };
+        let eta = if games_per_second > 0.0 {
+            Some(Duration::from_secs_f64(total_games.saturating_sub(games_done) as f64 / games_per_second))
+        } else {
+            None
+        };
+        Progress { games_done, total_games, elapsed, games_per_second, eta }
+    }
+
+    fn report(&self) {
+        if let Some(callback) = &self.on_progress {
+            callback(&self.progress());
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.started.lock().unwrap().map_or(Duration::ZERO, |start| start.elapsed())
+    }
+
+    // The first reason to stop a run is the one reported.
+    fn set_stop_reason(&self, reason: StopReason) {
+        let _ = self.stop_reason.compare_exchange(NOT_STOPPED, reason.code(), Ordering::Relaxed, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,396 @@
+// File: src/env.rs
+
+use crate::{GameMode, GameState, OptimizationLevel, Strategy};
+use crate::game::{legal_moves, Game, GameRules, Play, Seats, TurnObserver};
+use crate::rng::{game_seed, Xoroshiro128Plus};
+
+// Rewards paid to the agent. `per_point` is added for every point the agent scores when one of
+// its base-mode turns ends (one per tile left open), so a negative value rewards shutting tiles
+// round by round; the others are paid once, on the step that ends the game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RewardShaping {
+    pub per_point: f64,
+    pub win: f64,
+    pub loss: f64,
+    pub draw: f64,
+}
+
+impl Default for RewardShaping {
+    fn default() -> Self {
+        RewardShaping { per_point: 0.0, win: 1.0, loss: -1.0, draw: 0.0 }
+    }
+}
+
+// The agent plays `seat` (0 moves first; in extended mode seat 0 clears tiles and seat 1 sets
+// them) against a fixed opponent strategy.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EnvConfig {
+    pub mode: GameMode,
+    pub optimization: OptimizationLevel,
+    pub rules: GameRules,
+    pub reward: RewardShaping,
+    pub seat: usize,
+}
+
+impl EnvConfig {
+    pub fn new(mode: GameMode, optimization: OptimizationLevel) -> Self {
+        EnvConfig { mode, optimization, rules: GameRules::default(), reward: RewardShaping::default(), seat: 0 }
+    }
+}
+
+// What the agent sees before each move. `legal_moves` is empty once the game is over.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Observation {
+    pub board: u16,
+    pub roll: u8,
+    pub round: u8,
+    pub scores: [u32; 2],
+    pub seat: usize,
+    pub legal_moves: Vec<u16>,
+}
+
+impl Observation {
+    // One flag per action `0..num_actions`, where action `a` closes (or, for the setting side,
+    // opens) the tiles in bitmask `a`.
+    pub fn action_mask(&self, num_actions: usize) -> Vec<bool> {
+        let mut mask = vec![false; num_actions];
+        for &action in &self.legal_moves {
+            mask[action as usize] = true;
+        }
+        mask
+    }
+}
+
+// `winner` is None until the game ends, and stays None for a drawn base game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StepInfo {
+    pub winner: Option<usize>,
+    pub scores: [u32; 2],
+    pub num_rounds: u8,
+}
+
+pub type Step = (Observation, f64, bool, StepInfo);
+
+// A two-player game the agent plays one move at a time. The opponent's moves are made inside
+// `reset` and `step`, and the dice are rolled in the same order as `Game::play_pair`, so an agent
+// making the same moves as a strategy replays its game. Moves are made by the engine's own
+// `Game::apply_move`, which calls the opponent's hooks as a game would.
+pub struct Env {
+    game: Game,
+    config: EnvConfig,
+    opponent: Box<dyn Strategy>,
+    play: Play,
+    scores: [u32; 2],
+    roll: u8,
+}
+
+// The opponent in its seat, the agent's seat being empty.
+struct Opponent<'a> {
+    strategy: &'a mut dyn Strategy,
+    seat: usize,
+}
+
+impl Seats for Opponent<'_> {
+    fn count(&self) -> usize {
+        2
+    }
+
+    fn choose_move(&mut self, _seat: usize, state: &GameState, roll: u8) -> u16 {
+        self.strategy.choose_move(state, roll)
+    }
+
+    fn on_game_start(&mut self, seat: usize, rng: Xoroshiro128Plus) {
+        if seat == self.seat {
+            self.strategy.on_game_start(seat, rng);
+        }
+    }
+
+    fn on_opponent_move(&mut self, seat: usize, state: &GameState, roll: u8, move_bits: u16) {
+        if seat == self.seat {
+            self.strategy.on_opponent_move(state, roll, move_bits);
+        }
+    }
+
+    fn on_turn_end(&mut self, seat: usize, player: usize, points: Option<u32>) {
+        if seat == self.seat {
+            self.strategy.on_turn_end(player, points);
+        }
+    }
+
+    fn on_game_end(&mut self, seat: usize, winner: usize, scores: &[u32]) {
+        if seat == self.seat {
+            self.strategy.on_game_end(winner, scores);
+        }
+    }
+}
+
+// Collects the `per_point` reward for the agent's finished turns.
+struct TurnRewards {
+    seat: usize,
+    per_point: f64,
+    reward: f64,
+}
+
+impl TurnObserver for TurnRewards {
+    fn end_turn(&mut self, player: usize, points: Option<u32>, _moves: u32) {
+        if player == self.seat {
+            self.reward += self.per_point * points.unwrap_or(0) as f64;
+        }
+    }
+}
+
+impl Env {
+    pub fn new(config: EnvConfig, opponent: Box<dyn Strategy>, seed: u64) -> Result<Self, String> {
+        if config.seat > 1 {
+            return Err(format!("seat must be 0 or 1, got {}", config.seat));
+        }
+        let game = Game::from_rules(config.mode, config.optimization, config.rules, seed);
+        let mut play = game.new_play();
+        play.done = true;
+        Ok(Env { game, config, opponent, play, scores: [0; 2], roll: 0 })
+    }
+
+    pub fn config(&self) -> &EnvConfig {
+        &self.config
+    }
+
+    // Size of the action space: every bitmask of the board's tiles.
+    pub fn num_actions(&self) -> usize {
+        self.game.full_board() as usize + 1
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.play.done
+    }
+
+    // Starts a new game. If the opponent finishes it before the agent's first move, the returned
+    // observation has no legal moves and `is_done` is true.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.game.reset(seed);
+        self.scores = [0; 2];
+        let mut seats = Opponent { strategy: self.opponent.as_mut(), seat: 1 - self.config.seat };
+        self.play = self.game.start_game(&mut seats);
+        // Rewards can only come from the agent's own turns or the end of the game, and a game
+        // the opponent finishes before the agent moves has nothing to reward.
+        self.advance(&mut 0.0);
+        self.observation()
+    }
+
+    pub fn legal_moves(&self) -> Vec<u16> {
+        if self.play.done {
+            return Vec::new();
+        }
+        legal_moves(self.view().movable, self.roll)
+    }
+
+    pub fn step(&mut self, action: u16) -> Result<Step, String> {
+        if self.play.done {
+            return Err("the game is over; call reset to start the next one".to_string());
+        }
+        if !self.legal_moves().contains(&action) {
+            return Err(format!("illegal move {:#06x} for board {:#06x} and roll {}", action, self.play.state.board, self.roll));
+        }
+
+        let mut reward = 0.0;
+        self.opponent.on_opponent_move(&self.view(), self.roll, action);
+        self.apply(action, &mut reward);
+        self.advance(&mut reward);
+        let info = self.info();
+        if self.play.done {
+            reward += match info.winner {
+                Some(winner) if winner == self.config.seat => self.config.reward.win,
+                Some(_) => self.config.reward.loss,
+                None => self.config.reward.draw,
+            };
+        }
+        Ok((self.observation(), reward, self.play.done, info))
+    }
+
+    pub fn observation(&self) -> Observation {
+        Observation {
+            board: self.play.state.board,
+            roll: if self.play.done { 0 } else { self.roll },
+            round: self.play.state.round,
+            scores: self.scores,
+            seat: self.config.seat,
+            legal_moves: self.legal_moves(),
+        }
+    }
+
+    fn info(&self) -> StepInfo {
+        let winner = match (self.play.done, self.config.mode) {
+            (false, _) => None,
+            (true, GameMode::Base) if self.scores[0] == self.scores[1] => None,
+            (true, GameMode::Base) => Some(if self.scores[0] < self.scores[1] { 0 } else { 1 }),
+            (true, GameMode::Extended) => Some(if self.play.state.board == 0 { 0 } else { 1 }),
+        };
+        StepInfo { winner, scores: self.scores, num_rounds: self.play.state.round }
+    }
+
+    // The state as the player to move sees it.
+    fn view(&self) -> GameState<'static> {
+        GameState { score: self.scores[self.play.player], ..self.game.view(&self.play) }
+    }
+
+    // Rolls for the next move and plays the opponent's moves until it is the agent's turn or
+    // the game ends.
+    fn advance(&mut self, reward: &mut f64) {
+        while !self.play.done {
+            self.roll = self.game.roll_dice();
+            if self.play.player == self.config.seat {
+                return;
+            }
+            let action = self.opponent.choose_move(&self.view(), self.roll);
+            self.apply(action, reward);
+        }
+    }
+
+    fn apply(&mut self, move_bits: u16, reward: &mut f64) {
+        let mut seats = Opponent { strategy: self.opponent.as_mut(), seat: 1 - self.config.seat };
+        let mut rewards = TurnRewards { seat: self.config.seat, per_point: self.config.reward.per_point, reward: 0.0 };
+        self.game.apply_move(&mut self.play, move_bits, &mut self.scores, &mut seats, &mut rewards);
+        *reward += rewards.reward;
+        if self.play.done {
+            self.game.end_game(&self.play, &self.scores, &mut seats);
+        }
+    }
+}
+
+// A batch of environments stepped together. An environment whose game ends is reset straight
+// away, so its entry from `step` holds the finished game's reward, `done` and info alongside the
+// first observation of the next game. Games are seeded with `game_seed(seed, i)` for
+// consecutive `i`, and games the opponent finishes before the agent moves are skipped.
+pub struct VecEnv {
+    envs: Vec<Env>,
+    seed: u64,
+    next_game: u64,
+}
+
+impl VecEnv {
+    pub fn new(config: EnvConfig, opponent: Box<dyn Strategy>, num_envs: usize, seed: u64) -> Result<Self, String> {
+        let envs = (0..num_envs).map(|i| Env::new(config, opponent.clone(), game_seed(seed, i as u64))).collect::<Result<_, _>>()?;
+        Ok(VecEnv { envs, seed, next_game: 0 })
+    }
+
+    pub fn len(&self) -> usize {
+        self.envs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.envs.is_empty()
+    }
+
+    pub fn envs(&self) -> &[Env] {
+        &self.envs
+    }
+
+    pub fn reset(&mut self, seed: u64) -> Vec<Observation> {
+        self.seed = seed;
+        self.next_game = 0;
+        (0..self.envs.len()).map(|i| self.start(i)).collect()
+    }
+
+    fn start(&mut self, i: usize) -> Observation {
+        loop {
+            let observation = self.envs[i].reset(game_seed(self.seed, self.next_game));
+            self.next_game += 1;
+            if !self.envs[i].is_done() {
+                return observation;
+            }
+        }
+    }
+
+    // Takes one action per environment. Every action is checked before any environment moves.
+    pub fn step(&mut self, actions: &[u16]) -> Result<Vec<Step>, String> {
+        if actions.len() != self.envs.len() {
+            return Err(format!("expected {} actions, got {}", self.envs.len(), actions.len()));
+        }
+        if let Some(i) = self.envs.iter().zip(actions).position(|(env, action)| env.is_done() || !env.legal_moves().contains(action)) {
+            return Err(match self.envs[i].is_done() {
+                true => format!("environment {} has no game in progress; call reset", i),
+                false => format!("illegal move {:#06x} for environment {}", actions[i], i),
+            });
+        }
+
+        let mut steps = Vec::with_capacity(self.envs.len());
+        for (i, &action) in actions.iter().enumerate() {
+            let (mut observation, reward, done, info) = self.envs[i].step(action)?;
+            if done {
+                observation = self.start(i);
+            }
+            steps.push((observation, reward, done, info));
+        }
+        Ok(steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::empty_history;
+    use crate::strategies::HighestValueStrategy;
+
+    // Plays one game in `env`, choosing the agent's moves with Highest Value.
+    fn play_out(env: &mut Env, seed: u64) -> StepInfo {
+        let mut agent = HighestValueStrategy;
+        let mut observation = env.reset(seed);
+        while !env.is_done() {
+            let state = GameState { board: observation.board, movable: observation.board, score: 0, round: observation.round, player: observation.seat, history: empty_history() };
+            let (next, _, done, info) = env.step(agent.choose_move(&state, observation.roll)).unwrap();
+            if done {
+                return info;
+            }
+            observation = next;
+        }
+        env.info()
+    }
+
+    #[test]
+    fn agent_replays_the_engine_game() {
+        for seat in 0..2 {
+            let config = EnvConfig { seat, ..EnvConfig::new(GameMode::Base, OptimizationLevel::Standard) };
+            let mut env = Env::new(config, Box::new(HighestValueStrategy), 3).unwrap();
+            let mut game = Game::new(GameMode::Base, OptimizationLevel::Standard, 3);
+            for seed in 0..20 {
+                let info = play_out(&mut env, seed);
+                game.reset(seed);
+                let outcome = game.play_pair([&mut HighestValueStrategy, &mut HighestValueStrategy]);
+                assert_eq!(info.scores, outcome.scores);
+                assert_eq!(info.num_rounds, outcome.num_rounds);
+                assert!(env.step(0).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn illegal_moves_are_rejected() {
+        let mut env = Env::new(EnvConfig::new(GameMode::Base, OptimizationLevel::Standard), Box::new(HighestValueStrategy), 0).unwrap();
+        let observation = env.reset(0);
+        let illegal = (1..=env.game.full_board()).find(|action| !observation.legal_moves.contains(action)).unwrap();
+        assert!(env.step(illegal).is_err());
+        assert_eq!(env.observation(), observation);
+    }
+
+    #[test]
+    fn seat_1_plays_a_one_round_game() {
+        let mut config = EnvConfig { seat: 1, ..EnvConfig::new(GameMode::Base, OptimizationLevel::Standard) };
+        config.rules.rounds = 1;
+        config.reward = RewardShaping { per_point: -1.0, win: 0.0, loss: 0.0, draw: 0.0 };
+        let mut env = Env::new(config, Box::new(HighestValueStrategy), 0).unwrap();
+        for seed in 0..20 {
+            let mut observation = env.reset(seed);
+            let mut total = 0.0;
+            assert!(!env.is_done());
+            loop {
+                let (next, reward, done, info) = env.step(observation.legal_moves[observation.legal_moves.len() - 1]).unwrap();
+                total += reward;
+                if done {
+                    assert_eq!(info.num_rounds, 2);
+                    assert_eq!(total, -(info.scores[1] as f64));
+                    break;
+                }
+                observation = next;
+            }
+        }
+    }
+}
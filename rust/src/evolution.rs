@@ -0,0 +1,302 @@
+// File: src/evolution.rs
+
+use crate::meta::PayoffMatrix;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+const EPSILON: f64 = 1e-12;
+
+// Fitness of strategy i against j is its expected match score, win rate plus half the draws.
+// With the zero-sum payoffs in `PayoffMatrix` that is (1 + a_ij) / 2.
+fn fitness_matrix(matrix: &PayoffMatrix) -> Vec<Vec<f64>> {
+    matrix.payoffs.iter()
+        .map(|row| row.iter().map(|a| (1.0 + a) / 2.0).collect())
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ReplicatorReport {
+    pub strategies: Vec<String>,
+    pub trajectory: Vec<Vec<f64>>,
+    pub final_shares: Vec<f64>,
+    pub converged_at: Option<usize>,
+    pub survivors: Vec<String>,
+    pub stable_strategies: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MoranReport {
+    pub strategies: Vec<String>,
+    pub trajectory: Vec<Vec<usize>>,
+    pub final_counts: Vec<usize>,
+    pub average_shares: Vec<f64>,
+    pub fixated: Option<String>,
+}
+
+pub struct ReplicatorDynamics {
+    pub generations: usize,
+    pub tolerance: f64,
+    pub survival_threshold: f64,
+}
+
+impl Default for ReplicatorDynamics {
+    fn default() -> Self {
+        ReplicatorDynamics { generations: 10_000, tolerance: 1e-10, survival_threshold: 1e-3 }
+    }
+}
+
+impl ReplicatorDynamics {
+    // Discrete replicator dynamics, x_i' = x_i f_i(x) / f(x), starting from `initial` shares
+    // (uniform when None). Stops early once no share moves by more than `tolerance`.
+    pub fn run(&self, matrix: &PayoffMatrix, initial: Option<&[f64]>) -> Result<ReplicatorReport, String> {
+        let n = matrix.len();
+        if n == 0 {
+            return Err("the payoff matrix has no strategies".to_string());
+        }
+        let fitness = fitness_matrix(matrix);
+        let mut shares: Vec<f64> = match initial {
+            Some(initial) if initial.len() != n => {
+                return Err(format!("expected {} initial shares, one per strategy, got {}", n, initial.len()));
+            },
+            Some(initial) => {
+                if let Some(x) = initial.iter().find(|x| !(x.is_finite() && **x >= 0.0)) {
+                    return Err(format!("initial shares must be finite and non-negative, got {}", x));
+                }
+                let total: f64 = initial.iter().sum();
+                if total <= 0.0 {
+                    return Err("the initial shares are all zero".to_string());
+                }
+                initial.iter().map(|x| x / total).collect()
+            },
+            None => vec![1.0 / n as f64; n],
+        };
+
+        let mut trajectory = vec![shares.clone()];
+        let mut converged_at = None;
+        for generation in 1..=self.generations {
+            let payoffs: Vec<f64> = (0..n)
+                .map(|i| (0..n).map(|j| fitness[i][j] * shares[j]).sum())
+                .collect();
+            let average: f64 = (0..n).map(|i| shares[i] * payoffs[i]).sum();
+            if average < EPSILON {
+                break;
+            }
+
+            let next: Vec<f64> = (0..n).map(|i| shares[i] * payoffs[i] / average).collect();
+            let change = next.iter().zip(&shares).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max);
+            shares = next;
+            trajectory.push(shares.clone());
+            if change < self.tolerance {
+                converged_at = Some(generation);
+                break;
+            }
+        }
+
+        let survivors = (0..n)
+            .filter(|&i| shares[i] > self.survival_threshold)
+            .map(|i| matrix.strategies[i].clone())
+            .collect();
+
+        Ok(ReplicatorReport {
+            strategies: matrix.strategies.clone(),
+            trajectory,
+            final_shares: shares,
+            converged_at,
+            survivors,
+            stable_strategies: evolutionary_stable_strategies(matrix),
+        })
+    }
+}
+
+pub struct MoranProcess {
+    pub population: usize,
+    pub mutation_rate: f64,
+    pub selection_intensity: f64,
+    pub generations: usize,
+    pub sample_interval: usize,
+    pub seed: u64,
+}
+
+impl MoranProcess {
+    // Finite-population birth-death process. Each step one individual is chosen to reproduce
+    // proportionally to fitness 1 - w + w * (average payoff against the rest of the population),
+    // its offspring mutates to a uniformly random strategy with probability `mutation_rate`,
+    // and replaces a uniformly chosen individual. `initial` gives one count per strategy.
+    pub fn run(&self, matrix: &PayoffMatrix, initial: Option<&[usize]>) -> Result<MoranReport, String> {
+        let n = matrix.len();
+        if n == 0 {
+            return Err("the payoff matrix has no strategies".to_string());
+        }
+        let fitness = fitness_matrix(matrix);
+        let mut rng = StdRng::seed_from_u64(self.seed);
+
+        let mut counts: Vec<usize> = match initial {
+            Some(initial) if initial.len() != n => {
+                return Err(format!("expected {} initial counts, one per strategy, got {}", n, initial.len()));
+            },
+            Some(initial) => initial.to_vec(),
+            None => (0..n).map(|i| self.population / n + usize::from(i < self.population % n)).collect(),
+        };
+        let population: usize = counts.iter().sum();
+        if population == 0 {
+            return Err("the population is empty".to_string());
+        }
+
+        let mut trajectory = vec![counts.clone()];
+        let mut share_sums = vec![0.0; n];
+        let mut steps = 0;
+
+        for step in 1..=self.generations {
+            let individual_fitness: Vec<f64> = (0..n)
+                .map(|i| {
+                    if counts[i] == 0 || population < 2 {
+                        return 0.0;
+                    }
+                    let payoff: f64 = (0..n)
+                        .map(|j| fitness[i][j] * (counts[j] - usize::from(i == j)) as f64)
+                        .sum::<f64>() / (population - 1) as f64;
+                    1.0 - self.selection_intensity + self.selection_intensity * payoff
+                })
+                .collect();
+            let total_fitness: f64 = (0..n).map(|i| counts[i] as f64 * individual_fitness[i]).sum();
+
+            // With no fitness to select on (w = 1 and nothing but losses) every living type is
+            // equally likely to reproduce.
+            let parent = if total_fitness > 0.0 {
+                pick_weighted(&mut rng, (0..n).map(|i| counts[i] as f64 * individual_fitness[i]), total_fitness)
+            } else {
+                let living = counts.iter().filter(|&&c| c > 0).count();
+                pick_weighted(&mut rng, counts.iter().map(|&c| if c > 0 { 1.0 } else { 0.0 }), living as f64)
+            };
+            let offspring = if rng.gen::<f64>() < self.mutation_rate { rng.gen_range(0..n) } else { parent };
+            let replaced = pick_weighted(&mut rng, counts.iter().map(|&c| c as f64), population as f64);
+
+            counts[replaced] -= 1;
+            counts[offspring] += 1;
+
+            for i in 0..n {
+                share_sums[i] += counts[i] as f64 / population as f64;
+            }
+            steps += 1;
+
+            if step % self.sample_interval.max(1) == 0 {
+                trajectory.push(counts.clone());
+            }
+            if self.mutation_rate == 0.0 && counts.contains(&population) {
+                break;
+            }
+        }
+
+        let fixated = counts.iter()
+            .position(|&c| c == population)
+            .map(|i| matrix.strategies[i].clone());
+        let average_shares = share_sums.iter().map(|s| s / steps.max(1) as f64).collect();
+
+        Ok(MoranReport {
+            strategies: matrix.strategies.clone(),
+            trajectory,
+            final_counts: counts,
+            average_shares,
+            fixated,
+        })
+    }
+}
+
+fn pick_weighted<I: Iterator<Item = f64>>(rng: &mut StdRng, weights: I, total: f64) -> usize {
+    let mut target = rng.gen::<f64>() * total;
+    let mut last = 0;
+    for (i, weight) in weights.enumerate() {
+        if weight > 0.0 {
+            last = i;
+            if target < weight {
+                return i;
+            }
+            target -= weight;
+        }
+    }
+    last
+}
+
+// Pure strategies satisfying the ESS condition from §7.3 of the analysis:
+// E(s, s) > E(t, s), or E(s, s) = E(t, s) and E(s, t) > E(t, t), for every t != s.
+pub fn evolutionary_stable_strategies(matrix: &PayoffMatrix) -> Vec<String> {
+    let fitness = fitness_matrix(matrix);
+    let n = matrix.len();
+    (0..n)
+        .filter(|&s| (0..n).filter(|&t| t != s).all(|t| {
+            fitness[s][s] > fitness[t][s] + EPSILON
+                || ((fitness[s][s] - fitness[t][s]).abs() <= EPSILON && fitness[s][t] > fitness[t][t] + EPSILON)
+        }))
+        .map(|s| matrix.strategies[s].clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Rock-paper-scissors plus a fourth strategy that loses to all three.
+    fn matrix() -> PayoffMatrix {
+        PayoffMatrix {
+            strategies: ["Rock", "Paper", "Scissors", "Loser"].iter().map(|s| s.to_string()).collect(),
+            payoffs: vec![
+                vec![0.0, -1.0, 1.0, 1.0],
+                vec![1.0, 0.0, -1.0, 1.0],
+                vec![-1.0, 1.0, 0.0, 1.0],
+                vec![-1.0, -1.0, -1.0, 0.0],
+            ],
+        }
+    }
+
+    fn moran() -> MoranProcess {
+        MoranProcess { population: 20, mutation_rate: 0.0, selection_intensity: 0.5, generations: 2000, sample_interval: 100, seed: 3 }
+    }
+
+    #[test]
+    fn moran_rejects_populations_that_do_not_fit_the_matrix() {
+        assert!(moran().run(&matrix(), Some(&[5, 5, 5])).is_err());
+        assert!(moran().run(&matrix(), Some(&[0, 0, 0, 0])).is_err());
+        assert!(MoranProcess { population: 0, ..moran() }.run(&matrix(), None).is_err());
+    }
+
+    #[test]
+    fn moran_replays_from_its_seed_and_keeps_the_population() {
+        let report = moran().run(&matrix(), Some(&[5, 5, 5, 5])).unwrap();
+        assert_eq!(report.final_counts, moran().run(&matrix(), Some(&[5, 5, 5, 5])).unwrap().final_counts);
+        assert!(report.trajectory.iter().all(|counts| counts.iter().sum::<usize>() == 20));
+    }
+
+    #[test]
+    fn replicator_dynamics_drive_out_a_dominated_strategy() {
+        let report = ReplicatorDynamics::default().run(&matrix(), None).unwrap();
+        assert!(report.final_shares[3] < 1e-3);
+        assert!(!report.survivors.contains(&"Loser".to_string()));
+        assert!(report.stable_strategies.is_empty());
+    }
+
+    #[test]
+    fn replicator_dynamics_reject_initial_shares_that_do_not_fit_the_matrix() {
+        let dynamics = ReplicatorDynamics::default();
+        assert!(dynamics.run(&matrix(), Some(&[0.5, 0.5])).is_err());
+        assert!(dynamics.run(&matrix(), Some(&[0.0, 0.0, 0.0, 0.0])).is_err());
+        assert!(dynamics.run(&matrix(), Some(&[0.5, 0.5, 0.5, -0.5])).is_err());
+        assert!(dynamics.run(&matrix(), Some(&[1.0, 1.0, 1.0, 1.0])).is_ok());
+    }
+
+    #[test]
+    fn moran_without_fitness_only_picks_living_types() {
+        // At full selection intensity a population that only ever loses has no fitness at all.
+        let losers = PayoffMatrix {
+            strategies: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+            payoffs: vec![vec![-1.0; 3]; 3],
+        };
+        let process = MoranProcess { population: 10, selection_intensity: 1.0, ..moran() };
+        let report = process.run(&losers, Some(&[0, 5, 5])).unwrap();
+        assert!(report.trajectory.iter().all(|counts| counts[0] == 0));
+        assert!(report.fixated.is_some());
+    }
+}
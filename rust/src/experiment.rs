@@ -0,0 +1,554 @@
+// File: src/experiment.rs
+
+use crate::{GameMode, OptimizationLevel, Strategy};
+use crate::game::{DiceKind, GameRules, MAX_BOARD_SIZE};
+use crate::neural::NeuralStrategy;
+use crate::simulation::{SimulationConfig, SimulatorType};
+use crate::opponent_model::{OpponentModelStrategy, OPPONENT_MODEL};
+use crate::strategies::{create_strategies, create_strategy, create_strategy_with, strategy_names};
+use crate::tournament::{RoundRobin, Tournament, TournamentResult};
+use crate::tournament_elimination::Elimination;
+use crate::tournament_gauntlet::Gauntlet;
+use crate::tournament_swiss::Swiss;
+use serde::{Serialize, Deserialize, Deserializer};
+use serde::de::{DeserializeOwned, Error as _};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+// A declarative experiment: the rules, the strategy field, how to run it and where the results
+// go. Files ending in `.toml` are read as TOML and anything else as JSON. An experiment runs a
+// tournament when `tournament` is set and plays every pair through `simulator` otherwise.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExperimentConfig {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub rules: Rules,
+    // Registry names as listed by `strategy_names`; every registered strategy when empty.
+    #[serde(default)]
+    pub strategies: Vec<String>,
+    // Numeric parameters by strategy name, e.g. `parameters."Look Ahead".depth = 4`.
+    #[serde(default)]
+    pub parameters: BTreeMap<String, BTreeMap<String, f64>>,
+    // Model files of neural strategies by the name they play under, e.g. `neural.DQN = "dqn.json"`.
+    // The names can be listed in `strategies`, and join the registry's when it is empty.
+    #[serde(default)]
+    pub neural: BTreeMap<String, PathBuf>,
+    // Games per pairing, or per match in a tournament.
+    #[serde(default = "default_num_games")]
+    pub num_games: usize,
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default, deserialize_with = "optional_from_name")]
+    pub simulator: Option<SimulatorType>,
+    #[serde(default)]
+    pub tournament: Option<TournamentSpec>,
+    #[serde(default)]
+    pub outputs: Vec<OutputSpec>,
+    // Streams one row per game while the pairings play; not available for tournaments.
+    #[serde(default)]
+    pub games: Option<OutputSpec>,
+    // Records the experiment in a results database; needs the `sqlite` feature.
+    #[serde(default)]
+    pub store: Option<StoreSpec>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rules {
+    #[serde(default = "default_mode", deserialize_with = "from_name")]
+    pub mode: GameMode,
+    #[serde(default = "default_optimization", deserialize_with = "from_name")]
+    pub optimization: OptimizationLevel,
+    #[serde(default = "default_board_size")]
+    pub board_size: u8,
+    #[serde(default = "default_rounds")]
+    pub rounds: u8,
+    // The optimization level's dice model when not given.
+    #[serde(default, deserialize_with = "optional_from_name")]
+    pub dice: Option<DiceKind>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "format", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum TournamentSpec {
+    RoundRobin,
+    Swiss { rounds: usize },
+    SingleElimination { best_of: usize },
+    DoubleElimination { best_of: usize },
+    Gauntlet { candidate: String },
+}
+
+// Json writes the whole result as one document; the other formats write one row per record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Json,
+    Csv,
+    Jsonl,
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputSpec {
+    pub path: PathBuf,
+    // Taken from the file extension when not given.
+    #[serde(default, deserialize_with = "optional_from_name")]
+    pub format: Option<OutputFormat>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StoreSpec {
+    pub path: PathBuf,
+    // Also store a row per game; simulator runs only.
+    #[serde(default)]
+    pub games: bool,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(String),
+    UnknownStrategy(String),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "{}", e),
+            ConfigError::Parse(message) | ConfigError::UnknownStrategy(message) | ConfigError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl ConfigError {
+    // Prefixes the message with the file it came from.
+    pub fn in_file(self, path: &Path) -> Self {
+        match self {
+            ConfigError::Io(e) => ConfigError::Io(e),
+            ConfigError::Parse(message) => ConfigError::Parse(format!("{}: {}", path.display(), message)),
+            ConfigError::UnknownStrategy(message) => ConfigError::UnknownStrategy(format!("{}: {}", path.display(), message)),
+            ConfigError::Invalid(message) => ConfigError::Invalid(format!("{}: {}", path.display(), message)),
+        }
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(e: io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+fn default_num_games() -> usize { 10_000 }
+fn default_mode() -> GameMode { GameMode::Base }
+fn default_optimization() -> OptimizationLevel { OptimizationLevel::Standard }
+fn default_board_size() -> u8 { GameRules::default().board_size }
+fn default_rounds() -> u8 { GameRules::default().rounds }
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            mode: default_mode(),
+            optimization: default_optimization(),
+            board_size: default_board_size(),
+            rounds: default_rounds(),
+            dice: None,
+        }
+    }
+}
+
+impl Rules {
+    pub fn game_rules(&self) -> GameRules {
+        GameRules { board_size: self.board_size, rounds: self.rounds, dice: self.dice }
+    }
+}
+
+// Rule and backend names go through `FromStr`, so files accept the same spellings as the
+// command line and get the same error messages.
+fn from_name<'de, D: Deserializer<'de>, T: FromStr<Err = String>>(deserializer: D) -> Result<T, D::Error> {
+    String::deserialize(deserializer)?.parse().map_err(D::Error::custom)
+}
+
+fn optional_from_name<'de, D: Deserializer<'de>, T: FromStr<Err = String>>(deserializer: D) -> Result<Option<T>, D::Error> {
+    match Option::<String>::deserialize(deserializer)? {
+        Some(name) => name.parse().map(Some).map_err(D::Error::custom),
+        None => Ok(None),
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "jsonl" | "ndjson" => Ok(OutputFormat::Jsonl),
+            #[cfg(feature = "parquet")]
+            "parquet" => Ok(OutputFormat::Parquet),
+            #[cfg(not(feature = "parquet"))]
+            "parquet" => Err("parquet output is not available in this build; rebuild with `--features parquet`".to_string()),
+            other => Err(format!("unknown output format '{}' (expected json, csv, jsonl or parquet)", other)),
+        }
+    }
+}
+
+impl OutputFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()?.to_str()?.parse().ok()
+    }
+
+    pub fn is_tabular(self) -> bool {
+        self != OutputFormat::Json
+    }
+}
+
+impl OutputSpec {
+    pub fn format(&self) -> Option<OutputFormat> {
+        self.format.or_else(|| OutputFormat::from_path(&self.path))
+    }
+}
+
+impl TournamentSpec {
+    // Plays the experiment's tournament with its rules, games and seed.
+    pub fn run(&self, experiment: &ExperimentConfig, strategies: &[Box<dyn Strategy>]) -> Result<TournamentResult, ConfigError> {
+        self.build(experiment.num_games)
+            .run(experiment.rules.mode, experiment.rules.optimization, strategies, experiment.seed)
+            .map_err(ConfigError::Invalid)
+    }
+
+    pub fn build(&self, games_per_match: usize) -> Box<dyn Tournament> {
+        match self {
+            TournamentSpec::RoundRobin => Box::new(RoundRobin { games_per_match }),
+            TournamentSpec::Swiss { rounds } => Box::new(Swiss { rounds: *rounds, games_per_match }),
+            TournamentSpec::SingleElimination { best_of } => Box::new(Elimination::single(*best_of)),
+            TournamentSpec::DoubleElimination { best_of } => Box::new(Elimination::double(*best_of)),
+            TournamentSpec::Gauntlet { candidate } => Box::new(Gauntlet { candidate: candidate.clone(), games_per_match }),
+        }
+    }
+}
+
+impl ExperimentConfig {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let config: Self = read_file(path)?;
+        config.validate().map_err(|e| e.in_file(path))?;
+        Ok(config)
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, ConfigError> {
+        let config: Self = parse_toml(text)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, ConfigError> {
+        let config: Self = parse_json(text)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    // Returns the strategies it built to check the config, so a caller about to run it need not
+    // build them again.
+    pub fn validate(&self) -> Result<Vec<Box<dyn Strategy>>, ConfigError> {
+        let invalid = |message: String| Err(ConfigError::Invalid(message));
+
+        if self.num_games == 0 {
+            return invalid("num_games must be at least 1".to_string());
+        }
+        if !(2..=MAX_BOARD_SIZE).contains(&self.rules.board_size) {
+            return invalid(format!("rules.board_size must be between 2 and {}, got {}", MAX_BOARD_SIZE, self.rules.board_size));
+        }
+        if self.rules.rounds == 0 {
+            return invalid("rules.rounds must be at least 1".to_string());
+        }
+        let strategies = self.strategies()?;
+        if strategies.len() < 2 {
+            return invalid("strategies: an experiment needs at least two strategies".to_string());
+        }
+        let mut seen = HashSet::new();
+        if let Some(duplicate) = strategies.iter().find(|s| !seen.insert(s.name())) {
+            return invalid(format!("strategies: '{}' is listed more than once", duplicate.name()));
+        }
+        // Parameters are keyed by the names as listed; a strategy's name may include them.
+        let names = self.strategy_names();
+        if let Some(name) = self.parameters.keys().find(|name| !names.iter().any(|n| n.trim().eq_ignore_ascii_case(name))) {
+            return invalid(format!("parameters: '{}' is not one of the strategies", name));
+        }
+        // Workers rebuild strategies from their names alone.
+        if self.simulator == Some(SimulatorType::Distributed) && !self.parameters.is_empty() {
+            return invalid("parameters: the distributed simulator only runs strategies with their default parameters".to_string());
+        }
+        if self.simulator == Some(SimulatorType::Distributed) && !self.neural.is_empty() {
+            return invalid("neural: the distributed simulator only runs registered strategies".to_string());
+        }
+        if self.simulator == Some(SimulatorType::Distributed) && strategies.iter().any(|s| s.name() == OPPONENT_MODEL) {
+            return invalid(format!("strategies: the distributed simulator only runs registered strategies, not '{}'", OPPONENT_MODEL));
+        }
+        // Tournaments always play the standard game.
+        if self.tournament.is_some() && self.rules.game_rules() != GameRules::default() {
+            return invalid("tournaments only support the default board_size, rounds and dice".to_string());
+        }
+
+        match &self.tournament {
+            Some(_) if self.simulator.is_some() => {
+                return invalid("set either `simulator` or `tournament`, not both".to_string());
+            },
+            Some(TournamentSpec::Swiss { rounds: 0 }) => {
+                return invalid("tournament.rounds must be at least 1".to_string());
+            },
+            Some(TournamentSpec::SingleElimination { best_of } | TournamentSpec::DoubleElimination { best_of }) if best_of % 2 == 0 => {
+                return invalid(format!("tournament.best_of must be odd, got {}", best_of));
+            },
+            Some(TournamentSpec::Gauntlet { candidate }) if !strategies.iter().any(|s| s.name() == candidate) => {
+                return invalid(format!("tournament.candidate '{}' is not one of the strategies", candidate));
+            },
+            _ => {},
+        }
+
+        if let Some(games) = &self.games {
+            if self.tournament.is_some() {
+                return invalid("games: per-game rows are only recorded for simulator runs, not tournaments".to_string());
+            }
+            validate_output("games", games)?;
+        }
+        if let Some(store) = &self.store {
+            if cfg!(not(feature = "sqlite")) {
+                return invalid("store: the results database is not available in this build; rebuild with `--features sqlite`".to_string());
+            }
+            if store.games && self.tournament.is_some() {
+                return invalid("store.games: per-game rows are only recorded for simulator runs, not tournaments".to_string());
+            }
+            let parent = store.path.parent().filter(|p| !p.as_os_str().is_empty());
+            if let Some(parent) = parent.filter(|p| !p.is_dir()) {
+                return invalid(format!("store: directory '{}' does not exist", parent.display()));
+            }
+        }
+        validate_outputs(&self.outputs)?;
+        Ok(strategies)
+    }
+
+    // Resolves the strategy names against the neural models, the opponent model and the
+    // registry, and applies the registry strategies' parameters. The opponent model picks its
+    // candidates from the whole registry.
+    pub fn strategies(&self) -> Result<Vec<Box<dyn Strategy>>, ConfigError> {
+        let no_parameters = BTreeMap::new();
+        self.strategy_names().iter()
+            .map(|name| {
+                if let Some(path) = self.neural.get(name) {
+                    return self.neural_strategy(name, path);
+                }
+                if name.trim().eq_ignore_ascii_case(OPPONENT_MODEL) {
+                    let candidates = create_strategies();
+                    return Ok(Box::new(OpponentModelStrategy::new(self.rules.mode, self.rules.game_rules(), candidates)));
+                }
+                if create_strategy(name).is_none() {
+                    return Err(ConfigError::UnknownStrategy(format!("strategies: unknown strategy '{}'", name)));
+                }
+                let params = self.parameters.iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(name.trim()))
+                    .map_or(&no_parameters, |(_, params)| params);
+                create_strategy_with(name, params)
+                    .map_err(|message| ConfigError::Invalid(format!("parameters.{}: {}", name, message)))
+            })
+            .collect()
+    }
+
+    // The listed strategy names, or every registered and neural one when none are listed.
+    fn strategy_names(&self) -> Vec<String> {
+        if self.strategies.is_empty() {
+            strategy_names().into_iter().chain(self.neural.keys().cloned()).collect()
+        } else {
+            self.strategies.clone()
+        }
+    }
+
+    fn neural_strategy(&self, name: &str, path: &Path) -> Result<Box<dyn Strategy>, ConfigError> {
+        if create_strategy(name).is_some() {
+            return Err(ConfigError::Invalid(format!("neural.{}: the name is taken by a registered strategy", name)));
+        }
+        let strategy = NeuralStrategy::load(path, Some(name)).map_err(|e| ConfigError::Invalid(format!("neural.{}: {}", name, e)))?;
+        if strategy.network.board_size != self.rules.board_size {
+            return Err(ConfigError::Invalid(format!("neural.{}: the model plays a {}-tile board, the rules use {}", name, strategy.network.board_size, self.rules.board_size)));
+        }
+        Ok(Box::new(strategy))
+    }
+
+    // Falls back to the parallel simulator, or the multithreaded one without the `parallel` feature.
+    pub fn simulator_type(&self) -> SimulatorType {
+        #[cfg(feature = "parallel")]
+        let default = SimulatorType::Parallel;
+        #[cfg(not(feature = "parallel"))]
+        let default = SimulatorType::Multithreaded;
+        self.simulator.unwrap_or(default)
+    }
+
+    // The serializable description of this experiment's runs.
+    pub fn simulation_config(&self) -> crate::SimulationConfig {
+        crate::SimulationConfig {
+            num_games: self.num_games,
+            game_mode: self.rules.mode,
+            optimization_level: self.rules.optimization,
+            strategies: self.strategies.clone(),
+            seed: self.seed,
+            rules: self.rules.game_rules(),
+        }
+    }
+
+    // One run per unordered pair of strategies, in the order they are listed.
+    pub fn pairings(&self) -> Result<Vec<SimulationConfig>, ConfigError> {
+        Ok(self.pairings_of(self.strategies()?))
+    }
+
+    // `pairings` for strategies already built, such as those returned by `validate`.
+    pub fn pairings_of(&self, strategies: Vec<Box<dyn Strategy>>) -> Vec<SimulationConfig> {
+        let strategies: Vec<Arc<dyn Strategy>> = strategies.into_iter().map(Arc::from).collect();
+        let mut pairings = Vec::new();
+        for i in 0..strategies.len() {
+            for j in i + 1..strategies.len() {
+                pairings.push(SimulationConfig {
+                    mode: self.rules.mode,
+                    optimization: self.rules.optimization,
+                    rules: self.rules.game_rules(),
+                    strategies: vec![strategies[i].clone(), strategies[j].clone()],
+                    num_games: self.num_games,
+                    first_game: 0,
+                    seed: self.seed,
+                    simulator_type: self.simulator_type(),
+                });
+            }
+        }
+        pairings
+    }
+}
+
+// Reads `path` as TOML when it ends in `.toml` and as JSON otherwise.
+pub fn read_file<T: DeserializeOwned>(path: &Path) -> Result<T, ConfigError> {
+    let text = fs::read_to_string(path)?;
+    let is_toml = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+    let parsed = if is_toml { parse_toml(&text) } else { parse_json(&text) };
+    parsed.map_err(|e| e.in_file(path))
+}
+
+fn parse_toml<T: DeserializeOwned>(text: &str) -> Result<T, ConfigError> {
+    toml::from_str(text).map_err(|e| ConfigError::Parse(e.to_string().trim_end().to_string()))
+}
+
+fn parse_json<T: DeserializeOwned>(text: &str) -> Result<T, ConfigError> {
+    serde_json::from_str(text).map_err(|e| ConfigError::Parse(e.to_string()))
+}
+
+pub fn validate_outputs(outputs: &[OutputSpec]) -> Result<(), ConfigError> {
+    for (i, output) in outputs.iter().enumerate() {
+        validate_output(&format!("outputs[{}]", i), output)?;
+    }
+    Ok(())
+}
+
+fn validate_output(key: &str, output: &OutputSpec) -> Result<(), ConfigError> {
+    if output.format().is_none() {
+        // A .parquet file in a build without the feature says how to get it.
+        let extension = output.path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        if extension.eq_ignore_ascii_case("parquet") {
+            if let Err(e) = extension.parse::<OutputFormat>() {
+                return Err(ConfigError::Invalid(format!("{}: {}", key, e)));
+            }
+        }
+        return Err(ConfigError::Invalid(format!("{}: can't tell the format of '{}'; set `format` to json, csv, jsonl or parquet", key, output.path.display())));
+    }
+    let parent = output.path.parent().filter(|p| !p.as_os_str().is_empty());
+    if let Some(parent) = parent.filter(|p| !p.is_dir()) {
+        return Err(ConfigError::Invalid(format!("{}: directory '{}' does not exist", key, parent.display())));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_robin(seed: u64) -> TournamentResult {
+        let text = format!("strategies = [\"Highest Value\", \"Highest Probability\", \"Balanced Value\"]\nnum_games = 50\nseed = {}\n[rules]\noptimization = \"fast\"\n[tournament]\nformat = \"round-robin\"\n", seed);
+        let config = ExperimentConfig::from_toml(&text).unwrap();
+        let strategies = config.validate().unwrap();
+        config.tournament.as_ref().unwrap().run(&config, &strategies).unwrap()
+    }
+
+    #[test]
+    fn tournaments_use_the_experiment_seed() {
+        let wins = |result: &TournamentResult| result.matches.iter().map(|m| (m.wins1, m.wins2)).collect::<Vec<_>>();
+        assert_eq!(wins(&round_robin(1)), wins(&round_robin(1)));
+        assert_ne!(wins(&round_robin(1)), wins(&round_robin(2)));
+    }
+
+    #[test]
+    fn validate_returns_the_strategies_it_built() {
+        let config = ExperimentConfig::from_json(r#"{"strategies": ["Highest Value", "Balanced Value"], "num_games": 10}"#).unwrap();
+        let names: Vec<&str> = config.validate().unwrap().iter().map(|s| s.name()).collect();
+        assert_eq!(names, ["Highest Value", "Balanced Value"]);
+        assert_eq!(config.pairings_of(config.validate().unwrap()).len(), 1);
+    }
+
+    #[test]
+    fn strategy_parameters_show_in_the_name() {
+        let config = ExperimentConfig::from_json(r#"{"strategies": ["Highest Value", "look ahead"], "num_games": 10, "parameters": {"Look Ahead": {"depth": 4}}}"#).unwrap();
+        let names: Vec<&str> = config.validate().unwrap().iter().map(|s| s.name()).collect();
+        assert_eq!(names, ["Highest Value", "Look Ahead (depth 4)"]);
+    }
+
+    // `fields` set over a valid two-strategy experiment.
+    fn with_fields(fields: &str) -> Result<ExperimentConfig, ConfigError> {
+        let mut config = serde_json::json!({"strategies": ["Highest Value", "Look Ahead"], "num_games": 10});
+        let fields: serde_json::Map<String, serde_json::Value> = serde_json::from_str(fields).unwrap();
+        for (key, value) in fields {
+            config[key] = value;
+        }
+        ExperimentConfig::from_json(&config.to_string())
+    }
+
+    fn rejection(fields: &str) -> String {
+        match with_fields(fields) {
+            Err(ConfigError::Invalid(message)) => message,
+            other => panic!("{} gave {:?}", fields, other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn validate_rejects_bad_rules_and_fields() {
+        assert!(rejection(r#"{"num_games": 0}"#).contains("num_games"));
+        assert!(rejection(r#"{"rules": {"board_size": 1}}"#).contains("rules.board_size"));
+        assert!(rejection(r#"{"rules": {"board_size": 13}}"#).contains("rules.board_size"));
+        assert!(rejection(r#"{"rules": {"rounds": 0}}"#).contains("rules.rounds"));
+        assert!(rejection(r#"{"strategies": ["Look Ahead"]}"#).contains("at least two strategies"));
+        assert!(rejection(r#"{"strategies": ["Look Ahead", "look ahead"]}"#).contains("more than once"));
+        assert!(rejection(r#"{"parameters": {"Balanced Value": {"weight": 1}}}"#).contains("not one of the strategies"));
+        assert!(rejection(r#"{"simulator": "distributed", "parameters": {"Look Ahead": {"depth": 2}}}"#).contains("distributed"));
+    }
+
+    #[test]
+    fn validate_rejects_bad_tournaments() {
+        for rules in [r#"{"board_size": 9}"#, r#"{"rounds": 3}"#, r#"{"dice": "weighted"}"#] {
+            let fields = format!(r#"{{"rules": {}, "tournament": {{"format": "round-robin"}}}}"#, rules);
+            assert!(rejection(&fields).contains("default board_size, rounds and dice"));
+        }
+        assert!(rejection(r#"{"simulator": "multithreaded", "tournament": {"format": "round-robin"}}"#).contains("not both"));
+        assert!(rejection(r#"{"tournament": {"format": "swiss", "rounds": 0}}"#).contains("tournament.rounds"));
+        assert!(rejection(r#"{"tournament": {"format": "single-elimination", "best_of": 2}}"#).contains("best_of"));
+        assert!(rejection(r#"{"tournament": {"format": "gauntlet", "candidate": "Random"}}"#).contains("tournament.candidate"));
+        assert!(rejection(r#"{"tournament": {"format": "round-robin"}, "games": {"path": "games.csv"}}"#).contains("games"));
+        // The mode and optimization level are free to change.
+        assert!(with_fields(r#"{"rules": {"mode": "extended", "optimization": "fast"}, "tournament": {"format": "round-robin"}}"#).is_ok());
+    }
+}
@@ -1,117 +1,662 @@
 // File: src/game.rs
 
-use crate::{GameState, Strategy, GameMode, OptimizationLevel, GameResult};
-use crate::rng::Xoroshiro128Plus;
+use crate::{GameState, Strategy, GameMode, OptimizationLevel, GameResult, GameOutcome};
+use crate::history::{empty_history, MatchHistory};
+use crate::rng::{game_seed, Xoroshiro128Plus};
 use rand::distributions::{Distribution, WeightedIndex};
-use rand::Rng;
-use rand::rngs::ThreadRng;
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+use std::str::FromStr;
+
+pub const MAX_BOARD_SIZE: u8 = 12;
 
 const FULL_BOARD: u16 = 0xFFF;
 
-pub struct Game {
-    rng: Box<dyn rand::RngCore>,
-    weighted_dist: WeightedIndex<u32>,
-    subset_cache: [u16; 13],
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "kebab-case"))]
+pub enum DiceKind {
+    TwoDice,
+    Weighted,
+}
+
+impl FromStr for DiceKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().replace('_', "-").as_str() {
+            "two-dice" | "twodice" => Ok(DiceKind::TwoDice),
+            "weighted" => Ok(DiceKind::Weighted),
+            other => Err(format!("unknown dice model '{}' (expected two-dice or weighted)", other)),
+        }
+    }
+}
+
+// Tiles 1..=board_size start up, and a base game ends once each seat has played `rounds` turns.
+// `dice` overrides the model `OptimizationLevel` would pick. The defaults are the standard 12-tile, 5-round game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct GameRules {
+    pub board_size: u8,
+    pub rounds: u8,
+    pub dice: Option<DiceKind>,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        GameRules { board_size: MAX_BOARD_SIZE, rounds: 5, dice: None }
+    }
+}
+
+// Moves a player may make with `roll`: passing (0), or any set of the `open` tiles whose values
+// add up to the roll, tile `i` being bit `i - 1`. The engine itself applies whatever a strategy
+// returns; this is the rule the RL environments enforce.
+pub fn legal_moves(open: u16, roll: u8) -> Vec<u16> {
+    let mut moves = vec![0];
+    // No tile above the roll can be part of a move.
+    let open = open & FULL_BOARD & ((1u16 << roll.min(MAX_BOARD_SIZE)) - 1);
+    let mut subset = open;
+    while subset != 0 {
+        if tile_sum(subset) == roll as u32 {
+            moves.push(subset);
+        }
+        subset = (subset - 1) & open;
+    }
+    moves[1..].sort_unstable();
+    moves
+}
+
+// The tiles `seat` moves: the open ones, except in extended mode, where seat 1 reopens closed
+// tiles.
+pub fn movable_tiles(mode: GameMode, full_board: u16, board: u16, seat: usize) -> u16 {
+    match mode {
+        GameMode::Extended if seat == 1 => !board & full_board,
+        _ => board & full_board,
+    }
+}
+
+pub fn tile_sum(tiles: u16) -> u32 {
+    (0..MAX_BOARD_SIZE as u32).filter(|i| tiles & (1 << i) != 0).map(|i| i + 1).sum()
+}
+
+// Per-turn hook for statistics. `points` is the turn's score in base mode and None in extended
+// mode, which has no scores. `()` ignores every turn, so the plain `play_*` methods pay nothing.
+// `record_move` sees every move, and `history` is what strategies are shown as
+// `GameState::history`; `history::MatchHistory` implements both.
+pub trait TurnObserver {
+    fn end_turn(&mut self, player: usize, points: Option<u32>, moves: u32);
+
+    #[inline]
+    fn record_move(&mut self, _player: usize, _board: u16, _round: u8, _roll: u8, _move_bits: u16) {}
+
+    #[inline]
+    fn history(&self) -> &MatchHistory {
+        empty_history()
+    }
+}
+
+impl TurnObserver for () {
+    #[inline]
+    fn end_turn(&mut self, _player: usize, _points: Option<u32>, _moves: u32) {}
+}
+
+pub trait ReseedableRng: RngCore {
+    fn reseed(&mut self, seed: u64);
+}
+
+impl ReseedableRng for Xoroshiro128Plus {
+    fn reseed(&mut self, seed: u64) {
+        self.seed(seed);
+    }
+}
+
+// Generator picked by `OptimizationLevel` for the dynamic path. Standard and FastPrecise use
+// the standard library's seeded CSPRNG, so every level replays from its seed and `reset`.
+pub enum LevelRng {
+    Std(Box<StdRng>),
+    Xoroshiro(Xoroshiro128Plus),
+}
+
+impl LevelRng {
+    pub fn new(optimization: OptimizationLevel, seed: u64) -> Self {
+        match optimization {
+            OptimizationLevel::Standard | OptimizationLevel::FastPrecise => LevelRng::Std(Box::new(StdRng::seed_from_u64(seed))),
+            OptimizationLevel::Fast => LevelRng::Xoroshiro(Xoroshiro128Plus::new(seed)),
+            OptimizationLevel::UltraOptimized => LevelRng::Xoroshiro(Xoroshiro128Plus::new(seed)), // You might want to implement an even faster RNG for this
+        }
+    }
+}
+
+impl RngCore for LevelRng {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            LevelRng::Std(rng) => rng.next_u32(),
+            LevelRng::Xoroshiro(rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            LevelRng::Std(rng) => rng.next_u64(),
+            LevelRng::Xoroshiro(rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            LevelRng::Std(rng) => rng.fill_bytes(dest),
+            LevelRng::Xoroshiro(rng) => rng.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl ReseedableRng for StdRng {
+    fn reseed(&mut self, seed: u64) {
+        *self = StdRng::seed_from_u64(seed);
+    }
+}
+
+impl ReseedableRng for LevelRng {
+    fn reseed(&mut self, seed: u64) {
+        match self {
+            LevelRng::Std(rng) => rng.reseed(seed),
+            LevelRng::Xoroshiro(rng) => rng.reseed(seed),
+        }
+    }
+}
+
+pub trait DiceModel {
+    fn roll<R: RngCore + ?Sized>(&self, rng: &mut R) -> u8;
+}
+
+// Two independent uniform dice.
+pub struct TwoDice;
+
+impl DiceModel for TwoDice {
+    #[inline]
+    fn roll<R: RngCore + ?Sized>(&self, rng: &mut R) -> u8 {
+        rng.gen_range(1..=6) + rng.gen_range(1..=6)
+    }
+}
+
+// The sum of two dice sampled in a single draw from its 1-2-3-4-5-6-5-4-3-2-1 distribution.
+pub struct WeightedDice {
+    dist: WeightedIndex<u32>,
+}
+
+impl WeightedDice {
+    pub fn new() -> Self {
+        WeightedDice { dist: WeightedIndex::new([1, 2, 3, 4, 5, 6, 5, 4, 3, 2, 1]).unwrap() }
+    }
+}
+
+impl Default for WeightedDice {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DiceModel for WeightedDice {
+    #[inline]
+    fn roll<R: RngCore + ?Sized>(&self, rng: &mut R) -> u8 {
+        (self.dist.sample(rng) + 2) as u8
+    }
+}
+
+// Dice picked by `OptimizationLevel` for the dynamic path.
+pub enum LevelDice {
+    TwoDice(TwoDice),
+    Weighted(WeightedDice),
+}
+
+impl LevelDice {
+    pub fn new(optimization: OptimizationLevel) -> Self {
+        match optimization {
+            OptimizationLevel::Standard | OptimizationLevel::FastPrecise => LevelDice::TwoDice(TwoDice),
+            OptimizationLevel::Fast | OptimizationLevel::UltraOptimized => LevelDice::Weighted(WeightedDice::new()),
+        }
+    }
+
+    pub fn from_kind(kind: DiceKind) -> Self {
+        match kind {
+            DiceKind::TwoDice => LevelDice::TwoDice(TwoDice),
+            DiceKind::Weighted => LevelDice::Weighted(WeightedDice::new()),
+        }
+    }
+}
+
+impl DiceModel for LevelDice {
+    fn roll<R: RngCore + ?Sized>(&self, rng: &mut R) -> u8 {
+        match self {
+            LevelDice::TwoDice(dice) => dice.roll(rng),
+            LevelDice::Weighted(dice) => dice.roll(rng),
+        }
+    }
+}
+
+// `Game` with the default parameters is the dynamic, registry-driven engine configured by
+// `OptimizationLevel`. Naming a concrete generator and dice model, e.g.
+// `Game<Xoroshiro128Plus, WeightedDice>`, lets the compiler inline the whole hot loop.
+pub struct Game<R = LevelRng, D = LevelDice> {
+    rng: R,
+    dice: D,
     mode: GameMode,
-    optimization: OptimizationLevel,
+    full_board: u16,
+    rounds: u8,
+    // Strategies' generators are seeded from `seed` and the number of games played since.
+    seed: u64,
+    games: u64,
 }
 
 impl Game {
     pub fn new(mode: GameMode, optimization: OptimizationLevel, seed: u64) -> Self {
-        let rng: Box<dyn rand::RngCore> = match optimization {
-            OptimizationLevel::Standard | OptimizationLevel::FastPrecise => Box::new(ThreadRng::default()),
-            OptimizationLevel::Fast => Box::new(Xoroshiro128Plus::new(seed)),
-            OptimizationLevel::UltraOptimized => Box::new(Xoroshiro128Plus::new(seed)), // You might want to implement an even faster RNG for this
-        };
+        Game::with_parts(mode, LevelRng::new(optimization, seed), LevelDice::new(optimization)).with_seed(seed)
+    }
+
+    pub fn from_rules(mode: GameMode, optimization: OptimizationLevel, rules: GameRules, seed: u64) -> Self {
+        let dice = rules.dice.map_or_else(|| LevelDice::new(optimization), LevelDice::from_kind);
+        Game::with_parts(mode, LevelRng::new(optimization, seed), dice).with_rules(rules).with_seed(seed)
+    }
+}
 
-        let mut game = Game {
+impl<R: ReseedableRng, D: DiceModel> Game<R, D> {
+    // Reseeds the generator so the next game plays exactly like one from a freshly built game
+    // with this seed, without rebuilding the dice distribution.
+    pub fn reset(&mut self, seed: u64) {
+        self.rng.reseed(seed);
+        self.seed = seed;
+        self.games = 0;
+    }
+}
+
+impl<R: RngCore, D: DiceModel> Game<R, D> {
+    pub fn with_parts(mode: GameMode, rng: R, dice: D) -> Self {
+        Game {
             rng,
-            weighted_dist: WeightedIndex::new(&[1, 2, 3, 4, 5, 6, 5, 4, 3, 2, 1]).unwrap(),
-            subset_cache: [0; 13],
+            dice,
             mode,
-            optimization,
-        };
-        game.init_subset_cache();
-        game
+            full_board: FULL_BOARD,
+            rounds: GameRules::default().rounds,
+            seed: 0,
+            games: 0,
+        }
+    }
+
+    // Board size and round limit; the dice model is fixed by `D`.
+    pub fn with_rules(mut self, rules: GameRules) -> Self {
+        self.full_board = (1 << rules.board_size.clamp(1, MAX_BOARD_SIZE)) - 1;
+        self.rounds = rules.rounds;
+        self
+    }
+
+    // The seed strategies' generators are derived from; `with_parts` can't see the generator's.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    #[inline]
+    pub(crate) fn roll_dice(&mut self) -> u8 {
+        self.dice.roll(&mut self.rng)
+    }
+
+    pub fn mode(&self) -> GameMode {
+        self.mode
     }
 
-    fn init_subset_cache(&mut self) {
-        for i in 2..=12 {
-            self.subset_cache[i] = (1 << i) - 1;
+    pub fn full_board(&self) -> u16 {
+        self.full_board
+    }
+
+    pub fn rounds(&self) -> u8 {
+        self.rounds
+    }
+
+    pub fn play_game(&mut self, strategies: &mut [&mut dyn Strategy]) -> GameResult {
+        let mut scores = vec![0; strategies.len()];
+        let (winner, num_rounds) = self.play_loop(strategies, &mut scores, &mut ());
+        GameResult {
+            winner,
+            scores,
+            num_rounds,
         }
     }
 
-    fn roll_dice(&mut self) -> u8 {
-        match self.optimization {
-            OptimizationLevel::Standard | OptimizationLevel::FastPrecise => {
-                self.rng.gen_range(1..=6) + self.rng.gen_range(1..=6)
-            },
-            OptimizationLevel::Fast | OptimizationLevel::UltraOptimized => {
-                (self.weighted_dist.sample(&mut self.rng) + 2) as u8
-            },
+    // Two-player game without the heap allocation of `GameResult::scores`, for the simulators' hot loops.
+    pub fn play_pair(&mut self, strategies: [&mut dyn Strategy; 2]) -> GameOutcome {
+        self.play_pair_observed(strategies, &mut ())
+    }
+
+    pub fn play_pair_observed<O: TurnObserver>(&mut self, strategies: [&mut dyn Strategy; 2], observer: &mut O) -> GameOutcome {
+        let [a, b] = strategies;
+        self.play_static_observed(a, b, observer)
+    }
+
+    // Monomorphised over both strategy types, so no call on a strategy goes through a vtable.
+    pub fn play_game_static<A: Strategy + ?Sized, B: Strategy + ?Sized>(&mut self, a: &mut A, b: &mut B) -> GameOutcome {
+        self.play_static_observed(a, b, &mut ())
+    }
+
+    pub fn play_static_observed<A: Strategy + ?Sized, B: Strategy + ?Sized, O: TurnObserver>(&mut self, a: &mut A, b: &mut B, observer: &mut O) -> GameOutcome {
+        let mut scores = [0; 2];
+        let (winner, num_rounds) = self.play_loop(&mut (a, b), &mut scores, observer);
+        GameOutcome {
+            winner,
+            scores,
+            num_rounds,
         }
     }
 
-    fn generate_move(&self, board: u16, roll: u8) -> u16 {
-        let direct_move = board & (1 << (roll - 1));
-        if direct_move != 0 {
-            return direct_move;
+    // Seed of the next game's strategy generators; seat `i` gets `game_seed(seed, i)`.
+    pub(crate) fn strategy_seed(&mut self) -> u64 {
+        self.games += 1;
+        game_seed(self.seed, self.games - 1)
+    }
+
+    #[inline]
+    fn play_loop<S, O>(&mut self, seats: &mut S, scores: &mut [u32], observer: &mut O) -> (usize, u8)
+    where
+        S: Seats + ?Sized,
+        O: TurnObserver,
+    {
+        let mut play = self.start_game(seats);
+        while !play.done {
+            let roll = self.roll_dice();
+            let player = play.player;
+            let view = GameState { score: scores[player], history: observer.history(), ..self.view(&play) };
+            let move_bits = seats.choose_move(player, &view, roll);
+            for seat in (0..seats.count()).filter(|&seat| seat != player) {
+                seats.on_opponent_move(seat, &view, roll, move_bits);
+            }
+            observer.record_move(player, play.state.board, play.state.round, roll, move_bits);
+            self.apply_move(&mut play, move_bits, scores, seats, observer);
         }
-        let subset = self.subset_cache[roll as usize] & board;
-        if subset != 0 {
-            subset & (!subset + 1) // BLSI instruction equivalent
-        } else {
-            0
+        (self.end_game(&play, scores, seats), play.state.round)
+    }
+
+    // Seeds the strategies' generators for the next game and calls their `on_game_start`.
+    #[inline]
+    pub(crate) fn start_game<S: Seats + ?Sized>(&mut self, seats: &mut S) -> Play {
+        let seed = self.strategy_seed();
+        for seat in 0..seats.count() {
+            seats.on_game_start(seat, Xoroshiro128Plus::new(game_seed(seed, seat as u64)));
         }
+        self.new_play()
     }
 
-    pub fn play_game(&mut self, strategies: &[&dyn Strategy]) -> GameResult {
-        let mut state = GameState { board: FULL_BOARD, score: 0, round: 0 };
-        let mut scores = vec![0; strategies.len()];
-        let mut current_player = 0;
+    // A game before its first move, without calling any strategy.
+    #[inline]
+    pub(crate) fn new_play(&self) -> Play {
+        Play {
+            state: GameState { board: self.full_board, movable: self.full_board, score: 0, round: 0, player: 0, history: empty_history() },
+            player: 0,
+            moves: 0,
+            turns: 0,
+            done: false,
+        }
+    }
 
-        loop {
-            let roll = self.roll_dice();
-            let move_bits = strategies[current_player].choose_move(&state, roll);
-
-            match self.mode {
-                GameMode::Base => {
-                    if move_bits == 0 {
-                        scores[current_player] += state.board.count_ones() as u32;
-                        state.round += 1;
-                        if state.round >= 5 {
-                            break;
-                        }
-                        state.board = FULL_BOARD;
-                        current_player = (current_player + 1) % strategies.len();
-                    } else {
-                        state.board &= !move_bits;
+    // The board as the player to move sees it, without its score or the match history.
+    #[inline]
+    pub(crate) fn view(&self, play: &Play) -> GameState<'static> {
+        let movable = movable_tiles(self.mode, self.full_board, play.state.board, play.player);
+        GameState { movable, player: play.player, ..play.state }
+    }
+
+    // Makes `move_bits` for the player to move, ending its turn, and the game, where the rules
+    // say so.
+    #[inline]
+    pub(crate) fn apply_move<S, O>(&self, play: &mut Play, move_bits: u16, scores: &mut [u32], seats: &mut S, observer: &mut O)
+    where
+        S: Seats + ?Sized,
+        O: TurnObserver,
+    {
+        let player = play.player;
+        let num_players = seats.count();
+        match self.mode {
+            GameMode::Base => {
+                if move_bits == 0 {
+                    let points = play.state.board.count_ones();
+                    scores[player] += points;
+                    play.end_turn(seats, observer, Some(points));
+                    if play.turns >= self.rounds as u32 * num_players as u32 {
+                        play.done = true;
+                        return;
                     }
-                },
-                GameMode::Extended => {
-                    if move_bits == 0 {
-                        current_player = (current_player + 1) % strategies.len();
-                    } else {
-                        state.board = if current_player == 0 { state.board & !move_bits } else { state.board | move_bits };
-                        if state.board == 0 || state.board == FULL_BOARD {
-                            break;
-                        }
+                    play.state.board = self.full_board;
+                    play.player = (player + 1) % num_players;
+                } else {
+                    play.moves += 1;
+                    play.state.board &= !move_bits;
+                }
+            },
+            GameMode::Extended => {
+                if move_bits == 0 {
+                    play.end_turn(seats, observer, None);
+                    play.player = (player + 1) % num_players;
+                } else {
+                    play.moves += 1;
+                    play.state.board = if player == 0 { play.state.board & !move_bits } else { play.state.board | move_bits };
+                    if play.state.board == 0 || play.state.board == self.full_board {
+                        play.end_turn(seats, observer, None);
+                        play.done = true;
                     }
-                },
-            }
-
-            state.round += 1;
+                }
+            },
         }
+    }
 
+    // The winner of a finished game, after calling the strategies' `on_game_end`. Base-mode ties
+    // go to seat 0.
+    #[inline]
+    pub(crate) fn end_game<S: Seats + ?Sized>(&self, play: &Play, scores: &[u32], seats: &mut S) -> usize {
         let winner = match self.mode {
             GameMode::Base => scores.iter().enumerate().min_by_key(|&(_, &score)| score).unwrap().0,
-            GameMode::Extended => if state.board == 0 { 0 } else { 1 },
+            GameMode::Extended => if play.state.board == 0 { 0 } else { 1 },
         };
+        for seat in 0..seats.count() {
+            seats.on_game_end(seat, winner, scores);
+        }
+        winner
+    }
+}
 
-        GameResult {
-            winner,
-            scores,
-            num_rounds: state.round,
+// Where a game stands between moves. `Game::play_loop` and `env::Env` both advance it with
+// `Game::apply_move`. `state.round` counts finished turns, saturating at 255, and `turns` is
+// the count that ends a base game.
+pub(crate) struct Play {
+    pub state: GameState<'static>,
+    pub player: usize,
+    moves: u32,
+    turns: u32,
+    pub done: bool,
+}
+
+impl Play {
+    #[inline]
+    fn end_turn<S: Seats + ?Sized, O: TurnObserver>(&mut self, seats: &mut S, observer: &mut O, points: Option<u32>) {
+        observer.end_turn(self.player, points, self.moves);
+        for seat in 0..seats.count() {
+            seats.on_turn_end(seat, self.player, points);
+        }
+        self.moves = 0;
+        self.turns += 1;
+        self.state.round = self.state.round.saturating_add(1);
+    }
+}
+
+// The strategies of a game by seat: a pair of concrete types for the static path, or a slice
+// of trait objects.
+pub(crate) trait Seats {
+    fn count(&self) -> usize;
+    fn choose_move(&mut self, seat: usize, state: &GameState, roll: u8) -> u16;
+    fn on_game_start(&mut self, seat: usize, rng: Xoroshiro128Plus);
+    fn on_opponent_move(&mut self, seat: usize, state: &GameState, roll: u8, move_bits: u16);
+    fn on_turn_end(&mut self, seat: usize, player: usize, points: Option<u32>);
+    fn on_game_end(&mut self, seat: usize, winner: usize, scores: &[u32]);
+}
+
+impl<A: Strategy + ?Sized, B: Strategy + ?Sized> Seats for (&mut A, &mut B) {
+    #[inline]
+    fn count(&self) -> usize {
+        2
+    }
+
+    #[inline]
+    fn choose_move(&mut self, seat: usize, state: &GameState, roll: u8) -> u16 {
+        if seat == 0 { self.0.choose_move(state, roll) } else { self.1.choose_move(state, roll) }
+    }
+
+    #[inline]
+    fn on_game_start(&mut self, seat: usize, rng: Xoroshiro128Plus) {
+        if seat == 0 { self.0.on_game_start(seat, rng) } else { self.1.on_game_start(seat, rng) }
+    }
+
+    #[inline]
+    fn on_opponent_move(&mut self, seat: usize, state: &GameState, roll: u8, move_bits: u16) {
+        if seat == 0 { self.0.on_opponent_move(state, roll, move_bits) } else { self.1.on_opponent_move(state, roll, move_bits) }
+    }
+
+    #[inline]
+    fn on_turn_end(&mut self, seat: usize, player: usize, points: Option<u32>) {
+        if seat == 0 { self.0.on_turn_end(player, points) } else { self.1.on_turn_end(player, points) }
+    }
+
+    #[inline]
+    fn on_game_end(&mut self, seat: usize, winner: usize, scores: &[u32]) {
+        if seat == 0 { self.0.on_game_end(winner, scores) } else { self.1.on_game_end(winner, scores) }
+    }
+}
+
+impl Seats for [&mut dyn Strategy] {
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    fn choose_move(&mut self, seat: usize, state: &GameState, roll: u8) -> u16 {
+        self[seat].choose_move(state, roll)
+    }
+
+    fn on_game_start(&mut self, seat: usize, rng: Xoroshiro128Plus) {
+        self[seat].on_game_start(seat, rng)
+    }
+
+    fn on_opponent_move(&mut self, seat: usize, state: &GameState, roll: u8, move_bits: u16) {
+        self[seat].on_opponent_move(state, roll, move_bits)
+    }
+
+    fn on_turn_end(&mut self, seat: usize, player: usize, points: Option<u32>) {
+        self[seat].on_turn_end(player, points)
+    }
+
+    fn on_game_end(&mut self, seat: usize, winner: usize, scores: &[u32]) {
+        self[seat].on_game_end(winner, scores)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategies::{HighestValueStrategy, RandomStrategy};
+
+    // Rolls and moves of `games` games between Random and Highest Value after `reset(seed)`.
+    fn play(mode: GameMode, optimization: OptimizationLevel, seed: u64, games: usize) -> Vec<(u8, u16)> {
+        let mut game = Game::new(mode, optimization, 12345);
+        game.reset(seed);
+        let mut history = MatchHistory::new();
+        let mut random = RandomStrategy::default();
+        for _ in 0..games {
+            game.play_pair_observed([&mut random, &mut HighestValueStrategy], &mut history);
+        }
+        history.moves.iter().map(|m| (m.roll, m.move_bits)).collect()
+    }
+
+    #[test]
+    fn every_level_replays_from_its_seed() {
+        for optimization in [OptimizationLevel::Standard, OptimizationLevel::FastPrecise, OptimizationLevel::Fast, OptimizationLevel::UltraOptimized] {
+            for mode in [GameMode::Base, GameMode::Extended] {
+                let first = play(mode, optimization, 7, 20);
+                assert_eq!(first, play(mode, optimization, 7, 20), "{:?} {:?}", optimization, mode);
+                assert_ne!(first, play(mode, optimization, 8, 20), "{:?} {:?}", optimization, mode);
+            }
         }
     }
-}
\ No newline at end of file
+
+    // Highest Value, checking that every state shows the points of its own finished turns.
+    #[derive(Clone, Default)]
+    struct ScoreCheck {
+        seat: usize,
+        points: u32,
+        moves: usize,
+    }
+
+    impl Strategy for ScoreCheck {
+        fn choose_move(&mut self, state: &GameState, roll: u8) -> u16 {
+            assert_eq!(state.score, self.points);
+            self.moves += 1;
+            HighestValueStrategy.choose_move(state, roll)
+        }
+
+        fn name(&self) -> &'static str {
+            "Score Check"
+        }
+
+        fn on_game_start(&mut self, seat: usize, _rng: Xoroshiro128Plus) {
+            self.seat = seat;
+            self.points = 0;
+        }
+
+        fn on_turn_end(&mut self, player: usize, points: Option<u32>) {
+            if player == self.seat {
+                self.points += points.unwrap_or(0);
+            }
+        }
+    }
+
+    #[test]
+    fn states_show_the_movers_score() {
+        let mut game = Game::new(GameMode::Base, OptimizationLevel::Fast, 1);
+        let (mut a, mut b) = (ScoreCheck::default(), ScoreCheck::default());
+        for _ in 0..50 {
+            let outcome = game.play_pair([&mut a, &mut b]);
+            assert_eq!(outcome.scores, [a.points, b.points]);
+        }
+        assert!(a.moves > 0 && b.moves > 0);
+    }
+
+    // Turns played by each seat.
+    struct TurnCount([u8; 2]);
+
+    impl TurnObserver for TurnCount {
+        fn end_turn(&mut self, player: usize, _points: Option<u32>, _moves: u32) {
+            self.0[player] += 1;
+        }
+    }
+
+    #[test]
+    fn both_seats_play_every_round() {
+        for rounds in [1, 3, 5] {
+            let rules = GameRules { rounds, ..GameRules::default() };
+            let mut game = Game::from_rules(GameMode::Base, OptimizationLevel::Fast, rules, 2);
+            for _ in 0..20 {
+                let mut turns = TurnCount([0; 2]);
+                let outcome = game.play_pair_observed([&mut HighestValueStrategy, &mut RandomStrategy::default()], &mut turns);
+                assert_eq!(turns.0, [rounds, rounds]);
+                assert_eq!(outcome.num_rounds, 2 * rounds);
+            }
+        }
+    }
+
+    #[test]
+    fn legal_moves_add_up_to_the_roll() {
+        // Tiles 1 to 4 open: 5 is 2 + 3 or 1 + 4.
+        assert_eq!(legal_moves(0b1111, 5), vec![0, 0b0110, 0b1001]);
+        assert_eq!(legal_moves(0b1111, 11), vec![0]);
+    }
+}
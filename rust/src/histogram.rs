@@ -0,0 +1,96 @@
+// File: src/histogram.rs
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+// Fixed-width bins over `low..low + bin_width * counts.len()`, plus counters for values that
+// fall outside. The layout is set at construction, so memory doesn't grow with the sample count.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Histogram {
+    pub low: i64,
+    pub bin_width: u64,
+    pub counts: Vec<u64>,
+    pub underflow: u64,
+    pub overflow: u64,
+}
+
+impl Histogram {
+    pub fn new(low: i64, bin_width: u64, bins: usize) -> Self {
+        Histogram {
+            low,
+            bin_width: bin_width.max(1),
+            counts: vec![0; bins],
+            underflow: 0,
+            overflow: 0,
+        }
+    }
+
+    pub fn add(&mut self, value: i64) {
+        if value < self.low {
+            self.underflow += 1;
+            return;
+        }
+        let bin = ((value - self.low) as u64 / self.bin_width) as usize;
+        match self.counts.get_mut(bin) {
+            Some(count) => *count += 1,
+            None => self.overflow += 1,
+        }
+    }
+
+    // Both histograms must share a layout.
+    pub fn merge(&mut self, other: &Histogram) {
+        assert!(
+            self.low == other.low && self.bin_width == other.bin_width && self.counts.len() == other.counts.len(),
+            "cannot merge histograms with different bins"
+        );
+        for (count, other_count) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other_count;
+        }
+        self.underflow += other.underflow;
+        self.overflow += other.overflow;
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum::<u64>() + self.underflow + self.overflow
+    }
+
+    // Inclusive lower and exclusive upper edge of a bin.
+    pub fn bin_range(&self, bin: usize) -> (i64, i64) {
+        let start = self.low + (bin as u64 * self.bin_width) as i64;
+        (start, start + self.bin_width as i64)
+    }
+
+    // Mean of the binned samples, taking each bin at its lower edge (exact for unit-width bins).
+    pub fn mean(&self) -> f64 {
+        let binned: u64 = self.counts.iter().sum();
+        if binned == 0 {
+            return 0.0;
+        }
+        let sum: f64 = self.counts.iter().enumerate()
+            .map(|(bin, &count)| self.bin_range(bin).0 as f64 * count as f64)
+            .sum();
+        sum / binned as f64
+    }
+
+    // Lower edge of the bin holding the `q` quantile, or None for an empty histogram or when the
+    // quantile lands in the underflow or overflow counter.
+    pub fn quantile(&self, q: f64) -> Option<i64> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+        let target = (q.clamp(0.0, 1.0) * total as f64).ceil().max(1.0) as u64;
+        let mut seen = self.underflow;
+        if seen >= target {
+            return None;
+        }
+        for (bin, &count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                return Some(self.bin_range(bin).0);
+            }
+        }
+        None
+    }
+}
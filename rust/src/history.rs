@@ -0,0 +1,108 @@
+// File: src/history.rs
+
+use crate::GameOutcome;
+use crate::game::TurnObserver;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+// What a game outside of a match sees.
+pub static EMPTY_HISTORY: MatchHistory = MatchHistory { id: 0, moves: Vec::new(), turns: Vec::new(), winners: Vec::new(), seats: [0, 1] };
+
+pub fn empty_history() -> &'static MatchHistory {
+    &EMPTY_HISTORY
+}
+
+// One move of a match. `player` is the match player who made it (0 for the match's first
+// strategy) and `seat` the seat they had in that game; the rest is what they were shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MoveRecord {
+    pub game: usize,
+    pub player: usize,
+    pub seat: usize,
+    pub board: u16,
+    pub round: u8,
+    pub roll: u8,
+    pub move_bits: u16,
+}
+
+// The points scored by a finished base-mode turn. Extended mode has no scores.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TurnRecord {
+    pub game: usize,
+    pub player: usize,
+    pub points: u32,
+}
+
+// Everything played so far in a match between two strategies, including the game in progress.
+// As a `TurnObserver` it records the games it is passed to, and the engine shows it to both
+// strategies through `GameState::history`. Each history gets its own `id`, so a strategy can
+// tell matches apart when caching what it has learned.
+#[derive(Debug)]
+pub struct MatchHistory {
+    pub id: u64,
+    pub moves: Vec<MoveRecord>,
+    pub turns: Vec<TurnRecord>,
+    pub winners: Vec<usize>,
+    seats: [usize; 2],
+}
+
+impl Default for MatchHistory {
+    fn default() -> Self {
+        MatchHistory::new()
+    }
+}
+
+impl MatchHistory {
+    pub fn new() -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        MatchHistory { id, moves: Vec::new(), turns: Vec::new(), winners: Vec::new(), seats: [0, 1] }
+    }
+
+    // Finished games; the game in progress is number `games()`.
+    pub fn games(&self) -> usize {
+        self.winners.len()
+    }
+
+    // Starts the next game with match player `first` in seat 0.
+    pub fn start_game(&mut self, first: usize) {
+        self.seats = [first, 1 - first];
+    }
+
+    // `outcome` is in seat order, as `Game::play_pair_observed` returns it.
+    pub fn end_game(&mut self, outcome: &GameOutcome) {
+        self.winners.push(self.seats[outcome.winner]);
+    }
+
+    // The match player in `seat` of the current game.
+    pub fn player(&self, seat: usize) -> usize {
+        self.seats[seat.min(1)]
+    }
+
+    // Moves made by the opponent of whoever is in `seat` of the current game.
+    pub fn opponent_moves(&self, seat: usize) -> impl Iterator<Item = &MoveRecord> {
+        let opponent = 1 - self.player(seat);
+        self.moves.iter().filter(move |m| m.player == opponent)
+    }
+
+    pub fn opponent_turns(&self, seat: usize) -> impl Iterator<Item = &TurnRecord> {
+        let opponent = 1 - self.player(seat);
+        self.turns.iter().filter(move |t| t.player == opponent)
+    }
+}
+
+impl TurnObserver for MatchHistory {
+    fn end_turn(&mut self, player: usize, points: Option<u32>, _moves: u32) {
+        if let Some(points) = points {
+            self.turns.push(TurnRecord { game: self.games(), player: self.player(player), points });
+        }
+    }
+
+    fn record_move(&mut self, player: usize, board: u16, round: u8, roll: u8, move_bits: u16) {
+        self.moves.push(MoveRecord { game: self.games(), player: self.player(player), seat: player, board, round, roll, move_bits });
+    }
+
+    fn history(&self) -> &MatchHistory {
+        self
+    }
+}
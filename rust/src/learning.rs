@@ -0,0 +1,427 @@
+// File: src/learning.rs
+
+use crate::{GameMode, GameState, Strategy};
+use crate::game::{DiceModel, GameRules, WeightedDice};
+use crate::rng::{game_seed, Xoroshiro128Plus};
+use crate::solver::{roll_probability, Model, Outcome, Position, Solution, MAX_ROLL, MIN_ROLL};
+use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+#[cfg(feature = "serde")]
+use std::fs::File;
+#[cfg(feature = "serde")]
+use std::io::{self, BufReader, BufWriter, Write};
+#[cfg(feature = "serde")]
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+const EPSILON: f64 = 1e-9;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "kebab-case"))]
+pub enum Algorithm {
+    QLearning,
+    Sarsa,
+    TdLambda,
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().replace('_', "-").as_str() {
+            "q-learning" | "qlearning" | "q" => Ok(Algorithm::QLearning),
+            "sarsa" => Ok(Algorithm::Sarsa),
+            "td-lambda" | "tdlambda" | "td" => Ok(Algorithm::TdLambda),
+            other => Err(format!("unknown algorithm '{}' (expected q-learning, sarsa or td-lambda)", other)),
+        }
+    }
+}
+
+// Self-play training: one table plays both sides, each valuing positions from the mover's point
+// of view, with rewards as in `solver::Outcome`. `lambda` is only used by TD(λ), which is
+// SARSA(λ) with replacing traces. An extended game can go on forever, so an episode is cut off
+// after `max_moves` moves and counted as a draw, as the solver counts endless games.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TrainingConfig {
+    pub mode: GameMode,
+    pub rules: GameRules,
+    pub algorithm: Algorithm,
+    pub episodes: usize,
+    pub alpha: f64,
+    pub epsilon: f64,
+    pub lambda: f64,
+    pub max_moves: usize,
+    pub seed: u64,
+}
+
+impl TrainingConfig {
+    pub fn new(mode: GameMode, algorithm: Algorithm) -> Self {
+        TrainingConfig {
+            mode,
+            rules: GameRules::default(),
+            algorithm,
+            episodes: 100_000,
+            alpha: 0.1,
+            epsilon: 0.1,
+            lambda: 0.8,
+            max_moves: 1_000,
+            seed: 0,
+        }
+    }
+}
+
+// Action values for every (position, roll) seen in training, in the order of
+// `Model::moves`. Unseen positions are all zeros, so the greedy move there is to pass.
+pub struct QTable {
+    pub mode: GameMode,
+    pub rules: GameRules,
+    pub episodes: usize,
+    model: Model,
+    values: HashMap<(Position, u8), Vec<f64>>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct SavedEntry {
+    position: Position,
+    roll: u8,
+    moves: Vec<u16>,
+    values: Vec<f64>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct SavedTable {
+    mode: GameMode,
+    rules: GameRules,
+    episodes: usize,
+    entries: Vec<SavedEntry>,
+}
+
+impl QTable {
+    pub fn new(mode: GameMode, rules: GameRules) -> Self {
+        QTable { mode, rules, episodes: 0, model: Model::new(mode, rules), values: HashMap::new() }
+    }
+
+    pub fn model(&self) -> &Model {
+        &self.model
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn values(&self, position: Position, roll: u8) -> Option<&[f64]> {
+        self.values.get(&(position, roll)).map(Vec::as_slice)
+    }
+
+    fn entry(&mut self, position: Position, roll: u8) -> &mut Vec<f64> {
+        let count = self.model.moves(position, roll).len();
+        self.values.entry((position, roll)).or_insert_with(|| vec![0.0; count])
+    }
+
+    // Index into `Model::moves` of the highest value, the first one on ties.
+    fn greedy(&self, position: Position, roll: u8) -> usize {
+        self.values(position, roll).map_or(0, |values| {
+            values.iter().enumerate().fold(0, |best, (i, &v)| if v > values[best] { i } else { best })
+        })
+    }
+
+    fn max_value(&self, position: Position, roll: u8) -> f64 {
+        self.values(position, roll).map_or(0.0, |values| values.iter().copied().fold(f64::NEG_INFINITY, f64::max))
+    }
+
+    pub fn best_move(&self, position: Position, roll: u8) -> u16 {
+        self.model.moves(position, roll)[self.greedy(position, roll)]
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut entries: Vec<SavedEntry> = self.values.iter()
+            .map(|(&(position, roll), values)| SavedEntry {
+                position,
+                roll,
+                moves: self.model.moves(position, roll).to_vec(),
+                values: values.clone(),
+            })
+            .collect();
+        entries.sort_by_key(|e| (e.position.stage, e.position.board, e.roll));
+        let saved = SavedTable { mode: self.mode, rules: self.rules, episodes: self.episodes, entries };
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut writer, &saved)?;
+        writer.flush()
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let saved: SavedTable = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+        let mut table = QTable::new(saved.mode, saved.rules);
+        table.episodes = saved.episodes;
+        for entry in saved.entries {
+            if !(MIN_ROLL..=MAX_ROLL).contains(&entry.roll)
+                || entry.position.board > table.model.full_board
+                || table.model.moves(entry.position, entry.roll) != entry.moves.as_slice()
+                || entry.values.len() != entry.moves.len() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{}: entry for board {:#06x} and roll {} does not match the rules", path.display(), entry.position.board, entry.roll)));
+            }
+            table.values.insert((entry.position, entry.roll), entry.values);
+        }
+        Ok(table)
+    }
+}
+
+struct Trace {
+    key: (Position, u8),
+    action: usize,
+    eligibility: f64,
+}
+
+pub struct Trainer {
+    pub config: TrainingConfig,
+    pub table: QTable,
+    dice: WeightedDice,
+}
+
+impl Trainer {
+    pub fn new(config: TrainingConfig) -> Self {
+        Trainer { table: QTable::new(config.mode, config.rules), config, dice: WeightedDice::new() }
+    }
+
+    // Continues training a saved table; its mode and rules replace the config's.
+    pub fn resume(mut config: TrainingConfig, table: QTable) -> Self {
+        config.mode = table.mode;
+        config.rules = table.rules;
+        Trainer { table, config, dice: WeightedDice::new() }
+    }
+
+    // Plays `config.episodes` more games. Episode `i` of a table's training is seeded with
+    // `game_seed(config.seed, i)`, so resuming continues the same sequence.
+    pub fn train(&mut self) {
+        let first = self.table.episodes;
+        for episode in first..first + self.config.episodes {
+            let mut rng = Xoroshiro128Plus::new(game_seed(self.config.seed, episode as u64));
+            self.episode(&mut rng);
+            self.table.episodes += 1;
+        }
+    }
+
+    fn choose(&self, rng: &mut Xoroshiro128Plus, position: Position, roll: u8) -> usize {
+        let count = self.table.model.moves(position, roll).len();
+        if rng.gen::<f64>() < self.config.epsilon {
+            rng.gen_range(0..count)
+        } else {
+            self.table.greedy(position, roll)
+        }
+    }
+
+    fn episode(&mut self, rng: &mut Xoroshiro128Plus) {
+        let TrainingConfig { algorithm, alpha, lambda, .. } = self.config;
+        let mut traces: Vec<Trace> = Vec::new();
+        let mut position = self.table.model.start();
+        let mut roll = self.dice.roll(rng);
+        let mut action = self.choose(rng, position, roll);
+
+        for moves in 1.. {
+            let move_bits = self.table.model.moves(position, roll)[action];
+            // The next position's value counts for the mover with the sign `sign`.
+            let (reward, next, sign) = match self.table.model.apply(position, move_bits) {
+                _ if moves >= self.config.max_moves => (0.0, None, 0.0),
+                Outcome::Continue(next) => (0.0, Some(next), 1.0),
+                Outcome::Pass(reward, next) => (reward, Some(next), -1.0),
+                Outcome::End(reward) => (reward, None, 0.0),
+            };
+            let next_roll = self.dice.roll(rng);
+            let next_action = next.map(|next| self.choose(rng, next, next_roll));
+
+            let bootstrap = match (next, next_action, algorithm) {
+                (Some(next), _, Algorithm::QLearning) => self.table.max_value(next, next_roll),
+                (Some(next), Some(next_action), _) => self.table.values(next, next_roll).map_or(0.0, |values| values[next_action]),
+                _ => 0.0,
+            };
+            let key = (position, roll);
+            let delta = reward + sign * bootstrap - self.table.entry(position, roll)[action];
+
+            if algorithm == Algorithm::TdLambda {
+                match traces.iter_mut().find(|t| t.key == key && t.action == action) {
+                    Some(trace) => trace.eligibility = 1.0,
+                    None => traces.push(Trace { key, action, eligibility: 1.0 }),
+                }
+                for trace in &traces {
+                    self.table.entry(trace.key.0, trace.key.1)[trace.action] += alpha * delta * trace.eligibility;
+                }
+                // Earlier moves were made by whoever is now the opponent when the turn passes.
+                for trace in &mut traces {
+                    trace.eligibility *= lambda * sign;
+                }
+                traces.retain(|t| t.eligibility.abs() > EPSILON);
+            } else {
+                self.table.entry(position, roll)[action] += alpha * delta;
+            }
+
+            match (next, next_action) {
+                (Some(next), Some(next_action)) => {
+                    position = next;
+                    roll = next_roll;
+                    action = next_action;
+                },
+                _ => break,
+            }
+        }
+    }
+
+    pub fn report(&self, solution: &Solution) -> TrainingReport {
+        TrainingReport::new(&self.config, &self.table, solution)
+    }
+}
+
+// How far the learned table is from the exact solution. `agreement` is the share of visited
+// (position, roll) pairs whose greedy move is optimal, `value_error` the mean gap between the
+// learned and exact values of the best move there, and `gap` how much the greedy policy, moving
+// first, gives up against an opponent that best-responds to it.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TrainingReport {
+    pub mode: GameMode,
+    pub algorithm: Algorithm,
+    pub episodes: usize,
+    pub states: usize,
+    pub agreement: f64,
+    pub value_error: f64,
+    pub learned_value: f64,
+    pub policy_value: f64,
+    pub exact_value: f64,
+    pub gap: f64,
+}
+
+impl TrainingReport {
+    pub fn new(config: &TrainingConfig, table: &QTable, solution: &Solution) -> Self {
+        let mut agreeing = 0;
+        let mut value_error = 0.0;
+        for (&(position, roll), values) in &table.values {
+            let best = solution.move_value(position, solution.best_move(position, roll));
+            let greedy = table.best_move(position, roll);
+            if solution.move_value(position, greedy) >= best - EPSILON {
+                agreeing += 1;
+            }
+            value_error += (values.iter().copied().fold(f64::NEG_INFINITY, f64::max) - best).abs();
+        }
+        let states = table.len();
+        let start = table.model.start();
+        let learned_value = (MIN_ROLL..=MAX_ROLL).map(|roll| roll_probability(roll) * table.max_value(start, roll)).sum();
+        let policy = |position: Position, roll: u8| table.best_move(position, roll);
+        let policy_value = Solution::evaluate(table.model.clone(), &policy).start_value();
+        let exact_value = solution.start_value();
+        TrainingReport {
+            mode: table.mode,
+            algorithm: config.algorithm,
+            episodes: table.episodes,
+            states,
+            agreement: if states == 0 { 0.0 } else { agreeing as f64 / states as f64 },
+            value_error: if states == 0 { 0.0 } else { value_error / states as f64 },
+            learned_value,
+            policy_value,
+            exact_value,
+            gap: exact_value - policy_value,
+        }
+    }
+}
+
+// Greedy play from a trained table. In extended mode it plays the side of `seat`, which the
+// engine sets at the start of each game.
+#[derive(Clone)]
+pub struct LearnedStrategy {
+    pub table: Arc<QTable>,
+    pub seat: usize,
+}
+
+impl LearnedStrategy {
+    pub fn new(table: Arc<QTable>, seat: usize) -> Self {
+        LearnedStrategy { table, seat }
+    }
+}
+
+impl Strategy for LearnedStrategy {
+    fn choose_move(&mut self, state: &GameState, roll: u8) -> u16 {
+        if !(MIN_ROLL..=MAX_ROLL).contains(&roll) {
+            return 0;
+        }
+        let stage = match self.table.mode {
+            GameMode::Base => state.round,
+            GameMode::Extended => self.seat as u8,
+        };
+        self.table.best_move(Position { board: state.board & self.table.model.full_board, stage }, roll)
+    }
+
+    fn name(&self) -> &'static str {
+        "Learned"
+    }
+
+    fn on_game_start(&mut self, seat: usize, _rng: Xoroshiro128Plus) {
+        self.seat = seat;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: GameRules = GameRules { board_size: 4, rounds: 1, dice: None };
+
+    fn trained(algorithm: Algorithm) -> Trainer {
+        let config = TrainingConfig { rules: RULES, episodes: 20_000, ..TrainingConfig::new(GameMode::Base, algorithm) };
+        let mut trainer = Trainer::new(config);
+        trainer.train();
+        trainer
+    }
+
+    #[test]
+    fn every_algorithm_learns_a_small_board() {
+        let solution = Solution::solve(GameMode::Base, RULES);
+        for algorithm in [Algorithm::QLearning, Algorithm::Sarsa, Algorithm::TdLambda] {
+            let report = trained(algorithm).report(&solution);
+            assert!(report.gap.abs() < 0.05, "{:?} gives up {}", algorithm, report.gap);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn tables_round_trip_through_a_file() {
+        let table = trained(Algorithm::QLearning).table;
+        let path = std::env::temp_dir().join(format!("dice_game_qtable_{}.json", std::process::id()));
+        table.save(&path).unwrap();
+        let loaded = QTable::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let loaded = loaded.unwrap();
+        assert_eq!((loaded.mode, loaded.rules, loaded.episodes), (table.mode, table.rules, table.episodes));
+        assert_eq!(loaded.len(), table.len());
+        // JSON may round the last bit of a value, never enough to change a greedy move.
+        for (&(position, roll), values) in &table.values {
+            let read = loaded.values(position, roll).unwrap();
+            assert!(values.iter().zip(read).all(|(a, b)| (a - b).abs() < 1e-12));
+            assert_eq!(loaded.best_move(position, roll), table.best_move(position, roll));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn loading_rejects_entries_that_do_not_fit_the_rules() {
+        let path = std::env::temp_dir().join(format!("dice_game_qtable_rules_{}.json", std::process::id()));
+        trained(Algorithm::QLearning).table.save(&path).unwrap();
+        // The full 4-tile board no longer fits once the file claims a 3-tile one.
+        let mut saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        saved["rules"]["board_size"] = 3.into();
+        std::fs::write(&path, saved.to_string()).unwrap();
+        let loaded = QTable::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.err().map(|e| e.kind()), Some(io::ErrorKind::InvalidData));
+    }
+}
@@ -3,36 +3,128 @@
 pub mod game;
 pub mod strategies;
 pub mod tournament;
+pub mod tournament_swiss;
+pub mod tournament_elimination;
+pub mod tournament_gauntlet;
 pub mod simulation;
+pub mod control;
+pub mod histogram;
+#[cfg(feature = "serde")]
+pub mod checkpoint;
+#[cfg(feature = "serde")]
+pub mod experiment;
+#[cfg(feature = "serde")]
+pub mod sweep;
+#[cfg(feature = "serde")]
+pub mod sink;
+#[cfg(feature = "sqlite")]
+pub mod store;
+#[cfg(feature = "parallel")]
+pub mod simulator_paraller;
+pub mod simulator_multithreded;
+#[cfg(feature = "serde")]
+pub mod simulator_distributed;
+#[cfg(feature = "mpi")]
+pub mod simulator_mpi;
+#[cfg(feature = "python")]
+mod python;
 pub mod rng;
-pub mod common;
+pub mod rating;
+pub mod meta;
+pub mod evolution;
+pub mod env;
+pub mod solver;
+pub mod learning;
+pub mod cfr;
+pub mod history;
+pub mod opponent_model;
+#[cfg(feature = "serde")]
+pub mod neural;
 
-pub use game::{GameMode, OptimizationLevel};
-pub use strategies::Strategy;
-pub use tournament::run_simulation;
+use game::GameRules;
+use history::MatchHistory;
+use rng::Xoroshiro128Plus;
+pub use tournament::{run_simulation, Tournament, TournamentResult};
+pub use simulation::{Simulator, SimulationReport};
 
+#[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::str::FromStr;
+use std::sync::Mutex;
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
-pub struct GameState {
+// `player` is the mover's seat, `movable` the tiles it may move (see `game::movable_tiles`),
+// `score` its points from finished turns (always 0 in extended mode), `round` the number of
+// turns both seats have finished and `history` the match so far (empty outside of a match).
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GameState<'a> {
     pub board: u16,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub movable: u16,
     pub score: u32,
     pub round: u8,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub player: usize,
+    #[cfg_attr(feature = "serde", serde(skip, default = "history::empty_history"))]
+    pub history: &'a MatchHistory,
 }
 
-pub trait Strategy: Send + Sync {
-    fn choose_move(&self, state: &GameState, roll: u8) -> u16;
+// Strategies may keep state between moves: the engine calls the `on_*` hooks on every strategy
+// in a game, and each parallel worker plays with its own clone (see `CloneStrategy`), so that
+// state is never shared between games running at the same time.
+pub trait Strategy: CloneStrategy + Send + Sync {
+    fn choose_move(&mut self, state: &GameState, roll: u8) -> u16;
     fn name(&self) -> &'static str;
+
+    // Whether the strategy reads `GameState::history`. Matches involving one are played game
+    // by game with a history; the others are free to run their games in parallel.
+    fn uses_history(&self) -> bool {
+        false
+    }
+
+    // Called before each game with the strategy's seat and a generator the engine seeds from
+    // the game's seed, so a randomised strategy replays along with the dice.
+    fn on_game_start(&mut self, _seat: usize, _rng: Xoroshiro128Plus) {}
+
+    // Called after every move by another seat, with the state that seat was shown.
+    fn on_opponent_move(&mut self, _state: &GameState, _roll: u8, _move_bits: u16) {}
+
+    // Called at the end of every turn, the strategy's own included. `points` is as in
+    // `TurnObserver::end_turn`.
+    fn on_turn_end(&mut self, _player: usize, _points: Option<u32>) {}
+
+    // `scores` is in seat order.
+    fn on_game_end(&mut self, _winner: usize, _scores: &[u32]) {}
+}
+
+// Fresh instances for parallel workers, implemented for every `Clone` strategy. Strategies
+// that share large read-only data, such as solved tables, keep it behind an `Arc`.
+pub trait CloneStrategy {
+    fn clone_strategy(&self) -> Box<dyn Strategy>;
+}
+
+impl<T: Strategy + Clone + 'static> CloneStrategy for T {
+    fn clone_strategy(&self) -> Box<dyn Strategy> {
+        Box::new(self.clone())
+    }
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+impl Clone for Box<dyn Strategy> {
+    fn clone(&self) -> Self {
+        self.clone_strategy()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum GameMode {
     Base,
     Extended,
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum OptimizationLevel {
     Standard,
     FastPrecise,
@@ -40,24 +132,76 @@ pub enum OptimizationLevel {
     UltraOptimized,
 }
 
-#[derive(Serialize, Deserialize)]
+impl FromStr for GameMode {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "base" => Ok(GameMode::Base),
+            "extended" => Ok(GameMode::Extended),
+            other => Err(format!("unknown game mode '{}' (expected base or extended)", other)),
+        }
+    }
+}
+
+impl FromStr for OptimizationLevel {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().replace('_', "-").as_str() {
+            "standard" => Ok(OptimizationLevel::Standard),
+            "fast-precise" | "fastprecise" => Ok(OptimizationLevel::FastPrecise),
+            "fast" => Ok(OptimizationLevel::Fast),
+            "ultra" | "ultra-optimized" | "ultraoptimized" => Ok(OptimizationLevel::UltraOptimized),
+            other => Err(format!("unknown optimization level '{}' (expected standard, fast-precise, fast or ultra)", other)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SimulationConfig {
     pub num_games: usize,
     pub game_mode: GameMode,
     pub optimization_level: OptimizationLevel,
     pub strategies: Vec<String>,
     pub seed: u64,
+    // Missing from files written before rules were configurable, which used the defaults.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rules: GameRules,
 }
 
-#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct GameResult {
     pub winner: usize,
     pub scores: Vec<u32>,
     pub num_rounds: u8,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug)]
+pub struct GameOutcome {
+    pub winner: usize,
+    pub scores: [u32; 2],
+    pub num_rounds: u8,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SimulationResult {
     pub config: SimulationConfig,
     pub results: HashMap<String, Vec<GameResult>>,
 }
+
+// Strategy names must be `&'static str`; names only known at run time, such as those of Python
+// strategies and loaded models, are leaked once each.
+pub(crate) fn intern(name: String) -> &'static str {
+    static NAMES: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+    let mut names = NAMES.lock().unwrap();
+    match names.get(name.as_str()) {
+        Some(&interned) => interned,
+        None => {
+            let interned: &'static str = Box::leak(name.into_boxed_str());
+            names.insert(interned);
+            interned
+        },
+    }
+}
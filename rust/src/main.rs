@@ -0,0 +1,919 @@
+// File: src/main.rs
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use dice_game::{GameMode, OptimizationLevel, Strategy, TournamentResult};
+use dice_game::cfr::{CfrConfig, CfrReport, CfrTrainer, Variant};
+use dice_game::control::SimulationControl;
+use dice_game::experiment::{read_file, ConfigError, ExperimentConfig, OutputFormat, OutputSpec, Rules, StoreSpec, TournamentSpec};
+use dice_game::game::{Game, TurnObserver};
+use dice_game::learning::{Algorithm, QTable, Trainer, TrainingConfig, TrainingReport};
+use dice_game::meta::{MetaReport, PayoffMatrix};
+use dice_game::opponent_model::OPPONENT_MODEL;
+use dice_game::rng::game_seed;
+use dice_game::simulation::{self, SimulationReport, SimulatorType};
+use dice_game::simulator_distributed::run_worker;
+use dice_game::sink::{create_sink, record_games, Column, CsvSink, Field, JsonSink, PairingSummary, RankedStanding, Record, RecordSink};
+use dice_game::solver::Solution;
+#[cfg(feature = "sqlite")]
+use dice_game::store::ResultStore;
+use dice_game::strategies::{create_strategies, create_strategy, strategy_names};
+use dice_game::sweep::SweepConfig;
+use dice_game::tournament::RoundRobin;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+#[derive(Parser)]
+#[command(name = "dice_game", about = "Simulate and analyze strategies for the dice board game")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Plays every pair of the given strategies through one of the simulators")]
+    Simulate(SimulateArgs),
+    #[command(about = "Runs a tournament over the given strategies")]
+    Tournament(TournamentArgs),
+    #[command(about = "Nash equilibrium and dominance analysis of a tournament's payoff matrix")]
+    Solve(SolveArgs),
+    #[command(about = "Runs the experiment described by a TOML or JSON config file")]
+    Run(RunArgs),
+    #[command(about = "Runs an experiment once per combination of swept parameters")]
+    Sweep(RunArgs),
+    #[command(about = "Lists, shows and compares experiments in a results database")]
+    Query(QueryArgs),
+    #[command(about = "Replays a single game of a seeded run turn by turn")]
+    Replay(ReplayArgs),
+    #[command(about = "Learns a move table by self-play and compares it with the exact solution")]
+    Train(TrainArgs),
+    #[command(about = "Approximates an extended-mode equilibrium with CFR and reports its exploitability")]
+    Cfr(CfrArgs),
+    #[command(about = "Prints the names accepted by `--strategies`")]
+    ListStrategies(OutputArgs),
+    #[command(about = "Plays games for a `--simulator distributed` coordinator until it shuts down")]
+    Worker(WorkerArgs),
+}
+
+#[derive(Args)]
+struct GameArgs {
+    #[arg(short, long, default_value = "base", help = "Game mode: base or extended")]
+    mode: GameMode,
+    #[arg(short, long, default_value = "standard", help = "Optimization level: standard, fast-precise, fast or ultra")]
+    optimization: OptimizationLevel,
+    #[arg(short, long, value_delimiter = ',', help = "Comma-separated strategy names; all strategies when omitted")]
+    strategies: Vec<String>,
+    #[arg(short, long, default_value_t = 10_000, help = "Number of games to simulate (per pairing)")]
+    num_games: usize,
+    #[arg(long, value_name = "NAME=FILE", value_parser = parse_neural, help = "Adds a neural strategy playing as NAME from a model file; repeatable")]
+    neural: Vec<(String, PathBuf)>,
+}
+
+fn parse_neural(value: &str) -> Result<(String, PathBuf), String> {
+    match value.split_once('=') {
+        Some((name, path)) if !name.trim().is_empty() && !path.is_empty() => Ok((name.trim().to_string(), PathBuf::from(path))),
+        _ => Err(format!("expected NAME=FILE, got '{}'", value)),
+    }
+}
+
+#[derive(Args)]
+struct OutputArgs {
+    #[arg(long, help = "Output file; results go to stdout when omitted")]
+    output: Option<PathBuf>,
+    #[arg(long, help = "Output format: json, csv, jsonl or parquet; taken from the output file's extension when omitted, json otherwise")]
+    output_format: Option<OutputFormat>,
+}
+
+#[derive(Args)]
+struct LimitArgs {
+    #[arg(long, help = "Stop each run after this many seconds and report the games played so far")]
+    time_limit: Option<f64>,
+    #[arg(long, help = "Print progress to stderr")]
+    progress: bool,
+}
+
+#[derive(Args)]
+struct SimulateArgs {
+    #[command(flatten)]
+    game: GameArgs,
+    #[arg(long, default_value = "parallel", help = "Simulator type: multithreaded, parallel, distributed or mpi")]
+    simulator: String,
+    #[arg(long, default_value_t = 0, help = "Seed for game 0; game i uses rng::game_seed(seed, i)")]
+    seed: u64,
+    #[arg(long, help = "Also write one row per game to this file, in the format of its extension; the games are played in-process")]
+    games: Option<PathBuf>,
+    #[arg(long, help = "Record the run in this SQLite results database")]
+    store: Option<PathBuf>,
+    #[arg(long, requires = "store", help = "Also store one row per game in the results database")]
+    store_games: bool,
+    #[command(flatten)]
+    limits: LimitArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TournamentFormat {
+    RoundRobin,
+    Swiss,
+    SingleElimination,
+    DoubleElimination,
+    Gauntlet,
+}
+
+#[derive(Args)]
+struct TournamentArgs {
+    #[command(flatten)]
+    game: GameArgs,
+    #[arg(short, long, value_enum, default_value = "round-robin", help = "Tournament format")]
+    format: TournamentFormat,
+    #[arg(long, default_value_t = 5, help = "Rounds for a Swiss tournament")]
+    rounds: usize,
+    #[arg(long, default_value_t = 7, help = "Series length for elimination formats")]
+    best_of: usize,
+    #[arg(long, help = "Candidate strategy for a gauntlet")]
+    candidate: Option<String>,
+    #[arg(long, default_value_t = 0, help = "Seed for match 0; match k uses rng::game_seed(seed, k)")]
+    seed: u64,
+    #[arg(long, help = "Write the pairwise win-rate table instead of the full result")]
+    win_rates: bool,
+    #[arg(long, help = "Record the tournament in this SQLite results database")]
+    store: Option<PathBuf>,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[derive(Args)]
+struct SolveArgs {
+    #[arg(long, help = "Tournament result or win-rate table (JSON) to analyze; a round robin is played when omitted")]
+    input: Option<PathBuf>,
+    #[arg(long, default_value_t = 0, help = "Seed for the round robin played without --input")]
+    seed: u64,
+    #[command(flatten)]
+    game: GameArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[derive(Args)]
+struct RunArgs {
+    #[arg(help = "Config file; .toml files are read as TOML, anything else as JSON")]
+    config: PathBuf,
+    #[arg(long, help = "Only load and validate the file")]
+    check: bool,
+    #[command(flatten)]
+    limits: LimitArgs,
+}
+
+#[derive(Args)]
+struct QueryArgs {
+    #[arg(help = "Results database written by `--store` or an experiment's `store`")]
+    database: PathBuf,
+    #[command(subcommand)]
+    command: QueryCommand,
+}
+
+#[derive(Subcommand)]
+enum QueryCommand {
+    #[command(about = "Lists stored experiments, newest first")]
+    List {
+        #[arg(long, help = "Only experiments whose name contains this")]
+        name: Option<String>,
+        #[arg(long, help = "At most this many experiments")]
+        limit: Option<usize>,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    #[command(about = "Shows an experiment's metadata, config and results; tabular formats get one row per pairing")]
+    Show {
+        id: i64,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    #[command(about = "Compares each strategy's games, wins and win rate across experiments")]
+    Compare {
+        #[arg(required = true, num_args = 2.., help = "Experiment ids")]
+        ids: Vec<i64>,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+}
+
+#[derive(Args)]
+struct WorkerArgs {
+    #[arg(long, default_value = "127.0.0.1:7878", help = "Address of the coordinator")]
+    coordinator: String,
+}
+
+#[derive(Args)]
+struct ReplayArgs {
+    #[arg(short, long, default_value = "base", help = "Game mode: base or extended")]
+    mode: GameMode,
+    #[arg(short, long, default_value = "standard", help = "Optimization level: standard, fast-precise, fast or ultra")]
+    optimization: OptimizationLevel,
+    #[arg(short, long, value_delimiter = ',', required = true, help = "The two strategies, in seat order")]
+    strategies: Vec<String>,
+    #[arg(long, default_value_t = 0, help = "Seed of the run")]
+    seed: u64,
+    #[arg(long, default_value_t = 0, help = "Index of the game within the run")]
+    game: u64,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[derive(Args)]
+struct TrainArgs {
+    #[arg(short, long, default_value = "base", help = "Game mode: base or extended")]
+    mode: GameMode,
+    #[arg(short, long, default_value = "q-learning", help = "Algorithm: q-learning, sarsa or td-lambda")]
+    algorithm: Algorithm,
+    #[arg(short, long, default_value_t = 100_000, help = "Self-play games to train for")]
+    episodes: usize,
+    #[arg(long, default_value_t = 0.1, help = "Learning rate")]
+    alpha: f64,
+    #[arg(long, default_value_t = 0.1, help = "Probability of a random exploratory move")]
+    epsilon: f64,
+    #[arg(long, default_value_t = 0.8, help = "Trace decay for td-lambda")]
+    lambda: f64,
+    #[arg(long, default_value_t = 1_000, help = "Moves after which a game is cut off and counted as a draw")]
+    max_moves: usize,
+    #[arg(long, default_value_t = 0, help = "Seed; episode i uses rng::game_seed(seed, i)")]
+    seed: u64,
+    #[arg(long, help = "Continue training the table saved in this file; its mode replaces --mode")]
+    resume: Option<PathBuf>,
+    #[arg(long, help = "Save the learned table to this file")]
+    table: Option<PathBuf>,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[derive(Args)]
+struct CfrArgs {
+    #[arg(short, long, default_value = "cfr-plus", help = "Variant: cfr or cfr-plus")]
+    variant: Variant,
+    #[arg(short, long, default_value_t = 100, help = "Iterations to run")]
+    iterations: usize,
+    #[arg(long, default_value_t = 10, help = "Report exploitability every this many iterations, and after the last")]
+    report_every: usize,
+    #[arg(long, help = "Save the average strategy to this file")]
+    policy: Option<PathBuf>,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+// Exit codes: 1 for I/O failures, 2 for malformed command lines (reported by clap),
+// 3 for unknown strategy names and 4 for invalid configurations.
+enum CliError {
+    Strategy(String),
+    Config(String),
+    Io(io::Error),
+}
+
+impl CliError {
+    fn exit_code(&self) -> u8 {
+        match self {
+            CliError::Io(_) => 1,
+            CliError::Strategy(_) => 3,
+            CliError::Config(_) => 4,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Strategy(message) | CliError::Config(message) => write!(f, "{}", message),
+            CliError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(e: io::Error) -> Self {
+        CliError::Io(e)
+    }
+}
+
+impl From<ConfigError> for CliError {
+    fn from(e: ConfigError) -> Self {
+        match e {
+            ConfigError::Io(e) => CliError::Io(e),
+            ConfigError::UnknownStrategy(message) => CliError::Strategy(message),
+            ConfigError::Parse(message) | ConfigError::Invalid(message) => CliError::Config(message),
+        }
+    }
+}
+
+impl From<serde_json::Error> for CliError {
+    fn from(e: serde_json::Error) -> Self {
+        CliError::Io(e.into())
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Simulate(args) => simulate(args),
+        Command::Tournament(args) => tournament(args),
+        Command::Solve(args) => solve(args),
+        Command::Run(args) => run(args),
+        Command::Sweep(args) => sweep(args),
+        Command::Query(args) => query(args),
+        Command::Replay(args) => replay(args),
+        Command::Train(args) => train(args),
+        Command::Cfr(args) => cfr(args),
+        Command::ListStrategies(args) => list_strategies(args),
+        Command::Worker(args) => run_worker(args.coordinator.as_str()).map_err(CliError::Io),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(e.exit_code())
+        },
+    }
+}
+
+fn resolve_strategies(names: &[String]) -> Result<Vec<Box<dyn Strategy>>, CliError> {
+    if names.is_empty() {
+        return Ok(create_strategies());
+    }
+    names.iter()
+        .map(|name| create_strategy(name).ok_or_else(|| {
+            CliError::Strategy(format!("unknown strategy '{}'; run `list-strategies` for the available names", name))
+        }))
+        .collect()
+}
+
+impl GameArgs {
+    fn experiment(&self) -> ExperimentConfig {
+        ExperimentConfig {
+            name: String::new(),
+            rules: Rules { mode: self.mode, optimization: self.optimization, ..Rules::default() },
+            strategies: self.strategies.clone(),
+            parameters: Default::default(),
+            neural: self.neural.iter().cloned().collect(),
+            num_games: self.num_games,
+            seed: 0,
+            simulator: None,
+            tournament: None,
+            outputs: Vec::new(),
+            games: None,
+            store: None,
+        }
+    }
+
+    // The registry strategies and `--neural` models named by `--strategies`, or all of them.
+    fn strategies(&self) -> Result<Vec<Box<dyn Strategy>>, CliError> {
+        self.check_names()?;
+        Ok(self.experiment().strategies()?)
+    }
+
+    // Reports an unknown name with its own exit code before the config is validated.
+    fn check_names(&self) -> Result<(), CliError> {
+        let known = |name: &String| self.neural.iter().any(|(n, _)| n == name) || name.trim().eq_ignore_ascii_case(OPPONENT_MODEL) || create_strategy(name).is_some();
+        match self.strategies.iter().find(|name| !known(name)) {
+            Some(name) => Err(CliError::Strategy(format!("unknown strategy '{}'; run `list-strategies` for the available names", name))),
+            None => Ok(()),
+        }
+    }
+}
+
+fn simulate(args: SimulateArgs) -> Result<(), CliError> {
+    let simulator_type: SimulatorType = args.simulator.parse().map_err(CliError::Config)?;
+    let mut experiment = args.game.experiment();
+    experiment.simulator = Some(simulator_type);
+    experiment.seed = args.seed;
+    experiment.games = args.games.map(|path| OutputSpec { path, format: None });
+    experiment.store = args.store.map(|path| StoreSpec { path, games: args.store_games });
+    args.game.check_names()?;
+    let strategies = experiment.validate()?;
+
+    let reports = run_pairings(&experiment, strategies, &args.limits)?;
+    write_output(args.output.output.as_deref(), args.output.format(), &reports, &summaries(&reports))
+}
+
+// When per-game rows are wanted, in a file or the results database, every pairing is played by
+// `record_games` and all of their rows go to the same sinks.
+fn run_pairings(experiment: &ExperimentConfig, strategies: Vec<Box<dyn Strategy>>, limits: &LimitArgs) -> Result<Vec<SimulationReport>, CliError> {
+    #[cfg(feature = "sqlite")]
+    let store = begin_store(experiment)?;
+    let mut games: Vec<Box<dyn RecordSink + '_>> = Vec::new();
+    if let Some(output) = &experiment.games {
+        games.push(create_sink(&output.path, output.format().unwrap())?);
+    }
+    #[cfg(feature = "sqlite")]
+    if let Some((store, id)) = store.as_ref().filter(|_| experiment.store.as_ref().is_some_and(|s| s.games)) {
+        games.push(Box::new(store.game_sink(*id)));
+    }
+
+    let mut reports = Vec::new();
+    for config in experiment.pairings_of(strategies) {
+        let label = format!("{} vs {}", config.strategies[0].name(), config.strategies[1].name());
+        let control = limits.control(label);
+        let report = if games.is_empty() {
+            simulation::run_simulation(config, &control)?
+        } else {
+            let start = Instant::now();
+            let stats = record_games(&config, &control, &mut games)?;
+            let mut report = SimulationReport::new("Recorder", &config, &stats, start.elapsed());
+            report.stop_reason = control.stop_reason();
+            report
+        };
+        #[cfg(feature = "sqlite")]
+        if let Some((store, id)) = &store {
+            store.add_report(*id, reports.len(), &report)?;
+        }
+        reports.push(report);
+    }
+    games.finish()?;
+    #[cfg(feature = "sqlite")]
+    if let Some((store, id)) = &store {
+        store.finish(*id, reports.iter().find_map(|r| r.stop_reason))?;
+    }
+    Ok(reports)
+}
+
+// Opens the experiment's results database and adds its row.
+#[cfg(feature = "sqlite")]
+fn begin_store(experiment: &ExperimentConfig) -> Result<Option<(ResultStore, i64)>, CliError> {
+    match &experiment.store {
+        Some(spec) => {
+            let store = ResultStore::open(&spec.path)?;
+            let id = store.begin(experiment)?;
+            Ok(Some((store, id)))
+        },
+        None => Ok(None),
+    }
+}
+
+fn summaries(reports: &[SimulationReport]) -> Vec<PairingSummary<'_>> {
+    reports.iter().map(PairingSummary).collect()
+}
+
+impl LimitArgs {
+    fn control(&self, label: String) -> SimulationControl {
+        let mut control = SimulationControl::new();
+        if let Some(seconds) = self.time_limit {
+            control = control.with_time_limit(Duration::from_secs_f64(seconds));
+        }
+        if self.progress {
+            control = control.on_progress(move |p| {
+                eprintln!("{}: {}/{} games, {:.0} games/s", label, p.games_done, p.total_games, p.games_per_second);
+            });
+        }
+        control
+    }
+}
+
+fn tournament_spec(args: &TournamentArgs, strategies: &[Box<dyn Strategy>]) -> Result<TournamentSpec, CliError> {
+    Ok(match args.format {
+        TournamentFormat::RoundRobin => TournamentSpec::RoundRobin,
+        TournamentFormat::Swiss => TournamentSpec::Swiss { rounds: args.rounds },
+        TournamentFormat::SingleElimination => TournamentSpec::SingleElimination { best_of: args.best_of },
+        TournamentFormat::DoubleElimination => TournamentSpec::DoubleElimination { best_of: args.best_of },
+        TournamentFormat::Gauntlet => {
+            let candidate = args.candidate.as_deref()
+                .ok_or_else(|| CliError::Config("a gauntlet needs --candidate".to_string()))?;
+            let candidate = strategies.iter()
+                .find(|s| s.name().eq_ignore_ascii_case(candidate))
+                .ok_or_else(|| CliError::Strategy(format!("gauntlet candidate '{}' is not in the field", candidate)))?;
+            TournamentSpec::Gauntlet { candidate: candidate.name().to_string() }
+        },
+    })
+}
+
+fn run_tournament(experiment: &ExperimentConfig, spec: &TournamentSpec, strategies: &[Box<dyn Strategy>]) -> Result<TournamentResult, CliError> {
+    #[cfg(feature = "sqlite")]
+    let store = begin_store(experiment)?;
+    let result = spec.run(experiment, strategies)?;
+    #[cfg(feature = "sqlite")]
+    if let Some((store, id)) = &store {
+        store.add_tournament(*id, &result)?;
+        store.finish(*id, None)?;
+    }
+    Ok(result)
+}
+
+fn standings(result: &TournamentResult) -> Vec<RankedStanding<'_>> {
+    result.standings.iter().enumerate().map(|(i, s)| RankedStanding(i + 1, s)).collect()
+}
+
+fn tournament(args: TournamentArgs) -> Result<(), CliError> {
+    let strategies = args.game.strategies()?;
+    let mut experiment = args.game.experiment();
+    experiment.tournament = Some(tournament_spec(&args, &strategies)?);
+    experiment.seed = args.seed;
+    experiment.store = args.store.clone().map(|path| StoreSpec { path, games: false });
+    let strategies = experiment.validate()?;
+    let result = run_tournament(&experiment, experiment.tournament.as_ref().unwrap(), &strategies)?;
+    let (path, format) = (args.output.output.as_deref(), args.output.format());
+
+    if args.win_rates {
+        let win_rates = result.win_rates();
+        let rows: Vec<WinRate> = win_rates.iter()
+            .flat_map(|(strategy, opponents)| opponents.iter().map(move |(opponent, &rate)| WinRate(strategy, opponent, rate)))
+            .collect();
+        return write_output(path, format, &win_rates, &rows);
+    }
+    write_output(path, format, &result, &standings(&result))
+}
+
+struct WinRate<'a>(&'a str, &'a str, f64);
+
+impl Record for WinRate<'_> {
+    fn columns(&self) -> Vec<Column> {
+        vec![Column::text("strategy"), Column::text("opponent"), Column::float("win_rate")]
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        vec![Field::Text(self.0.to_string()), Field::Text(self.1.to_string()), Field::Float(self.2)]
+    }
+}
+
+// Runs every output of the experiment, or prints JSON to stdout when it lists none.
+fn run(args: RunArgs) -> Result<(), CliError> {
+    let experiment: ExperimentConfig = read_file(&args.config)?;
+    let strategies = experiment.validate().map_err(|e| e.in_file(&args.config))?;
+    if args.check {
+        eprintln!("{}: ok", args.config.display());
+        return Ok(());
+    }
+
+    let outputs: Vec<(Option<&Path>, OutputFormat)> = if experiment.outputs.is_empty() {
+        vec![(None, OutputFormat::Json)]
+    } else {
+        experiment.outputs.iter().map(|o| (Some(o.path.as_path()), o.format().unwrap())).collect()
+    };
+
+    match &experiment.tournament {
+        Some(spec) => {
+            let result = run_tournament(&experiment, spec, &strategies)?;
+            for (path, format) in outputs {
+                write_output(path, format, &result, &standings(&result))?;
+            }
+        },
+        None => {
+            let reports = run_pairings(&experiment, strategies, &args.limits)?;
+            for (path, format) in outputs {
+                write_output(path, format, &reports, &summaries(&reports))?;
+            }
+        },
+    }
+    Ok(())
+}
+
+fn sweep(args: RunArgs) -> Result<(), CliError> {
+    let sweep = SweepConfig::load(&args.config)?;
+    if args.check {
+        let cells = sweep.cells()?;
+        eprintln!("{}: ok, {} cells", args.config.display(), cells.len());
+        return Ok(());
+    }
+
+    let control = args.limits.control("sweep".to_string());
+    let rows = sweep.run(&control)?;
+    if let Some(reason) = control.stop_reason() {
+        eprintln!("warning: sweep stopped early ({:?}); only complete cells are written", reason);
+    }
+    if sweep.outputs.is_empty() {
+        return write_output(None, OutputFormat::Json, &rows, &rows);
+    }
+    for output in &sweep.outputs {
+        write_output(Some(&output.path), output.format().unwrap(), &rows, &rows)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "sqlite")]
+fn query(args: QueryArgs) -> Result<(), CliError> {
+    // Opening would create an empty database.
+    if !args.database.is_file() {
+        return Err(CliError::Config(format!("no results database at '{}'", args.database.display())));
+    }
+    let store = ResultStore::open(&args.database)?;
+    match args.command {
+        QueryCommand::List { name, limit, output } => {
+            let experiments = store.experiments(name.as_deref(), limit)?;
+            write_output(output.output.as_deref(), output.format(), &experiments, &experiments)
+        },
+        QueryCommand::Show { id, output } => {
+            let details = store.experiment(id)?
+                .ok_or_else(|| CliError::Config(format!("no experiment {} in '{}'", id, args.database.display())))?;
+            write_output(output.output.as_deref(), output.format(), &details, &details.pairings)
+        },
+        QueryCommand::Compare { ids, output } => {
+            let rows = store.compare(&ids)?;
+            if let Some(id) = ids.iter().find(|&&id| !rows.iter().any(|row| row.experiment == id)) {
+                return Err(CliError::Config(format!("no results for experiment {} in '{}'", id, args.database.display())));
+            }
+            write_output(output.output.as_deref(), output.format(), &rows, &rows)
+        },
+    }
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn query(_: QueryArgs) -> Result<(), CliError> {
+    Err(CliError::Config("the results database is not available in this build; rebuild with `--features sqlite`".to_string()))
+}
+
+// `solve --input` accepts either a full tournament result or the legacy win-rate table.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SolveInput {
+    Tournament(TournamentResult),
+    WinRates(HashMap<String, HashMap<String, f64>>),
+}
+
+fn solve(args: SolveArgs) -> Result<(), CliError> {
+    let matrix = match &args.input {
+        Some(path) => {
+            let input: SolveInput = serde_json::from_reader(BufReader::new(File::open(path)?))
+                .map_err(|e| CliError::Config(format!("{}: not a tournament result or win-rate table: {}", path.display(), e)))?;
+            match input {
+                SolveInput::Tournament(result) => PayoffMatrix::from_tournament(&result),
+                SolveInput::WinRates(win_rates) => PayoffMatrix::from_win_rates(&win_rates),
+            }
+        },
+        None => {
+            let strategies = args.game.strategies()?;
+            let result = RoundRobin { games_per_match: args.game.num_games }
+                .play(args.game.mode, args.game.optimization, &strategies, args.seed);
+            PayoffMatrix::from_tournament(&result)
+        },
+    };
+    if matrix.len() < 2 {
+        return Err(CliError::Config("the payoff matrix needs at least two strategies".to_string()));
+    }
+
+    let report: MetaReport = matrix.analyze();
+    let rows: Vec<EquilibriumWeight> = report.equilibrium.iter()
+        .map(|(name, weight)| EquilibriumWeight(name, *weight, report.dominated.iter().any(|d| &d.dominated == name)))
+        .collect();
+    write_output(args.output.output.as_deref(), args.output.format(), &report, &rows)
+}
+
+// A strategy's weight in the equilibrium and whether another strategy dominates it.
+struct EquilibriumWeight<'a>(&'a str, f64, bool);
+
+impl Record for EquilibriumWeight<'_> {
+    fn columns(&self) -> Vec<Column> {
+        vec![Column::text("strategy"), Column::float("equilibrium_weight"), Column::text("dominated")]
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        vec![Field::Text(self.0.to_string()), Field::Float(self.1), Field::Text(self.2.to_string())]
+    }
+}
+
+#[derive(Serialize)]
+struct Turn {
+    player: usize,
+    strategy: String,
+    points: Option<u32>,
+    moves: u32,
+}
+
+#[derive(Serialize)]
+struct Replay {
+    seed: u64,
+    game: u64,
+    turns: Vec<Turn>,
+    winner: String,
+    scores: [u32; 2],
+    num_rounds: u8,
+}
+
+struct TurnLog<'a> {
+    names: [&'a str; 2],
+    turns: Vec<Turn>,
+}
+
+impl TurnObserver for TurnLog<'_> {
+    fn end_turn(&mut self, player: usize, points: Option<u32>, moves: u32) {
+        self.turns.push(Turn { player, strategy: self.names[player].to_string(), points, moves });
+    }
+}
+
+fn replay(args: ReplayArgs) -> Result<(), CliError> {
+    let Ok(mut strategies) = <[Box<dyn Strategy>; 2]>::try_from(resolve_strategies(&args.strategies)?) else {
+        return Err(CliError::Config("replay needs exactly two strategies".to_string()));
+    };
+
+    let mut game = Game::new(args.mode, args.optimization, 0);
+    game.reset(game_seed(args.seed, args.game));
+    let mut log = TurnLog { names: [strategies[0].name(), strategies[1].name()], turns: Vec::new() };
+    let [first, second] = &mut strategies;
+    let outcome = game.play_pair_observed([first.as_mut(), second.as_mut()], &mut log);
+
+    let replay = Replay {
+        seed: args.seed,
+        game: args.game,
+        turns: log.turns,
+        winner: strategies[outcome.winner].name().to_string(),
+        scores: outcome.scores,
+        num_rounds: outcome.num_rounds,
+    };
+    let rows: Vec<NumberedTurn> = replay.turns.iter().enumerate().map(|(i, turn)| NumberedTurn(i, turn)).collect();
+    write_output(args.output.output.as_deref(), args.output.format(), &replay, &rows)
+}
+
+struct NumberedTurn<'a>(usize, &'a Turn);
+
+impl Record for NumberedTurn<'_> {
+    fn columns(&self) -> Vec<Column> {
+        vec![Column::int("turn"), Column::int("player"), Column::text("strategy"), Column::int("points"), Column::int("moves")]
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        let NumberedTurn(i, turn) = *self;
+        vec![
+            Field::Int(i as i64),
+            Field::Int(turn.player as i64),
+            Field::Text(turn.strategy.clone()),
+            turn.points.map_or(Field::Null, |p| Field::Int(p as i64)),
+            Field::Int(turn.moves as i64),
+        ]
+    }
+}
+
+fn train(args: TrainArgs) -> Result<(), CliError> {
+    if !(args.alpha > 0.0 && args.alpha <= 1.0) {
+        return Err(CliError::Config(format!("--alpha must be in (0, 1], got {}", args.alpha)));
+    }
+    if !(0.0..=1.0).contains(&args.epsilon) || !(0.0..=1.0).contains(&args.lambda) {
+        return Err(CliError::Config("--epsilon and --lambda must be between 0 and 1".to_string()));
+    }
+    let config = TrainingConfig {
+        episodes: args.episodes,
+        alpha: args.alpha,
+        epsilon: args.epsilon,
+        lambda: args.lambda,
+        max_moves: args.max_moves,
+        seed: args.seed,
+        ..TrainingConfig::new(args.mode, args.algorithm)
+    };
+    let mut trainer = match &args.resume {
+        Some(path) => Trainer::resume(config, QTable::load(path)?),
+        None => Trainer::new(config),
+    };
+    trainer.train();
+    if let Some(path) = &args.table {
+        trainer.table.save(path)?;
+    }
+
+    let solution = Solution::solve(trainer.config.mode, trainer.config.rules);
+    let report = trainer.report(&solution);
+    write_output(args.output.output.as_deref(), args.output.format(), &report, &[TrainingRow(&report)])
+}
+
+struct TrainingRow<'a>(&'a TrainingReport);
+
+impl Record for TrainingRow<'_> {
+    fn columns(&self) -> Vec<Column> {
+        vec![
+            Column::text("algorithm"), Column::int("episodes"), Column::int("states"), Column::float("agreement"),
+            Column::float("value_error"), Column::float("learned_value"), Column::float("policy_value"),
+            Column::float("exact_value"), Column::float("gap"),
+        ]
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        let report = self.0;
+        vec![
+            Field::Text(format!("{:?}", report.algorithm)),
+            Field::Int(report.episodes as i64),
+            Field::Int(report.states as i64),
+            Field::Float(report.agreement),
+            Field::Float(report.value_error),
+            Field::Float(report.learned_value),
+            Field::Float(report.policy_value),
+            Field::Float(report.exact_value),
+            Field::Float(report.gap),
+        ]
+    }
+}
+
+fn cfr(args: CfrArgs) -> Result<(), CliError> {
+    if args.iterations == 0 {
+        return Err(CliError::Config("--iterations must be at least 1".to_string()));
+    }
+    let config = CfrConfig {
+        iterations: args.iterations,
+        report_every: args.report_every,
+        ..CfrConfig::new(args.variant)
+    };
+    let solution = Solution::solve(GameMode::Extended, config.rules);
+    let mut trainer = CfrTrainer::new(config);
+    let mut reports = Vec::new();
+    trainer.train(&solution, |report| reports.push(report));
+    if let Some(path) = &args.policy {
+        trainer.average_policy().save(path)?;
+    }
+
+    let rows: Vec<CfrRow> = reports.iter().map(CfrRow).collect();
+    write_output(args.output.output.as_deref(), args.output.format(), &reports, &rows)
+}
+
+struct CfrRow<'a>(&'a CfrReport);
+
+impl Record for CfrRow<'_> {
+    fn columns(&self) -> Vec<Column> {
+        vec![
+            Column::text("variant"), Column::int("iteration"), Column::float("first_seat_value"),
+            Column::float("second_seat_value"), Column::float("exact_value"), Column::float("exploitability"),
+        ]
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        let report = self.0;
+        vec![
+            Field::Text(format!("{:?}", report.variant)),
+            Field::Int(report.iteration as i64),
+            Field::Float(report.seat_values[0]),
+            Field::Float(report.seat_values[1]),
+            Field::Float(report.exact_value),
+            Field::Float(report.exploitability),
+        ]
+    }
+}
+
+fn list_strategies(args: OutputArgs) -> Result<(), CliError> {
+    let names = strategy_names();
+    if args.output.is_none() && args.output_format.is_none() {
+        // `println!` panics when stdout is closed early, as by `dice_game list-strategies | head -1`.
+        let mut stdout = io::stdout().lock();
+        for name in &names {
+            match writeln!(stdout, "{}", name) {
+                Ok(()) => {},
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        return Ok(());
+    }
+    let rows: Vec<StrategyName> = names.iter().map(|name| StrategyName(name)).collect();
+    write_output(args.output.as_deref(), args.format(), &names, &rows)
+}
+
+struct StrategyName<'a>(&'a str);
+
+impl Record for StrategyName<'_> {
+    fn columns(&self) -> Vec<Column> {
+        vec![Column::text("strategy")]
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        vec![Field::Text(self.0.to_string())]
+    }
+}
+
+impl OutputArgs {
+    fn format(&self) -> OutputFormat {
+        self.output_format
+            .or_else(|| self.output.as_deref().and_then(OutputFormat::from_path))
+            .unwrap_or(OutputFormat::Json)
+    }
+}
+
+// Writes `value` as pretty JSON, or `records` through a sink for the tabular formats, to
+// `path` or stdout.
+fn write_output<T: Serialize, R: Record>(path: Option<&Path>, format: OutputFormat, value: &T, records: &[R]) -> Result<(), CliError> {
+    if !format.is_tabular() {
+        let mut writer: Box<dyn Write> = match path {
+            Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+            None => Box::new(io::stdout().lock()),
+        };
+        serde_json::to_writer_pretty(&mut writer, value)?;
+        writeln!(writer)?;
+        writer.flush()?;
+        return Ok(());
+    }
+
+    let mut sink = open_sink(path, format)?;
+    for record in records {
+        sink.write(record)?;
+    }
+    sink.finish()?;
+    Ok(())
+}
+
+fn open_sink(path: Option<&Path>, format: OutputFormat) -> Result<Box<dyn RecordSink>, CliError> {
+    if let Some(path) = path {
+        return Ok(create_sink(path, format)?);
+    }
+    let stdout = io::stdout().lock();
+    Ok(match format {
+        OutputFormat::Csv => Box::new(CsvSink::new(stdout)),
+        OutputFormat::Json => Box::new(JsonSink::array(stdout)),
+        OutputFormat::Jsonl => Box::new(JsonSink::lines(stdout)),
+        #[cfg(feature = "parquet")]
+        OutputFormat::Parquet => return Err(CliError::Config("parquet output needs --output".to_string())),
+    })
+}
@@ -0,0 +1,304 @@
+// File: src/meta.rs
+
+use crate::tournament::TournamentResult;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+
+const EPSILON: f64 = 1e-9;
+
+// Zero-sum payoff matrix: `payoffs[i][j]` is what strategy i gains against strategy j,
+// taken as its win rate minus the opponent's win rate in that pairing.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PayoffMatrix {
+    pub strategies: Vec<String>,
+    pub payoffs: Vec<Vec<f64>>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Domination {
+    pub dominated: String,
+    pub dominated_by: String,
+    pub strict: bool,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MetaReport {
+    pub equilibrium: Vec<(String, f64)>,
+    pub value: f64,
+    pub exploitability: f64,
+    pub best_response: String,
+    pub dominated: Vec<Domination>,
+    pub surviving: Vec<String>,
+}
+
+impl PayoffMatrix {
+    pub fn from_win_rates(win_rates: &HashMap<String, HashMap<String, f64>>) -> Self {
+        let mut strategies: Vec<String> = win_rates.keys().cloned().collect();
+        strategies.sort();
+
+        let rate = |a: &String, b: &String| win_rates.get(a).and_then(|row| row.get(b)).copied().unwrap_or(0.0);
+        let payoffs = strategies.iter()
+            .map(|a| strategies.iter()
+                .map(|b| if a == b { 0.0 } else { rate(a, b) - rate(b, a) })
+                .collect())
+            .collect();
+
+        PayoffMatrix { strategies, payoffs }
+    }
+
+    pub fn from_tournament(result: &TournamentResult) -> Self {
+        Self::from_win_rates(&result.win_rates())
+    }
+
+    pub fn len(&self) -> usize {
+        self.strategies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strategies.is_empty()
+    }
+
+    // Expected payoff of every pure strategy j for the column player facing `mixture`, from the row player's view.
+    fn column_payoffs(&self, mixture: &[f64]) -> Vec<f64> {
+        (0..self.len())
+            .map(|j| (0..self.len()).map(|i| mixture[i] * self.payoffs[i][j]).sum())
+            .collect()
+    }
+
+    // Solves max_x min_j sum_i x_i a_ij for the row player.
+    // Returns the equilibrium mixture and the value of the game.
+    pub fn solve_nash(&self) -> (Vec<f64>, f64) {
+        let n = self.len();
+        if n == 0 {
+            return (Vec::new(), 0.0);
+        }
+
+        // Shift every payoff above zero so the LP below has a positive value.
+        let min_payoff = self.payoffs.iter().flatten().cloned().fold(f64::INFINITY, f64::min);
+        let shift = 1.0 - min_payoff.min(0.0);
+        let shifted: Vec<Vec<f64>> = self.payoffs.iter()
+            .map(|row| row.iter().map(|a| a + shift).collect())
+            .collect();
+
+        // Column player's LP: maximise sum u subject to A u <= 1, u >= 0.
+        // The row player's mixture is read off the dual values of the slack columns.
+        let dual = simplex_max(&shifted);
+        let total: f64 = dual.iter().sum();
+        let value = 1.0 / total - shift;
+        let mixture = dual.iter().map(|y| y / total).collect();
+        (mixture, value)
+    }
+
+    // How much a best-responding opponent gains against `mixture` beyond the game value.
+    pub fn exploitability(&self, mixture: &[f64]) -> f64 {
+        let (_, value) = self.solve_nash();
+        let worst = self.column_payoffs(mixture).into_iter().fold(f64::INFINITY, f64::min);
+        (value - worst).max(0.0)
+    }
+
+    pub fn best_response(&self, mixture: &[f64]) -> usize {
+        let payoffs = self.column_payoffs(mixture);
+        (0..self.len())
+            .min_by(|&a, &b| payoffs[a].partial_cmp(&payoffs[b]).unwrap())
+            .unwrap()
+    }
+
+    // Iterated elimination of pure strategies dominated by another pure strategy.
+    // Strictly dominated strategies are removed first; weak domination is only reported.
+    pub fn dominated_strategies(&self) -> (Vec<Domination>, Vec<String>) {
+        let mut alive: Vec<usize> = (0..self.len()).collect();
+        let mut dominated = Vec::new();
+
+        loop {
+            let mut removed = None;
+            'search: for &i in &alive {
+                for &k in &alive {
+                    if k != i && alive.iter().all(|&j| self.payoffs[k][j] > self.payoffs[i][j] + EPSILON) {
+                        removed = Some((i, k));
+                        break 'search;
+                    }
+                }
+            }
+            match removed {
+                Some((i, k)) => {
+                    dominated.push(Domination {
+                        dominated: self.strategies[i].clone(),
+                        dominated_by: self.strategies[k].clone(),
+                        strict: true,
+                    });
+                    alive.retain(|&x| x != i);
+                },
+                None => break,
+            }
+        }
+
+        for &i in &alive {
+            for &k in &alive {
+                if k != i
+                    && alive.iter().all(|&j| self.payoffs[k][j] >= self.payoffs[i][j] - EPSILON)
+                    && alive.iter().any(|&j| self.payoffs[k][j] > self.payoffs[i][j] + EPSILON)
+                {
+                    dominated.push(Domination {
+                        dominated: self.strategies[i].clone(),
+                        dominated_by: self.strategies[k].clone(),
+                        strict: false,
+                    });
+                    break;
+                }
+            }
+        }
+
+        let surviving = alive.iter().map(|&i| self.strategies[i].clone()).collect();
+        (dominated, surviving)
+    }
+
+    pub fn analyze(&self) -> MetaReport {
+        let (mixture, value) = self.solve_nash();
+        let (dominated, surviving) = self.dominated_strategies();
+        let worst = self.column_payoffs(&mixture).into_iter().fold(f64::INFINITY, f64::min);
+        let best_response = if self.is_empty() { String::new() } else { self.strategies[self.best_response(&mixture)].clone() };
+
+        MetaReport {
+            equilibrium: self.strategies.iter().cloned().zip(mixture).collect(),
+            value,
+            exploitability: (value - worst).max(0.0),
+            best_response,
+            dominated,
+            surviving,
+        }
+    }
+}
+
+// Dense-tableau simplex for max 1^T u subject to A u <= 1, u >= 0, using Bland's rule.
+// Returns the dual solution, one value per row of A.
+fn simplex_max(a: &[Vec<f64>]) -> Vec<f64> {
+    let rows = a.len();
+    let cols = a[0].len();
+    let width = cols + rows + 1;
+
+    let mut tableau = vec![vec![0.0; width]; rows + 1];
+    for i in 0..rows {
+        tableau[i][..cols].copy_from_slice(&a[i]);
+        tableau[i][cols + i] = 1.0;
+        tableau[i][width - 1] = 1.0;
+    }
+    tableau[rows][..cols].fill(-1.0);
+    let mut basis: Vec<usize> = (cols..cols + rows).collect();
+
+    while let Some(entering) = (0..width - 1).find(|&j| tableau[rows][j] < -EPSILON) {
+        let mut leaving = None;
+        let mut best_ratio = f64::INFINITY;
+        for i in 0..rows {
+            if tableau[i][entering] > EPSILON {
+                let ratio = tableau[i][width - 1] / tableau[i][entering];
+                let better = ratio < best_ratio - EPSILON
+                    || (ratio < best_ratio + EPSILON && leaving.is_none_or(|l: usize| basis[i] < basis[l]));
+                if better {
+                    best_ratio = ratio;
+                    leaving = Some(i);
+                }
+            }
+        }
+        // A u <= 1 with positive A is always bounded.
+        let pivot_row = leaving.expect("payoff LP is unbounded");
+
+        let pivot = tableau[pivot_row][entering];
+        for value in tableau[pivot_row].iter_mut() {
+            *value /= pivot;
+        }
+        let pivot_values = tableau[pivot_row].clone();
+        for (i, row) in tableau.iter_mut().enumerate() {
+            if i != pivot_row {
+                let factor = row[entering];
+                if factor.abs() > EPSILON {
+                    for (value, pivot_value) in row.iter_mut().zip(&pivot_values) {
+                        *value -= factor * pivot_value;
+                    }
+                }
+            }
+        }
+        basis[pivot_row] = entering;
+    }
+
+    (0..rows).map(|i| tableau[rows][cols + i].max(0.0)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(payoffs: Vec<Vec<f64>>) -> PayoffMatrix {
+        let strategies = (0..payoffs.len()).map(|i| format!("S{}", i)).collect();
+        PayoffMatrix { strategies, payoffs }
+    }
+
+    // The best worst-case payoff over a grid of row mixtures of a 3x3 game.
+    fn grid_value(m: &PayoffMatrix) -> f64 {
+        let steps = 200;
+        let mut best = f64::NEG_INFINITY;
+        for i in 0..=steps {
+            for j in 0..=steps - i {
+                let x = [i as f64 / steps as f64, j as f64 / steps as f64, (steps - i - j) as f64 / steps as f64];
+                best = best.max(m.column_payoffs(&x).into_iter().fold(f64::INFINITY, f64::min));
+            }
+        }
+        best
+    }
+
+    #[test]
+    fn rock_paper_scissors_is_uniform() {
+        let m = matrix(vec![vec![0.0, -1.0, 1.0], vec![1.0, 0.0, -1.0], vec![-1.0, 1.0, 0.0]]);
+        let (mixture, value) = m.solve_nash();
+        assert!(mixture.iter().all(|x| (x - 1.0 / 3.0).abs() < 1e-9));
+        assert!(value.abs() < 1e-9);
+        assert!(m.exploitability(&mixture) < 1e-9);
+        assert!((m.exploitability(&[1.0, 0.0, 0.0]) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn two_by_two_mixed_equilibrium() {
+        let m = matrix(vec![vec![3.0, -1.0], vec![-2.0, 1.0]]);
+        let (mixture, value) = m.solve_nash();
+        assert!((mixture[0] - 3.0 / 7.0).abs() < 1e-9);
+        assert!((value - 1.0 / 7.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn value_matches_a_grid_search() {
+        for payoffs in [
+            vec![vec![0.2, -0.5, 0.3], vec![-0.1, 0.4, -0.6], vec![0.5, -0.2, 0.1]],
+            vec![vec![0.0, 0.3, -0.2], vec![-0.3, 0.0, 0.6], vec![0.2, -0.6, 0.0]],
+            vec![vec![1.0, 0.5, 0.8], vec![0.2, 0.9, 0.4], vec![0.6, 0.1, 0.3]],
+        ] {
+            let m = matrix(payoffs);
+            let (mixture, value) = m.solve_nash();
+            assert!((mixture.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+            assert!(mixture.iter().all(|&x| x >= 0.0));
+            // The mixture guarantees the value, and no mixture on the grid does noticeably better.
+            assert!(m.column_payoffs(&mixture).iter().all(|&p| p >= value - 1e-9));
+            assert!((grid_value(&m) - value).abs() < 0.01, "{} vs {}", grid_value(&m), value);
+        }
+    }
+
+    #[test]
+    fn dominated_strategies_are_eliminated() {
+        // S3 is strictly dominated by S0, and S2 ties S0 against S3, so it is only strictly
+        // dominated once S3 is gone. S0 and S1 tie against S0, so S1 is weakly dominated and survives.
+        let m = matrix(vec![
+            vec![0.0, 0.2, 0.1, 0.9],
+            vec![0.0, 0.0, 0.3, 0.8],
+            vec![-0.1, -0.3, 0.0, 0.9],
+            vec![-0.9, -0.8, -0.9, 0.0],
+        ]);
+        let (dominated, surviving) = m.dominated_strategies();
+        let pairs: Vec<(&str, &str, bool)> = dominated.iter()
+            .map(|d| (d.dominated.as_str(), d.dominated_by.as_str(), d.strict))
+            .collect();
+        assert_eq!(pairs, [("S3", "S0", true), ("S2", "S0", true), ("S1", "S0", false)]);
+        assert_eq!(surviving, ["S0", "S1"]);
+    }
+}
@@ -0,0 +1,327 @@
+// File: src/neural.rs
+
+use crate::{intern, GameMode, GameState, Strategy};
+use crate::game::{legal_moves, GameRules, MAX_BOARD_SIZE};
+use crate::solver::{Model, Position, MAX_ROLL, MIN_ROLL};
+use serde::{Serialize, Deserialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+// Feature blocks, concatenated in the order the model file lists them:
+// `board` is one 0/1 value per tile (1 when open), `roll` a one-hot of the 11 rolls 2..=12,
+// `score` the mover's points so far and `round` the round counter, both raw.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Input {
+    Board,
+    Roll,
+    Score,
+    Round,
+}
+
+// `moves` has one logit per tile bitmask, the action space of `env::Env`. `tiles` has one per
+// tile, optionally followed by one for passing; a move scores the sum of its tiles' logits and
+// passing scores 0 without the extra output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Output {
+    Moves,
+    Tiles,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Activation {
+    #[default]
+    Linear,
+    Relu,
+    Tanh,
+    Sigmoid,
+}
+
+impl Activation {
+    #[inline]
+    fn apply(self, x: f32) -> f32 {
+        match self {
+            Activation::Linear => x,
+            Activation::Relu => x.max(0.0),
+            Activation::Tanh => x.tanh(),
+            Activation::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+        }
+    }
+}
+
+// A dense layer as exported from PyTorch's `nn.Linear`: `weights` is `out × in`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Layer {
+    pub weights: Vec<Vec<f32>>,
+    pub bias: Vec<f32>,
+    #[serde(default)]
+    pub activation: Activation,
+}
+
+fn default_board_size() -> u8 {
+    MAX_BOARD_SIZE
+}
+
+fn default_inputs() -> Vec<Input> {
+    vec![Input::Board, Input::Roll, Input::Score, Input::Round]
+}
+
+// The model file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NetworkSpec {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default = "default_board_size")]
+    pub board_size: u8,
+    #[serde(default = "default_inputs")]
+    pub inputs: Vec<Input>,
+    pub output: Output,
+    pub layers: Vec<Layer>,
+}
+
+impl NetworkSpec {
+    pub fn input_size(&self) -> usize {
+        self.inputs.iter().map(|input| match input {
+            Input::Board => self.board_size as usize,
+            Input::Roll => (MAX_ROLL - MIN_ROLL + 1) as usize,
+            Input::Score | Input::Round => 1,
+        }).sum()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=MAX_BOARD_SIZE).contains(&self.board_size) {
+            return Err(format!("board_size must be between 1 and {}, got {}", MAX_BOARD_SIZE, self.board_size));
+        }
+        if self.layers.is_empty() {
+            return Err("the model has no layers".to_string());
+        }
+        let mut width = self.input_size();
+        for (i, layer) in self.layers.iter().enumerate() {
+            if layer.weights.len() != layer.bias.len() {
+                return Err(format!("layer {}: {} rows of weights but {} biases", i, layer.weights.len(), layer.bias.len()));
+            }
+            if let Some(row) = layer.weights.iter().find(|row| row.len() != width) {
+                return Err(format!("layer {}: expected {} inputs per row, got {}", i, width, row.len()));
+            }
+            width = layer.bias.len();
+        }
+        let tiles = self.board_size as usize;
+        match self.output {
+            Output::Moves if width != 1 << tiles => Err(format!("a `moves` model needs {} outputs, got {}", 1 << tiles, width)),
+            Output::Tiles if width != tiles && width != tiles + 1 => Err(format!("a `tiles` model needs {} or {} outputs, got {}", tiles, tiles + 1, width)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct DenseLayer {
+    inputs: usize,
+    weights: Vec<f32>,
+    bias: Vec<f32>,
+    activation: Activation,
+}
+
+// A small MLP evaluated in plain Rust, with the weights flattened row by row.
+#[derive(Clone)]
+pub struct Network {
+    pub inputs: Vec<Input>,
+    pub output: Output,
+    pub board_size: u8,
+    layers: Vec<DenseLayer>,
+}
+
+impl Network {
+    pub fn new(spec: NetworkSpec) -> Result<Self, String> {
+        spec.validate()?;
+        let layers = spec.layers.into_iter().map(|layer| DenseLayer {
+            inputs: layer.weights.first().map_or(0, Vec::len),
+            weights: layer.weights.concat(),
+            bias: layer.bias,
+            activation: layer.activation,
+        }).collect();
+        Ok(Network { inputs: spec.inputs, output: spec.output, board_size: spec.board_size, layers })
+    }
+
+    pub fn encode(&self, state: &GameState, roll: u8) -> Vec<f32> {
+        let mut features = Vec::new();
+        for input in &self.inputs {
+            match input {
+                Input::Board => features.extend((0..self.board_size).map(|i| ((state.board >> i) & 1) as f32)),
+                Input::Roll => features.extend((MIN_ROLL..=MAX_ROLL).map(|r| if r == roll { 1.0 } else { 0.0 })),
+                Input::Score => features.push(state.score as f32),
+                Input::Round => features.push(state.round as f32),
+            }
+        }
+        features
+    }
+
+    pub fn forward(&self, features: &[f32]) -> Vec<f32> {
+        let mut values = features.to_vec();
+        for layer in &self.layers {
+            values = layer.bias.iter().enumerate().map(|(j, &bias)| {
+                let row = &layer.weights[j * layer.inputs..(j + 1) * layer.inputs];
+                layer.activation.apply(bias + row.iter().zip(&values).map(|(w, x)| w * x).sum::<f32>())
+            }).collect();
+        }
+        values
+    }
+
+    fn score(&self, logits: &[f32], move_bits: u16) -> f32 {
+        match self.output {
+            Output::Moves => logits[move_bits as usize],
+            Output::Tiles if move_bits == 0 => logits.get(self.board_size as usize).copied().unwrap_or(0.0),
+            Output::Tiles => (0..self.board_size as usize).filter(|&i| move_bits & (1 << i) != 0).map(|i| logits[i]).sum(),
+        }
+    }
+}
+
+// Plays the legal move (see `game::legal_moves`) of the `movable` tiles with the highest score.
+#[derive(Clone)]
+pub struct NeuralStrategy {
+    pub network: Network,
+    name: &'static str,
+    moves: Model,
+}
+
+impl NeuralStrategy {
+    pub fn new(network: Network, name: &str) -> Self {
+        let rules = GameRules { board_size: network.board_size, ..GameRules::default() };
+        NeuralStrategy { name: intern(name.to_string()), moves: Model::new(GameMode::Base, rules), network }
+    }
+
+    // Plays under the file's `name`, or `name` when given.
+    pub fn load(path: &Path, name: Option<&str>) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let spec: NetworkSpec = serde_json::from_reader(BufReader::new(file))
+            .map_err(|e| format!("{}: not a model file: {}", path.display(), e))?;
+        let name = name.map(str::to_string).or_else(|| spec.name.clone()).unwrap_or_else(|| "Neural".to_string());
+        let network = Network::new(spec).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(NeuralStrategy::new(network, &name))
+    }
+}
+
+impl Strategy for NeuralStrategy {
+    fn choose_move(&mut self, state: &GameState, roll: u8) -> u16 {
+        let logits = self.network.forward(&self.network.encode(state, roll));
+        let board = state.movable & self.moves.full_board;
+        let fallback;
+        let moves = if (MIN_ROLL..=MAX_ROLL).contains(&roll) {
+            self.moves.moves(Position { board, stage: 0 }, roll)
+        } else {
+            fallback = legal_moves(board, roll);
+            &fallback
+        };
+        let mut best = (0, f32::NEG_INFINITY);
+        for &m in moves {
+            let score = self.network.score(&logits, m);
+            if score > best.1 {
+                best = (m, score);
+            }
+        }
+        best.0
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::empty_history;
+    use crate::rng::Xoroshiro128Plus;
+    use rand::Rng;
+
+    fn layer(weights: Vec<Vec<f32>>, bias: Vec<f32>, activation: Activation) -> Layer {
+        Layer { weights, bias, activation }
+    }
+
+    fn spec(board_size: u8, inputs: Vec<Input>, output: Output, layers: Vec<Layer>) -> NetworkSpec {
+        NetworkSpec { name: None, board_size, inputs, output, layers }
+    }
+
+    // A network whose logits are its last layer's biases, whatever the input.
+    fn constant(board_size: u8, output: Output, logits: Vec<f32>) -> NeuralStrategy {
+        let weights = vec![vec![0.0]; logits.len()];
+        let network = Network::new(spec(board_size, vec![Input::Score], output, vec![layer(weights, logits, Activation::Linear)])).unwrap();
+        NeuralStrategy::new(network, "Constant")
+    }
+
+    fn state(board: u16) -> GameState<'static> {
+        GameState { board, movable: board, score: 0, round: 0, player: 0, history: empty_history() }
+    }
+
+    #[test]
+    fn validate_rejects_layers_of_the_wrong_width() {
+        let inputs = vec![Input::Board, Input::Roll];
+        let rows = |count: usize, width: usize| vec![vec![0.0; width]; count];
+        // 3 board tiles and 11 rolls make 14 inputs.
+        assert!(spec(3, inputs.clone(), Output::Tiles, vec![layer(rows(3, 14), vec![0.0; 3], Activation::Linear)]).validate().is_ok());
+        assert!(spec(3, inputs.clone(), Output::Tiles, vec![layer(rows(3, 13), vec![0.0; 3], Activation::Linear)]).validate().is_err());
+        assert!(spec(3, inputs.clone(), Output::Tiles, vec![layer(rows(3, 14), vec![0.0; 2], Activation::Linear)]).validate().is_err());
+        assert!(spec(3, inputs.clone(), Output::Tiles, vec![layer(rows(5, 14), vec![0.0; 5], Activation::Linear)]).validate().is_err());
+        assert!(spec(3, inputs.clone(), Output::Moves, vec![layer(rows(7, 14), vec![0.0; 7], Activation::Linear)]).validate().is_err());
+        assert!(spec(3, inputs.clone(), Output::Moves, vec![
+            layer(rows(4, 14), vec![0.0; 4], Activation::Relu),
+            layer(rows(8, 3), vec![0.0; 8], Activation::Linear),
+        ]).validate().is_err());
+        assert!(spec(3, inputs, Output::Moves, vec![]).validate().is_err());
+    }
+
+    #[test]
+    fn forward_matches_a_hand_computed_network() {
+        let network = Network::new(spec(1, vec![Input::Score, Input::Round], Output::Tiles, vec![
+            layer(vec![vec![1.0, -2.0], vec![0.5, 0.5]], vec![0.0, 1.0], Activation::Relu),
+            layer(vec![vec![2.0, -1.0]], vec![0.5], Activation::Linear),
+        ])).unwrap();
+        // relu(3 - 2) = 1 and relu(1.5 + 0.5 + 1) = 3, then 2 * 1 - 3 + 0.5.
+        assert_eq!(network.forward(&[3.0, 1.0]), vec![-0.5]);
+        // relu(1 - 4) = 0 and relu(0.5 + 1 + 1) = 2.5, then -2.5 + 0.5.
+        assert_eq!(network.forward(&[1.0, 2.0]), vec![-2.0]);
+    }
+
+    #[test]
+    fn moves_outputs_score_each_move_by_its_own_logit() {
+        // With tiles 1 and 2 open a roll of 3 can pass (0b00) or clear both (0b11).
+        assert_eq!(constant(2, Output::Moves, vec![0.0, 9.0, 9.0, 1.0]).choose_move(&state(0b11), 3), 0b11);
+        assert_eq!(constant(2, Output::Moves, vec![5.0, 0.0, 0.0, 1.0]).choose_move(&state(0b11), 3), 0);
+    }
+
+    #[test]
+    fn tiles_outputs_sum_their_tiles_and_may_score_the_pass() {
+        // A roll of 3 on tiles 1 to 3 can pass, clear tile 3 (0b100) or tiles 1 and 2 (0b011).
+        assert_eq!(constant(3, Output::Tiles, vec![1.0, 1.0, -5.0]).choose_move(&state(0b111), 3), 0b011);
+        assert_eq!(constant(3, Output::Tiles, vec![-1.0, -1.0, 3.0]).choose_move(&state(0b111), 3), 0b100);
+        // Without a pass logit passing scores 0.
+        assert_eq!(constant(3, Output::Tiles, vec![-1.0, -1.0, -5.0]).choose_move(&state(0b111), 3), 0);
+        assert_eq!(constant(3, Output::Tiles, vec![1.0, 1.0, -5.0, 10.0]).choose_move(&state(0b111), 3), 0);
+    }
+
+    #[test]
+    fn choose_move_only_plays_legal_moves() {
+        let mut rng = Xoroshiro128Plus::new(7);
+        let mut random = |rows: usize, width: usize| (0..rows).map(|_| (0..width).map(|_| rng.gen_range(-1.0..1.0)).collect()).collect::<Vec<Vec<f32>>>();
+        let inputs = vec![Input::Board, Input::Roll, Input::Score, Input::Round];
+        let hidden = random(16, 25);
+        let tiles = random(13, 16);
+        let network = Network::new(spec(12, inputs, Output::Tiles, vec![
+            layer(hidden, vec![0.1; 16], Activation::Tanh),
+            layer(tiles, vec![0.0; 13], Activation::Linear),
+        ])).unwrap();
+        let mut strategy = NeuralStrategy::new(network, "Random Weights");
+
+        let mut rng = Xoroshiro128Plus::new(11);
+        for _ in 0..2_000 {
+            let board = rng.gen_range(0..1u16 << 12);
+            let state = GameState { movable: board & rng.gen::<u16>(), ..state(board) };
+            let roll = rng.gen_range(MIN_ROLL..=MAX_ROLL);
+            let chosen = strategy.choose_move(&state, roll);
+            assert!(legal_moves(state.movable, roll).contains(&chosen), "{:#x} with {} on {:#x}", chosen, roll, state.movable);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+// File: src/opponent_model.rs
+
+use crate::{GameMode, GameState, Strategy};
+use crate::game::{movable_tiles, GameRules};
+use crate::history::empty_history;
+use crate::solver::{MixedPolicy, Model, Position, Solution, MAX_ROLL, MIN_ROLL};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+pub const OPPONENT_MODEL: &str = "Opponent Model";
+
+// Chance that an opponent playing like a candidate makes some other move.
+const NOISE: f64 = 0.05;
+
+// Best responses by candidate and seat.
+type Responses = HashMap<(usize, usize), Arc<Solution>>;
+
+// Log-likelihoods of the candidates for the opponent of `player` in match `history`, over its
+// first `seen` moves.
+#[derive(Clone)]
+struct Beliefs {
+    history: u64,
+    player: usize,
+    seen: usize,
+    log_likelihoods: Vec<f64>,
+}
+
+// Works out which of `candidates` the opponent plays like from the moves in the match history,
+// and once one of them explains the moves with posterior probability `confidence`, plays the
+// best response to it from the solver's value table. Until then, and whenever the evidence
+// turns ambiguous, it plays the exact solution. In base mode each player's score depends only
+// on their own turns, so the best response to every candidate is the exact solution itself;
+// the model only changes the moves in extended mode. Clones share the solved tables.
+#[derive(Clone)]
+pub struct OpponentModelStrategy {
+    pub candidates: Vec<Box<dyn Strategy>>,
+    pub confidence: f64,
+    model: Model,
+    solution: Arc<OnceLock<Solution>>,
+    responses: Arc<Mutex<Responses>>,
+    beliefs: Beliefs,
+}
+
+impl OpponentModelStrategy {
+    pub fn new(mode: GameMode, rules: GameRules, candidates: Vec<Box<dyn Strategy>>) -> Self {
+        let beliefs = Beliefs { history: 0, player: 0, seen: 0, log_likelihoods: vec![0.0; candidates.len()] };
+        OpponentModelStrategy {
+            candidates,
+            confidence: 0.9,
+            model: Model::new(mode, rules),
+            solution: Arc::new(OnceLock::new()),
+            responses: Arc::new(Mutex::new(HashMap::new())),
+            beliefs,
+        }
+    }
+
+    // Posterior probability of each candidate for the mover's opponent, from a uniform prior.
+    pub fn posterior(&mut self, state: &GameState) -> Vec<f64> {
+        let history = state.history;
+        let player = history.player(state.player);
+        let beliefs = &mut self.beliefs;
+        if beliefs.history != history.id || beliefs.player != player || beliefs.seen > history.moves.len() {
+            *beliefs = Beliefs { history: history.id, player, seen: 0, log_likelihoods: vec![0.0; self.candidates.len()] };
+        }
+        for record in &history.moves[beliefs.seen..] {
+            if record.player == player {
+                continue;
+            }
+            // Move records don't keep scores, so candidates see 0.
+            let movable = movable_tiles(self.model.mode, self.model.full_board, record.board, record.seat);
+            let seen = GameState { board: record.board, movable, score: 0, round: record.round, player: record.seat, history: empty_history() };
+            for (candidate, log_likelihood) in self.candidates.iter_mut().zip(beliefs.log_likelihoods.iter_mut()) {
+                let agrees = candidate.choose_move(&seen, record.roll) == record.move_bits;
+                *log_likelihood += if agrees { (1.0 - NOISE).ln() } else { NOISE.ln() };
+            }
+        }
+        beliefs.seen = history.moves.len();
+
+        let max = beliefs.log_likelihoods.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let weights: Vec<f64> = beliefs.log_likelihoods.iter().map(|l| (l - max).exp()).collect();
+        let total: f64 = weights.iter().sum();
+        weights.into_iter().map(|w| w / total).collect()
+    }
+
+    fn solution(&self) -> &Solution {
+        self.solution.get_or_init(|| Solution::evaluate_profile(self.model.clone(), [None, None]))
+    }
+
+    // Values for `seat` best-responding to candidate `index` in the other seat.
+    fn response(&self, index: usize, seat: usize) -> Arc<Solution> {
+        let key = (index, seat);
+        if let Some(solution) = self.responses.lock().unwrap().get(&key) {
+            return solution.clone();
+        }
+        let candidate = RefCell::new(self.candidates[index].clone());
+        let policy = |position: Position, roll: u8| {
+            let movable = movable_tiles(self.model.mode, self.model.full_board, position.board, 1 - seat);
+            let state = GameState { board: position.board, movable, score: 0, round: 0, player: 1 - seat, history: empty_history() };
+            vec![(candidate.borrow_mut().choose_move(&state, roll), 1.0)]
+        };
+        let mut policies = [None, None];
+        policies[1 - seat] = Some(&policy as MixedPolicy<'_>);
+        let solution = Arc::new(Solution::evaluate_profile(self.model.clone(), policies));
+        self.responses.lock().unwrap().insert(key, solution.clone());
+        solution
+    }
+}
+
+impl Strategy for OpponentModelStrategy {
+    fn choose_move(&mut self, state: &GameState, roll: u8) -> u16 {
+        if !(MIN_ROLL..=MAX_ROLL).contains(&roll) {
+            return 0;
+        }
+        let seat = state.player.min(1);
+        let board = state.board & self.model.full_board;
+        let position = match self.model.mode {
+            // Moves that close nothing still advance the round counter, which can then run past
+            // the solver's last stage.
+            GameMode::Base => Position { board, stage: state.round.min(self.model.stages() as u8 - 1) },
+            GameMode::Extended => Position { board, stage: seat as u8 },
+        };
+        if self.model.mode == GameMode::Base || self.candidates.is_empty() {
+            return self.solution().best_move(position, roll);
+        }
+        let posterior = self.posterior(state);
+        let (best, &probability) = posterior.iter().enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .unwrap();
+        if probability >= self.confidence {
+            self.response(best, seat).best_move(position, roll)
+        } else {
+            self.solution().best_move(position, roll)
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        OPPONENT_MODEL
+    }
+
+    fn uses_history(&self) -> bool {
+        self.model.mode == GameMode::Extended
+    }
+}
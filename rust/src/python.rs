@@ -0,0 +1,670 @@
+// File: src/python.rs
+
+use crate::{intern, GameMode, GameState, OptimizationLevel};
+use crate::control::SimulationControl;
+use crate::env::{Env, EnvConfig, Observation, RewardShaping, StepInfo, VecEnv};
+use crate::experiment::TournamentSpec;
+use crate::game::{DiceKind, Game, GameRules, MAX_BOARD_SIZE};
+use crate::history::empty_history;
+use crate::rng::{game_seed, Xoroshiro128Plus};
+use crate::simulation::{self, SimulationConfig, SimulatorType};
+use crate::strategies::{create_strategy_with, strategy_names};
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::call::PyCallArgs;
+use pyo3::types::{PyBytes, PyDict, PyList};
+use pyo3::IntoPyObjectExt;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+// The `game_logic` extension module. Strategies can be given as registry names, `Strategy`
+// objects, or any Python object with a `choose_move(board, score, round, roll)` method returning
+// the bitmask of tiles to close, which then plays inside the Rust engine. The GIL is released
+// while games run, so Python strategies are called from the simulator threads one at a time.
+#[pymodule]
+fn game_logic(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    m.add_class::<PyStrategy>()?;
+    m.add_class::<PyGame>()?;
+    m.add_class::<GameResults>()?;
+    m.add_class::<PyEnv>()?;
+    m.add_class::<PyVecEnv>()?;
+    m.add_function(wrap_pyfunction!(py_strategy_names, m)?)?;
+    m.add_function(wrap_pyfunction!(run_simulation, m)?)?;
+    m.add_function(wrap_pyfunction!(simulate, m)?)?;
+    m.add_function(wrap_pyfunction!(tournament, m)?)?;
+    m.add_function(wrap_pyfunction!(play_games, m)?)?;
+    Ok(())
+}
+
+fn value_error(message: String) -> PyErr {
+    PyValueError::new_err(message)
+}
+
+// Registry lookup that also ignores spaces, dashes and underscores, so `HighestValue` and
+// `highest_value` find "Highest Value".
+fn registry_name(name: &str) -> PyResult<String> {
+    let key = |s: &str| s.chars().filter(|c| !matches!(c, ' ' | '-' | '_')).collect::<String>().to_ascii_lowercase();
+    strategy_names().into_iter()
+        .find(|candidate| key(candidate) == key(name))
+        .ok_or_else(|| value_error(format!("unknown strategy '{}'; see game_logic.strategy_names()", name)))
+}
+
+// A registry strategy with its parameters.
+#[pyclass(name = "Strategy", frozen)]
+struct PyStrategy {
+    name: String,
+    parameters: BTreeMap<String, f64>,
+}
+
+#[pymethods]
+impl PyStrategy {
+    #[new]
+    #[pyo3(signature = (name, **parameters))]
+    fn new(name: &str, parameters: Option<BTreeMap<String, f64>>) -> PyResult<Self> {
+        let name = registry_name(name)?;
+        let parameters = parameters.unwrap_or_default();
+        create_strategy_with(&name, &parameters).map_err(value_error)?;
+        Ok(PyStrategy { name, parameters })
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (name, **parameters))]
+    fn from_name(name: &str, parameters: Option<BTreeMap<String, f64>>) -> PyResult<Self> {
+        PyStrategy::new(name, parameters)
+    }
+
+    #[getter]
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[getter]
+    fn parameters(&self) -> BTreeMap<String, f64> {
+        self.parameters.clone()
+    }
+
+    fn choose_move(&self, board: u16, score: u32, round: u8, roll: u8) -> u16 {
+        self.build().choose_move(&GameState { board, movable: board, score, round, player: 0, history: empty_history() }, roll)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Strategy({:?})", self.name)
+    }
+}
+
+impl PyStrategy {
+    fn build(&self) -> Box<dyn crate::Strategy> {
+        create_strategy_with(&self.name, &self.parameters).unwrap()
+    }
+}
+
+// The first exception raised by a Python strategy during a run. The engine can't stop a game
+// halfway, so the strategy passes for the rest of the run and the error is raised afterwards.
+#[derive(Clone, Default)]
+struct Callbacks(Arc<Mutex<Option<PyErr>>>);
+
+impl Callbacks {
+    fn check(&self) -> PyResult<()> {
+        match self.0.lock().unwrap().take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+// The engine's hooks a Python strategy may define, each taking the hook's arguments with the
+// generator of `on_game_start` replaced by a seed.
+const HOOKS: [&str; 4] = ["on_game_start", "on_opponent_move", "on_turn_end", "on_game_end"];
+
+// Clones share the Python object, so a Python strategy keeping state should expect calls from
+// every worker of a parallel simulator.
+struct PythonStrategy {
+    object: Py<PyAny>,
+    name: &'static str,
+    callbacks: Callbacks,
+    hooks: Vec<&'static str>,
+}
+
+impl Clone for PythonStrategy {
+    fn clone(&self) -> Self {
+        let object = Python::attach(|py| self.object.clone_ref(py));
+        PythonStrategy { object, name: self.name, callbacks: self.callbacks.clone(), hooks: self.hooks.clone() }
+    }
+}
+
+impl PythonStrategy {
+    fn call<'py, A: PyCallArgs<'py>>(&self, py: Python<'py>, method: &str, args: A) -> Option<Bound<'py, PyAny>> {
+        if self.callbacks.0.lock().unwrap().is_some() {
+            return None;
+        }
+        self.object.bind(py).call_method1(method, args)
+            .map_err(|e| {
+                self.callbacks.0.lock().unwrap().get_or_insert(e);
+            })
+            .ok()
+    }
+
+    fn hook<A: for<'py> PyCallArgs<'py>>(&self, method: &str, args: A) {
+        if self.hooks.contains(&method) {
+            Python::attach(|py| {
+                self.call(py, method, args);
+            });
+        }
+    }
+}
+
+impl crate::Strategy for PythonStrategy {
+    fn choose_move(&mut self, state: &GameState, roll: u8) -> u16 {
+        Python::attach(|py| {
+            self.call(py, "choose_move", (state.board, state.score, state.round, roll))
+                .and_then(|chosen| chosen.extract::<u16>().map_err(|e| {
+                    self.callbacks.0.lock().unwrap().get_or_insert(e);
+                }).ok())
+                .unwrap_or(0)
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn on_game_start(&mut self, seat: usize, mut rng: Xoroshiro128Plus) {
+        self.hook("on_game_start", (seat, rng.next_u64()));
+    }
+
+    fn on_opponent_move(&mut self, state: &GameState, roll: u8, move_bits: u16) {
+        self.hook("on_opponent_move", (state.board, state.score, state.round, roll, move_bits));
+    }
+
+    fn on_turn_end(&mut self, player: usize, points: Option<u32>) {
+        self.hook("on_turn_end", (player, points));
+    }
+
+    fn on_game_end(&mut self, winner: usize, scores: &[u32]) {
+        self.hook("on_game_end", (winner, scores.to_vec()));
+    }
+}
+
+// Returns the strategy and whether it calls back into Python.
+fn engine_strategy(object: &Bound<'_, PyAny>, callbacks: &Callbacks) -> PyResult<(Box<dyn crate::Strategy>, bool)> {
+    if let Ok(name) = object.extract::<String>() {
+        return Ok((PyStrategy::new(&name, None)?.build(), false));
+    }
+    if let Ok(strategy) = object.cast::<PyStrategy>() {
+        return Ok((strategy.get().build(), false));
+    }
+    if !object.hasattr("choose_move")? {
+        return Err(PyTypeError::new_err("a strategy is a registry name, a game_logic.Strategy or an object with a choose_move(board, score, round, roll) method"));
+    }
+    let name = match object.getattr("name").and_then(|name| name.extract::<String>()) {
+        Ok(name) => name,
+        Err(_) => object.get_type().name()?.to_string(),
+    };
+    let hooks = HOOKS.into_iter().filter(|hook| object.hasattr(*hook).unwrap_or(false)).collect();
+    let strategy = PythonStrategy { object: object.clone().unbind(), name: intern(name), callbacks: callbacks.clone(), hooks };
+    Ok((Box::new(strategy), true))
+}
+
+fn engine_strategies(objects: &[Bound<'_, PyAny>], callbacks: &Callbacks) -> PyResult<(Vec<Box<dyn crate::Strategy>>, bool)> {
+    let mut strategies = Vec::with_capacity(objects.len());
+    let mut any_python = false;
+    for object in objects {
+        let (strategy, python) = engine_strategy(object, callbacks)?;
+        strategies.push(strategy);
+        any_python |= python;
+    }
+    Ok((strategies, any_python))
+}
+
+fn game_rules(mode: &str, optimization: &str, board_size: u8, rounds: u8, dice: Option<&str>) -> PyResult<(GameMode, OptimizationLevel, GameRules)> {
+    let mode: GameMode = mode.parse().map_err(value_error)?;
+    let optimization: OptimizationLevel = optimization.parse().map_err(value_error)?;
+    if !(2..=MAX_BOARD_SIZE).contains(&board_size) {
+        return Err(value_error(format!("board_size must be between 2 and {}, got {}", MAX_BOARD_SIZE, board_size)));
+    }
+    if rounds == 0 {
+        return Err(value_error("rounds must be at least 1".to_string()));
+    }
+    let dice = dice.map(str::parse::<DiceKind>).transpose().map_err(value_error)?;
+    Ok((mode, optimization, GameRules { board_size, rounds, dice }))
+}
+
+// JSON-shaped values become dicts, lists and scalars.
+fn to_python<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    match value {
+        Value::Null => Ok(py.None().into_bound(py)),
+        Value::Bool(b) => b.into_bound_py_any(py),
+        Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(u), _) => u.into_bound_py_any(py),
+            (_, Some(i)) => i.into_bound_py_any(py),
+            _ => n.as_f64().unwrap_or(f64::NAN).into_bound_py_any(py),
+        },
+        Value::String(s) => s.into_bound_py_any(py),
+        Value::Array(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(to_python(py, item)?)?;
+            }
+            Ok(list.into_any())
+        },
+        Value::Object(fields) => {
+            let dict = PyDict::new(py);
+            for (key, item) in fields {
+                dict.set_item(key, to_python(py, item)?)?;
+            }
+            Ok(dict.into_any())
+        },
+    }
+}
+
+fn serialized<'py, T: Serialize>(py: Python<'py>, value: &T) -> PyResult<Bound<'py, PyAny>> {
+    to_python(py, &serde_json::to_value(value).map_err(|e| value_error(e.to_string()))?)
+}
+
+// A game engine with its own generator. `reset` makes the next game replay game `i` of a
+// seeded run when given `rng.game_seed(seed, i)`.
+#[pyclass(name = "Game", unsendable)]
+struct PyGame {
+    game: Game,
+    mode: GameMode,
+}
+
+#[pymethods]
+impl PyGame {
+    #[new]
+    #[pyo3(signature = (mode = "base", optimization = "standard", seed = 0, board_size = 12, rounds = 5, dice = None))]
+    fn new(mode: &str, optimization: &str, seed: u64, board_size: u8, rounds: u8, dice: Option<&str>) -> PyResult<Self> {
+        let (mode, optimization, rules) = game_rules(mode, optimization, board_size, rounds, dice)?;
+        Ok(PyGame { game: Game::from_rules(mode, optimization, rules, seed), mode })
+    }
+
+    fn reset(&mut self, seed: u64) {
+        self.game.reset(seed);
+    }
+
+    // Plays one game and returns the winner's index, or None for a drawn base game.
+    #[pyo3(signature = (*strategies))]
+    fn play(&mut self, strategies: Vec<Bound<'_, PyAny>>) -> PyResult<Option<usize>> {
+        Ok(self.play_one(&strategies)?.0)
+    }
+
+    // Plays one game and returns {"winner", "scores", "num_rounds"}.
+    #[pyo3(signature = (*strategies))]
+    fn play_outcome<'py>(&mut self, py: Python<'py>, strategies: Vec<Bound<'py, PyAny>>) -> PyResult<Bound<'py, PyDict>> {
+        let (winner, scores, num_rounds) = self.play_one(&strategies)?;
+        let dict = PyDict::new(py);
+        dict.set_item("winner", winner)?;
+        dict.set_item("scores", scores)?;
+        dict.set_item("num_rounds", num_rounds)?;
+        Ok(dict)
+    }
+}
+
+impl PyGame {
+    fn play_one(&mut self, strategies: &[Bound<'_, PyAny>]) -> PyResult<(Option<usize>, Vec<u32>, u8)> {
+        if strategies.is_empty() {
+            return Err(value_error("play needs at least one strategy".to_string()));
+        }
+        let callbacks = Callbacks::default();
+        let (mut strategies, _) = engine_strategies(strategies, &callbacks)?;
+        let mut players: Vec<&mut dyn crate::Strategy> = strategies.iter_mut().map(|s| s.as_mut() as &mut dyn crate::Strategy).collect();
+        let result = self.game.play_game(&mut players);
+        callbacks.check()?;
+
+        let best = result.scores[result.winner];
+        let draw = self.mode == GameMode::Base && result.scores.iter().filter(|&&s| s == best).count() > 1;
+        Ok((if draw { None } else { Some(result.winner) }, result.scores, result.num_rounds))
+    }
+}
+
+// The legacy win-rate table: {strategy: {opponent: win rate}}.
+#[pyfunction]
+#[pyo3(signature = (mode, optimization, strategies, num_games, seed = 0))]
+fn run_simulation(py: Python<'_>, mode: &str, optimization: &str, strategies: Vec<Bound<'_, PyAny>>, num_games: usize, seed: u64) -> PyResult<BTreeMap<String, BTreeMap<String, f64>>> {
+    let (mode, optimization, _) = game_rules(mode, optimization, 12, 5, None)?;
+    let callbacks = Callbacks::default();
+    let (strategies, _) = engine_strategies(&strategies, &callbacks)?;
+    let results = py.detach(|| crate::run_simulation(mode, optimization, &strategies, num_games, seed));
+    callbacks.check()?;
+    Ok(results.into_iter().map(|(name, rates)| (name, rates.into_iter().collect())).collect())
+}
+
+// Plays every pair through a simulator and returns one `SimulationReport` dict per pairing.
+#[pyfunction]
+#[pyo3(signature = (strategies, num_games = 10_000, mode = "base", optimization = "standard", simulator = "parallel", seed = 0, board_size = 12, rounds = 5, dice = None))]
+#[allow(clippy::too_many_arguments)]
+fn simulate<'py>(py: Python<'py>, strategies: Vec<Bound<'py, PyAny>>, num_games: usize, mode: &str, optimization: &str, simulator: &str, seed: u64, board_size: u8, rounds: u8, dice: Option<&str>) -> PyResult<Bound<'py, PyList>> {
+    let (mode, optimization, rules) = game_rules(mode, optimization, board_size, rounds, dice)?;
+    let simulator_type: SimulatorType = simulator.parse().map_err(value_error)?;
+    let callbacks = Callbacks::default();
+    let (strategies, any_python) = engine_strategies(&strategies, &callbacks)?;
+    if strategies.len() < 2 {
+        return Err(value_error("simulate needs at least two strategies".to_string()));
+    }
+    let in_process = match simulator_type {
+        SimulatorType::Multithreaded => true,
+        #[cfg(feature = "parallel")]
+        SimulatorType::Parallel => true,
+        _ => false,
+    };
+    if any_python && !in_process {
+        return Err(value_error(format!("Python strategies only run in-process; the {} simulator rebuilds strategies from their names", simulator)));
+    }
+
+    let strategies: Vec<Arc<dyn crate::Strategy>> = strategies.into_iter().map(Arc::from).collect();
+    let reports = PyList::empty(py);
+    for i in 0..strategies.len() {
+        for j in i + 1..strategies.len() {
+            let config = SimulationConfig {
+                mode,
+                optimization,
+                rules,
+                strategies: vec![strategies[i].clone(), strategies[j].clone()],
+                num_games,
+                first_game: 0,
+                seed,
+                simulator_type,
+            };
+            let report = py.detach(|| simulation::run_simulation(config, &SimulationControl::new()))?;
+            callbacks.check()?;
+            reports.append(serialized(py, &report)?)?;
+        }
+    }
+    Ok(reports)
+}
+
+// Runs a tournament and returns the `TournamentResult` as a dict.
+#[pyfunction]
+#[pyo3(signature = (strategies, format = "round-robin", games_per_match = 1000, mode = "base", optimization = "standard", rounds = 5, best_of = 7, candidate = None, seed = 0))]
+#[allow(clippy::too_many_arguments)]
+fn tournament<'py>(py: Python<'py>, strategies: Vec<Bound<'py, PyAny>>, format: &str, games_per_match: usize, mode: &str, optimization: &str, rounds: usize, best_of: usize, candidate: Option<&str>, seed: u64) -> PyResult<Bound<'py, PyAny>> {
+    let (mode, optimization, _) = game_rules(mode, optimization, 12, 5, None)?;
+    let callbacks = Callbacks::default();
+    let (strategies, _) = engine_strategies(&strategies, &callbacks)?;
+    let spec = match format.to_ascii_lowercase().replace('_', "-").as_str() {
+        "round-robin" => TournamentSpec::RoundRobin,
+        "swiss" if rounds > 0 => TournamentSpec::Swiss { rounds },
+        "single-elimination" if best_of % 2 == 1 => TournamentSpec::SingleElimination { best_of },
+        "double-elimination" if best_of % 2 == 1 => TournamentSpec::DoubleElimination { best_of },
+        "gauntlet" => {
+            let candidate = candidate.ok_or_else(|| value_error("a gauntlet needs a candidate".to_string()))?;
+            let candidate = strategies.iter()
+                .find(|s| s.name().eq_ignore_ascii_case(candidate))
+                .ok_or_else(|| value_error(format!("gauntlet candidate '{}' is not in the field", candidate)))?;
+            TournamentSpec::Gauntlet { candidate: candidate.name().to_string() }
+        },
+        "swiss" => return Err(value_error("rounds must be at least 1".to_string())),
+        "single-elimination" | "double-elimination" => return Err(value_error(format!("best_of must be odd, got {}", best_of))),
+        other => return Err(value_error(format!("unknown tournament format '{}' (expected round-robin, swiss, single-elimination, double-elimination or gauntlet)", other))),
+    };
+
+    let result = py.detach(|| spec.build(games_per_match).run(mode, optimization, &strategies, seed));
+    callbacks.check()?;
+    let result = result.map_err(value_error)?;
+    serialized(py, &result)
+}
+
+// Per-game results as flat columns. Each attribute is an `array.array`, which supports the
+// buffer protocol, so `numpy.asarray(results.score1)` and `pandas.DataFrame(results.to_dict())`
+// work without copying through Python lists.
+#[pyclass(frozen)]
+struct GameResults {
+    // Seat of the winner, -1 for a draw.
+    winners: Vec<i8>,
+    scores: [Vec<i32>; 2],
+    num_rounds: Vec<u8>,
+    #[pyo3(get)]
+    strategies: (String, String),
+}
+
+fn array<'py>(py: Python<'py>, typecode: &str, bytes: Vec<u8>) -> PyResult<Bound<'py, PyAny>> {
+    let array = py.import("array")?.getattr("array")?.call1((typecode,))?;
+    array.call_method1("frombytes", (PyBytes::new(py, &bytes),))?;
+    Ok(array)
+}
+
+#[pymethods]
+impl GameResults {
+    #[getter]
+    fn winner<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        array(py, "b", self.winners.iter().flat_map(|w| w.to_ne_bytes()).collect())
+    }
+
+    #[getter]
+    fn score1<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        array(py, "i", self.scores[0].iter().flat_map(|s| s.to_ne_bytes()).collect())
+    }
+
+    #[getter]
+    fn score2<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        array(py, "i", self.scores[1].iter().flat_map(|s| s.to_ne_bytes()).collect())
+    }
+
+    #[getter]
+    fn num_rounds<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        array(py, "B", self.num_rounds.clone())
+    }
+
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("winner", self.winner(py)?)?;
+        dict.set_item("score1", self.score1(py)?)?;
+        dict.set_item("score2", self.score2(py)?)?;
+        dict.set_item("num_rounds", self.num_rounds(py)?)?;
+        Ok(dict)
+    }
+
+    fn __len__(&self) -> usize {
+        self.winners.len()
+    }
+}
+
+// Plays `num_games` games between two strategies, game `i` seeded like every simulator's, and
+// returns the per-game results.
+#[pyfunction]
+#[pyo3(signature = (strategy1, strategy2, num_games = 10_000, mode = "base", optimization = "fast", seed = 0, board_size = 12, rounds = 5, dice = None))]
+#[allow(clippy::too_many_arguments)]
+fn play_games(py: Python<'_>, strategy1: Bound<'_, PyAny>, strategy2: Bound<'_, PyAny>, num_games: usize, mode: &str, optimization: &str, seed: u64, board_size: u8, rounds: u8, dice: Option<&str>) -> PyResult<GameResults> {
+    let (mode, optimization, rules) = game_rules(mode, optimization, board_size, rounds, dice)?;
+    let callbacks = Callbacks::default();
+    let (first, _) = engine_strategy(&strategy1, &callbacks)?;
+    let (second, _) = engine_strategy(&strategy2, &callbacks)?;
+    let names = (first.name().to_string(), second.name().to_string());
+
+    let outcomes = py.detach(|| {
+        let worker = || (Game::from_rules(mode, optimization, rules, seed), first.clone(), second.clone());
+        let play = |(game, a, b): &mut (Game, Box<dyn crate::Strategy>, Box<dyn crate::Strategy>), i: usize| {
+            game.reset(game_seed(seed, i as u64));
+            game.play_pair([a.as_mut(), b.as_mut()])
+        };
+
+        #[cfg(feature = "parallel")]
+        let outcomes: Vec<_> = (0..num_games).into_par_iter()
+            .map_init(worker, play)
+            .collect();
+
+        #[cfg(not(feature = "parallel"))]
+        let outcomes: Vec<_> = {
+            let mut worker = worker();
+            (0..num_games).map(|i| play(&mut worker, i)).collect()
+        };
+
+        outcomes
+    });
+    callbacks.check()?;
+
+    let draw = |scores: [u32; 2]| mode == GameMode::Base && scores[0] == scores[1];
+    Ok(GameResults {
+        winners: outcomes.iter().map(|o| if draw(o.scores) { -1 } else { o.winner as i8 }).collect(),
+        scores: [
+            outcomes.iter().map(|o| o.scores[0] as i32).collect(),
+            outcomes.iter().map(|o| o.scores[1] as i32).collect(),
+        ],
+        num_rounds: outcomes.iter().map(|o| o.num_rounds).collect(),
+        strategies: names,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn env_config(mode: &str, optimization: &str, board_size: u8, rounds: u8, dice: Option<&str>, seat: usize, per_point: f64, win: f64, loss: f64, draw: f64) -> PyResult<EnvConfig> {
+    let (mode, optimization, rules) = game_rules(mode, optimization, board_size, rounds, dice)?;
+    Ok(EnvConfig { mode, optimization, rules, reward: RewardShaping { per_point, win, loss, draw }, seat })
+}
+
+fn bool_bytes(flags: impl IntoIterator<Item = bool>) -> Vec<u8> {
+    flags.into_iter().map(u8::from).collect()
+}
+
+// {"board", "roll", "round", "scores", "seat", "legal_moves", "action_mask"}, the mask being an
+// `array.array` of 0/1 bytes indexed by action.
+fn observation_dict<'py>(py: Python<'py>, observation: &Observation, num_actions: usize) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("board", observation.board)?;
+    dict.set_item("roll", observation.roll)?;
+    dict.set_item("round", observation.round)?;
+    dict.set_item("scores", observation.scores.to_vec())?;
+    dict.set_item("seat", observation.seat)?;
+    dict.set_item("legal_moves", observation.legal_moves.clone())?;
+    dict.set_item("action_mask", array(py, "B", bool_bytes(observation.action_mask(num_actions)))?)?;
+    Ok(dict)
+}
+
+fn info_dict<'py>(py: Python<'py>, info: &StepInfo) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("winner", info.winner)?;
+    dict.set_item("scores", info.scores.to_vec())?;
+    dict.set_item("num_rounds", info.num_rounds)?;
+    Ok(dict)
+}
+
+// Gym-style environment: the agent plays `seat` against `opponent`, and an action is the
+// bitmask of tiles to close (or, for seat 1 in extended mode, to open), 0 ending the turn.
+// `reset()` without a seed starts game `i` of the run seeded by the constructor.
+#[pyclass(name = "Env", unsendable)]
+struct PyEnv {
+    env: Env,
+    callbacks: Callbacks,
+    seed: u64,
+    games: u64,
+}
+
+#[pymethods]
+impl PyEnv {
+    #[new]
+    #[pyo3(signature = (opponent, mode = "base", optimization = "fast", seed = 0, board_size = 12, rounds = 5, dice = None, seat = 0, per_point = 0.0, win = 1.0, loss = -1.0, draw = 0.0))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(opponent: Bound<'_, PyAny>, mode: &str, optimization: &str, seed: u64, board_size: u8, rounds: u8, dice: Option<&str>, seat: usize, per_point: f64, win: f64, loss: f64, draw: f64) -> PyResult<Self> {
+        let config = env_config(mode, optimization, board_size, rounds, dice, seat, per_point, win, loss, draw)?;
+        let callbacks = Callbacks::default();
+        let (opponent, _) = engine_strategy(&opponent, &callbacks)?;
+        let env = Env::new(config, opponent, seed).map_err(value_error)?;
+        Ok(PyEnv { env, callbacks, seed, games: 0 })
+    }
+
+    #[getter]
+    fn num_actions(&self) -> usize {
+        self.env.num_actions()
+    }
+
+    #[getter]
+    fn done(&self) -> bool {
+        self.env.is_done()
+    }
+
+    fn legal_moves(&self) -> Vec<u16> {
+        self.env.legal_moves()
+    }
+
+    #[pyo3(signature = (seed = None))]
+    fn reset<'py>(&mut self, py: Python<'py>, seed: Option<u64>) -> PyResult<Bound<'py, PyDict>> {
+        let seed = seed.unwrap_or_else(|| game_seed(self.seed, self.games));
+        self.games += 1;
+        let observation = self.env.reset(seed);
+        self.callbacks.check()?;
+        observation_dict(py, &observation, self.env.num_actions())
+    }
+
+    // Returns (observation, reward, done, info).
+    #[allow(clippy::type_complexity)]
+    fn step<'py>(&mut self, py: Python<'py>, action: u16) -> PyResult<(Bound<'py, PyDict>, f64, bool, Bound<'py, PyDict>)> {
+        let (observation, reward, done, info) = self.env.step(action).map_err(value_error)?;
+        self.callbacks.check()?;
+        Ok((observation_dict(py, &observation, self.env.num_actions())?, reward, done, info_dict(py, &info)?))
+    }
+}
+
+// A batch of `num_envs` environments that reset themselves when a game ends. Observations come
+// back as columns of `array.array`s, with `action_mask` flattened to `num_envs * num_actions`.
+#[pyclass(name = "VecEnv", unsendable)]
+struct PyVecEnv {
+    envs: VecEnv,
+    callbacks: Callbacks,
+    seed: u64,
+}
+
+impl PyVecEnv {
+    fn columns<'py>(&self, py: Python<'py>, observations: &[Observation]) -> PyResult<Bound<'py, PyDict>> {
+        let num_actions = self.envs.envs().first().map_or(0, Env::num_actions);
+        let dict = PyDict::new(py);
+        dict.set_item("board", array(py, "H", observations.iter().flat_map(|o| o.board.to_ne_bytes()).collect())?)?;
+        dict.set_item("roll", array(py, "B", observations.iter().map(|o| o.roll).collect())?)?;
+        dict.set_item("round", array(py, "B", observations.iter().map(|o| o.round).collect())?)?;
+        dict.set_item("score1", array(py, "i", observations.iter().flat_map(|o| (o.scores[0] as i32).to_ne_bytes()).collect())?)?;
+        dict.set_item("score2", array(py, "i", observations.iter().flat_map(|o| (o.scores[1] as i32).to_ne_bytes()).collect())?)?;
+        dict.set_item("action_mask", array(py, "B", bool_bytes(observations.iter().flat_map(|o| o.action_mask(num_actions))))?)?;
+        Ok(dict)
+    }
+}
+
+#[pymethods]
+impl PyVecEnv {
+    #[new]
+    #[pyo3(signature = (opponent, num_envs, mode = "base", optimization = "fast", seed = 0, board_size = 12, rounds = 5, dice = None, seat = 0, per_point = 0.0, win = 1.0, loss = -1.0, draw = 0.0))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(opponent: Bound<'_, PyAny>, num_envs: usize, mode: &str, optimization: &str, seed: u64, board_size: u8, rounds: u8, dice: Option<&str>, seat: usize, per_point: f64, win: f64, loss: f64, draw: f64) -> PyResult<Self> {
+        let config = env_config(mode, optimization, board_size, rounds, dice, seat, per_point, win, loss, draw)?;
+        let callbacks = Callbacks::default();
+        let (opponent, _) = engine_strategy(&opponent, &callbacks)?;
+        let envs = VecEnv::new(config, opponent, num_envs, seed).map_err(value_error)?;
+        Ok(PyVecEnv { envs, callbacks, seed })
+    }
+
+    #[getter]
+    fn num_actions(&self) -> usize {
+        self.envs.envs().first().map_or(0, Env::num_actions)
+    }
+
+    fn __len__(&self) -> usize {
+        self.envs.len()
+    }
+
+    #[pyo3(signature = (seed = None))]
+    fn reset<'py>(&mut self, py: Python<'py>, seed: Option<u64>) -> PyResult<Bound<'py, PyDict>> {
+        let observations = self.envs.reset(seed.unwrap_or(self.seed));
+        self.callbacks.check()?;
+        self.columns(py, &observations)
+    }
+
+    // Returns (observations, rewards, dones, infos); rewards and dones are `array.array`s.
+    #[allow(clippy::type_complexity)]
+    fn step<'py>(&mut self, py: Python<'py>, actions: Vec<u16>) -> PyResult<(Bound<'py, PyDict>, Bound<'py, PyAny>, Bound<'py, PyAny>, Vec<Bound<'py, PyDict>>)> {
+        let steps = self.envs.step(&actions).map_err(value_error)?;
+        self.callbacks.check()?;
+        let observations: Vec<Observation> = steps.iter().map(|(observation, ..)| observation.clone()).collect();
+        let rewards = array(py, "d", steps.iter().flat_map(|(_, reward, ..)| reward.to_ne_bytes()).collect())?;
+        let dones = array(py, "B", bool_bytes(steps.iter().map(|(_, _, done, _)| *done)))?;
+        let infos = steps.iter().map(|(.., info)| info_dict(py, info)).collect::<PyResult<_>>()?;
+        Ok((self.columns(py, &observations)?, rewards, dones, infos))
+    }
+}
+
+#[pyfunction]
+#[pyo3(name = "strategy_names")]
+fn py_strategy_names() -> Vec<String> {
+    strategy_names()
+}
@@ -0,0 +1,407 @@
+// File: src/rating.rs
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::f64::consts::{LN_10, PI};
+#[cfg(feature = "serde")]
+use std::fs::File;
+#[cfg(feature = "serde")]
+use std::io::{self, BufReader, BufWriter};
+#[cfg(feature = "serde")]
+use std::path::Path;
+
+const ELO_BASE: f64 = 1500.0;
+const ELO_SCALE: f64 = 400.0;
+const GLICKO_SCALE: f64 = 173.7178;
+const GLICKO_DEFAULT_RD: f64 = 350.0;
+const GLICKO_DEFAULT_VOLATILITY: f64 = 0.06;
+const GLICKO_TAU: f64 = 0.5;
+const CONVERGENCE_TOLERANCE: f64 = 1e-6;
+const MAX_ITERATIONS: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PairRecord {
+    pub wins: u64,
+    pub losses: u64,
+    pub draws: u64,
+}
+
+impl PairRecord {
+    pub fn games(&self) -> u64 {
+        self.wins + self.losses + self.draws
+    }
+
+    pub fn score(&self) -> f64 {
+        self.wins as f64 + 0.5 * self.draws as f64
+    }
+
+    fn reversed(&self) -> Self {
+        PairRecord { wins: self.losses, losses: self.wins, draws: self.draws }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Rating {
+    pub name: String,
+    pub elo: f64,
+    pub elo_error: f64,
+    pub glicko: f64,
+    pub glicko_rd: f64,
+    pub glicko_volatility: f64,
+    pub games: u64,
+}
+
+impl Rating {
+    fn new(name: &str) -> Self {
+        Rating {
+            name: name.to_string(),
+            elo: ELO_BASE,
+            elo_error: 0.0,
+            glicko: ELO_BASE,
+            glicko_rd: GLICKO_DEFAULT_RD,
+            glicko_volatility: GLICKO_DEFAULT_VOLATILITY,
+            games: 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LadderSnapshot {
+    pub label: String,
+    pub ratings: Vec<Rating>,
+}
+
+#[derive(Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RatingLadder {
+    records: HashMap<String, HashMap<String, PairRecord>>,
+    ratings: HashMap<String, Rating>,
+    history: Vec<LadderSnapshot>,
+}
+
+impl RatingLadder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    pub fn add_strategy(&mut self, name: &str) {
+        self.insert_strategy(name);
+        self.recompute_elo();
+    }
+
+    pub fn rating(&self, name: &str) -> Option<&Rating> {
+        self.ratings.get(name)
+    }
+
+    pub fn history(&self) -> &[LadderSnapshot] {
+        &self.history
+    }
+
+    // Records a single pairing as its own Glicko-2 rating period and refits Elo.
+    pub fn record_pairing(&mut self, a: &str, b: &str, record: PairRecord) {
+        self.record_period(&[(a.to_string(), b.to_string(), record)]);
+    }
+
+    // Converts the win-rate table from `tournament::run_simulation` back into counts.
+    // Whatever is left after both win rates is counted as draws.
+    pub fn add_tournament_results(&mut self, results: &HashMap<String, HashMap<String, f64>>, num_games: usize) {
+        let mut pairings = Vec::new();
+        for (a, opponents) in results {
+            for (b, &win_rate) in opponents {
+                if a >= b {
+                    continue;
+                }
+                let wins = (win_rate * num_games as f64).round() as u64;
+                let losses = results.get(b)
+                    .and_then(|row| row.get(a))
+                    .map_or(0, |&rate| (rate * num_games as f64).round() as u64);
+                let draws = (num_games as u64).saturating_sub(wins + losses);
+                pairings.push((a.clone(), b.clone(), PairRecord { wins, losses, draws }));
+            }
+        }
+        self.record_period(&pairings);
+    }
+
+    pub fn record_period(&mut self, pairings: &[(String, String, PairRecord)]) {
+        for (a, b, record) in pairings {
+            self.insert_strategy(a);
+            self.insert_strategy(b);
+            Self::accumulate(&mut self.records, a, b, *record);
+            Self::accumulate(&mut self.records, b, a, record.reversed());
+        }
+        self.update_glicko(pairings);
+        self.recompute_elo();
+    }
+
+    pub fn snapshot(&mut self, label: &str) {
+        let ratings = self.ladder();
+        self.history.push(LadderSnapshot { label: label.to_string(), ratings });
+    }
+
+    pub fn ladder(&self) -> Vec<Rating> {
+        let mut ladder: Vec<Rating> = self.ratings.values().cloned().collect();
+        ladder.sort_by(|a, b| b.elo.partial_cmp(&a.elo).unwrap().then_with(|| a.name.cmp(&b.name)));
+        ladder
+    }
+
+    fn insert_strategy(&mut self, name: &str) {
+        self.ratings.entry(name.to_string()).or_insert_with(|| Rating::new(name));
+    }
+
+    fn accumulate(records: &mut HashMap<String, HashMap<String, PairRecord>>, a: &str, b: &str, record: PairRecord) {
+        let entry = records.entry(a.to_string())
+            .or_default()
+            .entry(b.to_string())
+            .or_default();
+        entry.wins += record.wins;
+        entry.losses += record.losses;
+        entry.draws += record.draws;
+    }
+
+    // Bradley-Terry maximum likelihood fitted with Hunter's MM iteration over all recorded games.
+    // Every strategy also gets one virtual draw against an anchor of strength 1 so that
+    // undefeated or winless strategies keep a finite rating.
+    pub fn recompute_elo(&mut self) {
+        let mut names: Vec<String> = self.ratings.keys().cloned().collect();
+        names.sort();
+        let n = names.len();
+        if n == 0 {
+            return;
+        }
+
+        let mut games = vec![vec![0.0; n]; n];
+        let mut scores = vec![0.5; n];
+        for (i, a) in names.iter().enumerate() {
+            if let Some(row) = self.records.get(a) {
+                for (j, b) in names.iter().enumerate() {
+                    if let Some(record) = row.get(b) {
+                        games[i][j] = record.games() as f64;
+                        scores[i] += record.score();
+                    }
+                }
+            }
+        }
+
+        let mut strength = vec![1.0; n];
+        for _ in 0..MAX_ITERATIONS {
+            let mut next = vec![0.0; n];
+            for i in 0..n {
+                let mut denominator = 1.0 / (strength[i] + 1.0);
+                for j in 0..n {
+                    if games[i][j] > 0.0 {
+                        denominator += games[i][j] / (strength[i] + strength[j]);
+                    }
+                }
+                next[i] = scores[i] / denominator;
+            }
+
+            let mut max_change: f64 = 0.0;
+            for i in 0..n {
+                max_change = max_change.max((next[i].ln() - strength[i].ln()).abs());
+                strength[i] = next[i];
+            }
+            if max_change < CONVERGENCE_TOLERANCE {
+                break;
+            }
+        }
+
+        // Standard errors from the diagonal of the observed Fisher information.
+        for (i, name) in names.iter().enumerate() {
+            let mut information = strength[i] / (strength[i] + 1.0).powi(2);
+            let mut total_games = 0.0;
+            for j in 0..n {
+                if games[i][j] > 0.0 {
+                    let p = strength[i] / (strength[i] + strength[j]);
+                    information += games[i][j] * p * (1.0 - p);
+                    total_games += games[i][j];
+                }
+            }
+            let rating = self.ratings.get_mut(name).unwrap();
+            rating.elo = ELO_BASE + ELO_SCALE * strength[i].log10();
+            rating.elo_error = ELO_SCALE / LN_10 / information.sqrt();
+            rating.games = total_games as u64;
+        }
+    }
+
+    fn update_glicko(&mut self, pairings: &[(String, String, PairRecord)]) {
+        let before: HashMap<String, (f64, f64)> = self.ratings.iter()
+            .map(|(name, r)| (name.clone(), ((r.glicko - ELO_BASE) / GLICKO_SCALE, r.glicko_rd / GLICKO_SCALE)))
+            .collect();
+
+        let mut opponents: HashMap<&str, Vec<(&str, PairRecord)>> = HashMap::new();
+        for (a, b, record) in pairings {
+            opponents.entry(a.as_str()).or_default().push((b.as_str(), *record));
+            opponents.entry(b.as_str()).or_default().push((a.as_str(), record.reversed()));
+        }
+
+        for (name, rating) in self.ratings.iter_mut() {
+            let (mu, phi) = before[name];
+            let sigma = rating.glicko_volatility;
+
+            let results = match opponents.get(name.as_str()) {
+                Some(results) => results,
+                None => {
+                    let phi_star = (phi * phi + sigma * sigma).sqrt();
+                    rating.glicko_rd = (phi_star * GLICKO_SCALE).min(GLICKO_DEFAULT_RD);
+                    continue;
+                }
+            };
+
+            let mut v_inverse = 0.0;
+            let mut improvement = 0.0;
+            for (opponent, record) in results {
+                let (mu_j, phi_j) = before[*opponent];
+                let g = glicko_g(phi_j);
+                let expected = 1.0 / (1.0 + (-g * (mu - mu_j)).exp());
+                let n = record.games() as f64;
+                v_inverse += n * g * g * expected * (1.0 - expected);
+                improvement += g * (record.score() - n * expected);
+            }
+            if v_inverse == 0.0 {
+                continue;
+            }
+            let v = 1.0 / v_inverse;
+            let delta = v * improvement;
+
+            let sigma = glicko_volatility(delta, phi, v, sigma);
+            let phi_star = (phi * phi + sigma * sigma).sqrt();
+            let phi_new = 1.0 / (1.0 / (phi_star * phi_star) + 1.0 / v).sqrt();
+            let mu_new = mu + phi_new * phi_new * improvement;
+
+            rating.glicko = ELO_BASE + GLICKO_SCALE * mu_new;
+            rating.glicko_rd = GLICKO_SCALE * phi_new;
+            rating.glicko_volatility = sigma;
+        }
+    }
+}
+
+fn glicko_g(phi: f64) -> f64 {
+    1.0 / (1.0 + 3.0 * phi * phi / (PI * PI)).sqrt()
+}
+
+// Glickman's Illinois-method iteration for the new volatility.
+fn glicko_volatility(delta: f64, phi: f64, v: f64, sigma: f64) -> f64 {
+    let a = (sigma * sigma).ln();
+    let f = |x: f64| {
+        let ex = x.exp();
+        let denominator = phi * phi + v + ex;
+        ex * (delta * delta - phi * phi - v - ex) / (2.0 * denominator * denominator) - (x - a) / (GLICKO_TAU * GLICKO_TAU)
+    };
+
+    let mut bound_a = a;
+    let mut bound_b = if delta * delta > phi * phi + v {
+        (delta * delta - phi * phi - v).ln()
+    } else {
+        let mut k = 1.0;
+        while f(a - k * GLICKO_TAU) < 0.0 {
+            k += 1.0;
+        }
+        a - k * GLICKO_TAU
+    };
+
+    let mut f_a = f(bound_a);
+    let mut f_b = f(bound_b);
+    let mut iterations = 0;
+    while (bound_b - bound_a).abs() > CONVERGENCE_TOLERANCE && iterations < MAX_ITERATIONS {
+        let c = bound_a + (bound_a - bound_b) * f_a / (f_b - f_a);
+        let f_c = f(c);
+        if f_c * f_b <= 0.0 {
+            bound_a = bound_b;
+            f_a = f_b;
+        } else {
+            f_a /= 2.0;
+        }
+        bound_b = c;
+        f_b = f_c;
+        iterations += 1;
+    }
+
+    (bound_a / 2.0).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(wins: u64, losses: u64) -> PairRecord {
+        PairRecord { wins, losses, draws: 0 }
+    }
+
+    fn strength(ladder: &RatingLadder, name: &str) -> f64 {
+        10f64.powf((ladder.rating(name).unwrap().elo - ELO_BASE) / ELO_SCALE)
+    }
+
+    #[test]
+    fn elo_solves_the_bradley_terry_likelihood_equations() {
+        let mut ladder = RatingLadder::new();
+        ladder.record_period(&[
+            ("A".to_string(), "B".to_string(), record(70, 30)),
+            ("B".to_string(), "C".to_string(), record(60, 40)),
+            ("A".to_string(), "C".to_string(), PairRecord { wins: 75, losses: 20, draws: 5 }),
+        ]);
+
+        // At the maximum, each strategy's score (plus its virtual half point) equals its
+        // expected score, the virtual draw against the anchor of strength 1 included.
+        for name in ["A", "B", "C"] {
+            let s = strength(&ladder, name);
+            let mut expected = s / (s + 1.0);
+            let mut score = 0.5;
+            for (opponent, record) in &ladder.records[name] {
+                let t = strength(&ladder, opponent);
+                expected += record.games() as f64 * s / (s + t);
+                score += record.score();
+            }
+            assert!((score - expected).abs() < 1e-3, "{}: {} vs {}", name, score, expected);
+        }
+        let names: Vec<String> = ladder.ladder().into_iter().map(|r| r.name).collect();
+        assert_eq!(names, ["A", "B", "C"]);
+        assert_eq!(ladder.rating("A").unwrap().games, 200);
+    }
+
+    #[test]
+    fn even_records_rate_level() {
+        let mut ladder = RatingLadder::new();
+        ladder.record_pairing("A", "B", PairRecord { wins: 40, losses: 40, draws: 20 });
+        assert!((ladder.rating("A").unwrap().elo - ELO_BASE).abs() < 1e-6);
+        assert!((ladder.rating("B").unwrap().elo - ELO_BASE).abs() < 1e-6);
+    }
+
+    // The worked example from Glickman's "Example of the Glicko-2 system".
+    #[test]
+    fn glicko_matches_the_published_example() {
+        let mut ladder = RatingLadder::new();
+        for (name, glicko, rd) in [("P", 1500.0, 200.0), ("X", 1400.0, 30.0), ("Y", 1550.0, 100.0), ("Z", 1700.0, 300.0)] {
+            ladder.insert_strategy(name);
+            let rating = ladder.ratings.get_mut(name).unwrap();
+            rating.glicko = glicko;
+            rating.glicko_rd = rd;
+        }
+        ladder.update_glicko(&[
+            ("P".to_string(), "X".to_string(), record(1, 0)),
+            ("P".to_string(), "Y".to_string(), record(0, 1)),
+            ("P".to_string(), "Z".to_string(), record(0, 1)),
+        ]);
+
+        let rating = ladder.rating("P").unwrap();
+        assert!((rating.glicko - 1464.06).abs() < 0.01, "{}", rating.glicko);
+        assert!((rating.glicko_rd - 151.52).abs() < 0.01, "{}", rating.glicko_rd);
+        assert!((rating.glicko_volatility - 0.05999).abs() < 1e-5, "{}", rating.glicko_volatility);
+    }
+}
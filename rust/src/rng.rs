@@ -1,4 +1,5 @@
 
+#[derive(Clone, Debug)]
 pub struct Xoroshiro128Plus {
     s: [u64; 2],
 }
@@ -12,9 +13,7 @@ impl Xoroshiro128Plus {
 
     pub fn seed(&mut self, seed: u64) {
         let mut s = [0u8; 16];
-        for i in 0..8 {
-            s[i] = (seed >> (i * 8)) as u8;
-        }
+        s[..8].copy_from_slice(&seed.to_le_bytes());
         self.s[0] = u64::from_le_bytes([s[0], s[1], s[2], s[3], s[4], s[5], s[6], s[7]]);
         self.s[1] = u64::from_le_bytes([s[8], s[9], s[10], s[11], s[12], s[13], s[14], s[15]]);
     }
@@ -34,4 +33,35 @@ impl Xoroshiro128Plus {
     pub fn next_u32(&mut self) -> u32 {
         self.next_u64() as u32
     }
-}
\ No newline at end of file
+}
+
+impl rand::RngCore for Xoroshiro128Plus {
+    fn next_u32(&mut self) -> u32 {
+        Xoroshiro128Plus::next_u32(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        Xoroshiro128Plus::next_u64(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = Xoroshiro128Plus::next_u64(self).to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+// SplitMix64 of (base seed + game index), so every game in a run gets an independent,
+// reproducible seed regardless of which thread or process plays it.
+pub fn game_seed(base: u64, index: u64) -> u64 {
+    let mut z = base.wrapping_add(index.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -1,52 +1,527 @@
 // File: src/simulation.rs
 
-use crate::{GameMode, OptimizationLevel, Strategy, GameResult};
-use crate::multithreaded_simulator::MultithreadedSimulator;
-use crate::parallel_simulator::ParallelSimulator;
-use crate::distributed_simulator::DistributedSimulator;
+use crate::{GameMode, OptimizationLevel, Strategy, GameResult, GameOutcome};
+use crate::simulator_multithreded::MultithreadedSimulator;
+#[cfg(feature = "parallel")]
+use crate::simulator_paraller::ParallelSimulator;
+#[cfg(feature = "serde")]
+use crate::simulator_distributed::DistributedSimulator;
+#[cfg(feature = "mpi")]
+use crate::simulator_mpi::MpiSimulator;
+use crate::control::{SimulationControl, StopReason};
+use crate::game::{GameRules, TurnObserver};
+use crate::histogram::Histogram;
+#[cfg(feature = "serde")]
+use crate::checkpoint::{Checkpoint, SimulationCheckpoint};
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+use std::io;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+// Every simulator seats the first two strategies of the config against each other.
+pub const SEATS: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+// Backends behind a cargo feature only exist in builds with that feature; parsing
+// their name in other builds gives an error that names the missing feature.
 pub enum SimulatorType {
     Multithreaded,
+    #[cfg(feature = "parallel")]
     Parallel,
+    #[cfg(feature = "serde")]
     Distributed,
+    #[cfg(feature = "mpi")]
+    Mpi,
+}
+
+impl FromStr for SimulatorType {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "multithreaded" => Ok(SimulatorType::Multithreaded),
+            #[cfg(feature = "parallel")]
+            "parallel" => Ok(SimulatorType::Parallel),
+            #[cfg(not(feature = "parallel"))]
+            "parallel" => Err(unavailable("parallel", "parallel")),
+            #[cfg(feature = "serde")]
+            "distributed" => Ok(SimulatorType::Distributed),
+            #[cfg(not(feature = "serde"))]
+            "distributed" => Err(unavailable("distributed", "serde")),
+            #[cfg(feature = "mpi")]
+            "mpi" => Ok(SimulatorType::Mpi),
+            #[cfg(not(feature = "mpi"))]
+            "mpi" => Err(unavailable("mpi", "mpi")),
+            other => Err(format!("unknown simulator type '{}' (expected multithreaded, parallel, distributed or mpi)", other)),
+        }
+    }
+}
+
+#[cfg(not(all(feature = "parallel", feature = "serde", feature = "mpi")))]
+fn unavailable(name: &str, feature: &str) -> String {
+    format!("the {} simulator is not available in this build; rebuild with `--features {}`", name, feature)
 }
 
 pub struct SimulationConfig {
     pub mode: GameMode,
     pub optimization: OptimizationLevel,
+    pub rules: GameRules,
     pub strategies: Vec<Arc<dyn Strategy>>,
     pub num_games: usize,
+    // Index of the first game to play. Game `i` is always seeded with `game_seed(seed, i)`,
+    // so a run can be split into consecutive slices without changing the result.
+    pub first_game: usize,
+    pub seed: u64,
     pub simulator_type: SimulatorType,
 }
 
-pub fn run_simulation(config: SimulationConfig) -> Vec<(String, f64)> {
-    match config.simulator_type {
-        SimulatorType::Multithreaded => {
-            MultithreadedSimulator::run_simulations(
-                config.mode,
-                config.optimization,
-                &config.strategies,
-                config.num_games,
-                num_cpus::get()
-            )
-        },
-        SimulatorType::Parallel => {
-            ParallelSimulator::run_simulations(
-                config.mode,
-                config.optimization,
-                &config.strategies,
-                config.num_games
-            )
-        },
-        SimulatorType::Distributed => {
-            let simulator = DistributedSimulator::new();
-            simulator.run_simulations(
-                config.mode,
-                config.optimization,
-                &config.strategies,
-                config.num_games
-            )
+impl SimulationConfig {
+    // The same run restricted to games `first_game..first_game + num_games`.
+    pub fn slice(&self, first_game: usize, num_games: usize) -> SimulationConfig {
+        SimulationConfig {
+            mode: self.mode,
+            optimization: self.optimization,
+            rules: self.rules,
+            strategies: self.strategies.clone(),
+            num_games,
+            first_game,
+            seed: self.seed,
+            simulator_type: self.simulator_type,
+        }
+    }
+
+    // Instances of the two seated strategies for one worker.
+    pub fn seat_strategies(&self) -> io::Result<[Box<dyn Strategy>; 2]> {
+        match self.strategies.as_slice() {
+            [a, b] => Ok([a.clone_strategy(), b.clone_strategy()]),
+            strategies => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("a simulation seats exactly {} strategies, got {}", SEATS, strategies.len()))),
+        }
+    }
+
+    // Serializable copy of the settings, echoed back in every report.
+    pub fn echo(&self) -> crate::SimulationConfig {
+        crate::SimulationConfig {
+            num_games: self.num_games,
+            game_mode: self.mode,
+            optimization_level: self.optimization,
+            strategies: self.strategies.iter().map(|s| s.name().to_string()).collect(),
+            seed: self.seed,
+            rules: self.rules,
+        }
+    }
+}
+
+pub trait Simulator {
+    fn name(&self) -> &'static str;
+
+    // Plays up to `config.num_games` games and returns the aggregated counts. Implementations call
+    // `control.start` first and stop early, keeping the games played so far, once `control.should_stop()`.
+    fn simulate(&self, config: &SimulationConfig, control: &SimulationControl) -> io::Result<SimulationStats>;
+
+    fn run(&self, config: &SimulationConfig, control: &SimulationControl) -> io::Result<SimulationReport> {
+        let start = Instant::now();
+        let stats = self.simulate(config, control);
+        control.finish();
+        let mut report = SimulationReport::new(self.name(), config, &stats?, start.elapsed());
+        report.stop_reason = control.stop_reason();
+        Ok(report)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScoreStats {
+    pub count: u64,
+    pub sum: u64,
+    pub sum_squares: u64,
+    pub min: u32,
+    pub max: u32,
+}
+
+impl ScoreStats {
+    pub fn add(&mut self, value: u32) {
+        if self.count == 0 || value < self.min {
+            self.min = value;
+        }
+        self.max = self.max.max(value);
+        self.count += 1;
+        self.sum += value as u64;
+        self.sum_squares += (value as u64) * (value as u64);
+    }
+
+    pub fn merge(&mut self, other: &ScoreStats) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 || other.min < self.min {
+            self.min = other.min;
+        }
+        self.max = self.max.max(other.max);
+        self.count += other.count;
+        self.sum += other.sum;
+        self.sum_squares += other.sum_squares;
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 { 0.0 } else { self.sum as f64 / self.count as f64 }
+    }
+
+    pub fn std_dev(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        let mean = self.mean();
+        (self.sum_squares as f64 / self.count as f64 - mean * mean).max(0.0).sqrt()
+    }
+}
+
+// Upper bounds for the histogram layouts. A turn scores at most the 12 tiles of the board,
+// and `num_rounds` is a u8.
+const MAX_TURN_VALUE: usize = 16;
+const MAX_FINAL_SCORE: usize = 128;
+const MAX_ROUNDS: usize = 256;
+
+fn game_length_histogram() -> Histogram {
+    Histogram::new(0, 1, MAX_ROUNDS)
+}
+
+// Distributions for one seat. Scores, turn scores and margins (own score minus the opponent's)
+// are only recorded in base mode; extended games have no scores.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SeatHistograms {
+    pub turn_scores: Histogram,
+    pub final_scores: Histogram,
+    pub margins: Histogram,
+    pub moves_per_turn: Histogram,
+    // Length in rounds of the games this seat won.
+    pub win_lengths: Histogram,
+}
+
+impl Default for SeatHistograms {
+    fn default() -> Self {
+        SeatHistograms {
+            turn_scores: Histogram::new(0, 1, MAX_TURN_VALUE),
+            final_scores: Histogram::new(0, 1, MAX_FINAL_SCORE),
+            margins: Histogram::new(-(MAX_FINAL_SCORE as i64) / 2, 1, MAX_FINAL_SCORE),
+            moves_per_turn: Histogram::new(0, 1, MAX_TURN_VALUE),
+            win_lengths: game_length_histogram(),
+        }
+    }
+}
+
+impl SeatHistograms {
+    pub fn merge(&mut self, other: &SeatHistograms) {
+        self.turn_scores.merge(&other.turn_scores);
+        self.final_scores.merge(&other.final_scores);
+        self.margins.merge(&other.margins);
+        self.moves_per_turn.merge(&other.moves_per_turn);
+        self.win_lengths.merge(&other.win_lengths);
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SeatStats {
+    pub wins: u64,
+    pub scores: ScoreStats,
+    pub histograms: SeatHistograms,
+}
+
+// Per-run counters. Workers fill their own copy and merge them at the end. Every field has a
+// fixed size, so a run's memory use doesn't depend on its game count.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SimulationStats {
+    pub games: u64,
+    pub draws: u64,
+    pub seats: Vec<SeatStats>,
+    pub rounds: ScoreStats,
+    pub game_lengths: Histogram,
+}
+
+// Turn histograms are filled while a game is played, via `Game::play_pair_observed`.
+impl TurnObserver for SimulationStats {
+    fn end_turn(&mut self, player: usize, points: Option<u32>, moves: u32) {
+        if let Some(seat) = self.seats.get_mut(player) {
+            if let Some(points) = points {
+                seat.histograms.turn_scores.add(points as i64);
+            }
+            seat.histograms.moves_per_turn.add(moves as i64);
+        }
+    }
+}
+
+impl SimulationStats {
+    pub fn new(num_seats: usize) -> Self {
+        SimulationStats {
+            games: 0,
+            draws: 0,
+            seats: vec![SeatStats::default(); num_seats],
+            rounds: ScoreStats::default(),
+            game_lengths: game_length_histogram(),
+        }
+    }
+
+    // A base game where every seat finishes on the same score is a draw; the engine still
+    // names the first seat as winner in that case, so it is not counted as a win.
+    pub fn record(&mut self, mode: GameMode, result: &GameResult) {
+        self.record_scores(mode, result.winner, &result.scores, result.num_rounds);
+    }
+
+    pub fn record_outcome(&mut self, mode: GameMode, outcome: &GameOutcome) {
+        self.record_scores(mode, outcome.winner, &outcome.scores, outcome.num_rounds);
+    }
+
+    fn record_scores(&mut self, mode: GameMode, winner: usize, scores: &[u32], num_rounds: u8) {
+        self.games += 1;
+        self.rounds.add(num_rounds as u32);
+        self.game_lengths.add(num_rounds as i64);
+
+        let draw = matches!(mode, GameMode::Base)
+            && scores.len() > 1
+            && scores.iter().all(|&s| s == scores[0]);
+        if draw {
+            self.draws += 1;
+        } else if let Some(seat) = self.seats.get_mut(winner) {
+            seat.wins += 1;
+            seat.histograms.win_lengths.add(num_rounds as i64);
+        }
+
+        for (i, (seat, &score)) in self.seats.iter_mut().zip(scores).enumerate() {
+            seat.scores.add(score);
+            if matches!(mode, GameMode::Base) {
+                seat.histograms.final_scores.add(score as i64);
+                if let Some(best_other) = scores.iter().enumerate().filter(|&(j, _)| j != i).map(|(_, &s)| s).min() {
+                    seat.histograms.margins.add(score as i64 - best_other as i64);
+                }
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: &SimulationStats) {
+        self.games += other.games;
+        self.draws += other.draws;
+        self.rounds.merge(&other.rounds);
+        self.game_lengths.merge(&other.game_lengths);
+        if self.seats.len() < other.seats.len() {
+            self.seats.resize(other.seats.len(), SeatStats::default());
+        }
+        for (seat, other_seat) in self.seats.iter_mut().zip(&other.seats) {
+            seat.wins += other_seat.wins;
+            seat.scores.merge(&other_seat.scores);
+            seat.histograms.merge(&other_seat.histograms);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SeatReport {
+    pub seat: usize,
+    pub strategy: String,
+    pub wins: u64,
+    pub win_rate: f64,
+    pub mean_score: f64,
+    pub score_std_dev: f64,
+    pub min_score: u32,
+    pub max_score: u32,
+    pub histograms: SeatHistograms,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StrategyReport {
+    pub name: String,
+    pub games: u64,
+    pub wins: u64,
+    pub draws: u64,
+    pub losses: u64,
+    pub win_rate: f64,
+    pub histograms: SeatHistograms,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SimulationReport {
+    pub simulator: String,
+    pub config: crate::SimulationConfig,
+    pub games: u64,
+    pub draws: u64,
+    pub strategies: Vec<StrategyReport>,
+    pub seats: Vec<SeatReport>,
+    pub mean_rounds: f64,
+    pub game_lengths: Histogram,
+    pub elapsed_secs: f64,
+    pub games_per_second: f64,
+    pub stop_reason: Option<StopReason>,
+}
+
+impl SimulationReport {
+    pub fn new(simulator: &str, config: &SimulationConfig, stats: &SimulationStats, elapsed: Duration) -> Self {
+        let rate = |count: u64| if stats.games == 0 { 0.0 } else { count as f64 / stats.games as f64 };
+
+        let seats: Vec<SeatReport> = stats.seats.iter().zip(&config.strategies).enumerate()
+            .map(|(i, (seat, strategy))| SeatReport {
+                seat: i,
+                strategy: strategy.name().to_string(),
+                wins: seat.wins,
+                win_rate: rate(seat.wins),
+                mean_score: seat.scores.mean(),
+                score_std_dev: seat.scores.std_dev(),
+                min_score: seat.scores.min,
+                max_score: seat.scores.max,
+                histograms: seat.histograms.clone(),
+            })
+            .collect();
+
+        // One entry per seat, in seat order, even when a strategy plays itself: folding by name
+        // would count every self-play game twice.
+        let strategies: Vec<StrategyReport> = seats.iter()
+            .map(|seat| StrategyReport {
+                name: seat.strategy.clone(),
+                games: stats.games,
+                wins: seat.wins,
+                draws: stats.draws,
+                losses: stats.games - seat.wins - stats.draws,
+                win_rate: seat.win_rate,
+                histograms: seat.histograms.clone(),
+            })
+            .collect();
+
+        let elapsed_secs = elapsed.as_secs_f64();
+        SimulationReport {
+            simulator: simulator.to_string(),
+            config: config.echo(),
+            games: stats.games,
+            draws: stats.draws,
+            strategies,
+            seats,
+            mean_rounds: stats.rounds.mean(),
+            game_lengths: stats.game_lengths.clone(),
+            elapsed_secs,
+            games_per_second: if elapsed_secs > 0.0 { stats.games as f64 / elapsed_secs } else { 0.0 },
+            stop_reason: None,
+        }
+    }
+
+    pub fn win_rates(&self) -> Vec<(String, f64)> {
+        self.strategies.iter().map(|s| (s.name.clone(), s.win_rate)).collect()
+    }
+}
+
+// Fails for MPI once MPI has been initialized in this process.
+pub fn create_simulator(simulator_type: &SimulatorType) -> io::Result<Box<dyn Simulator>> {
+    Ok(match simulator_type {
+        SimulatorType::Multithreaded => Box::new(MultithreadedSimulator::default()),
+        #[cfg(feature = "parallel")]
+        SimulatorType::Parallel => Box::new(ParallelSimulator),
+        #[cfg(feature = "serde")]
+        SimulatorType::Distributed => Box::new(DistributedSimulator::default()),
+        #[cfg(feature = "mpi")]
+        SimulatorType::Mpi => Box::new(MpiSimulator::new()
+            .ok_or_else(|| io::Error::other("the MPI simulator is not available: MPI can only be initialized once per process"))?),
+    })
+}
+
+pub fn run_simulation(config: SimulationConfig, control: &SimulationControl) -> io::Result<SimulationReport> {
+    create_simulator(&config.simulator_type)?.run(&config, control)
+}
+
+// Plays the run in slices of `checkpoint.interval` games, saving the merged counts and the next game
+// index after each slice, and continues from an existing checkpoint for the same config. A slice cut
+// short by the control handle is dropped and replayed on resume, so the final counts match an
+// uninterrupted run. Not supported under MPI, where only rank 0 sees the merged counts.
+#[cfg(feature = "serde")]
+pub fn run_checkpointed(config: &SimulationConfig, control: &SimulationControl, checkpoint: &Checkpoint) -> io::Result<SimulationReport> {
+    #[cfg(feature = "mpi")]
+    if config.simulator_type == SimulatorType::Mpi {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "checkpointing is not supported by the MPI simulator"));
+    }
+    let simulator = create_simulator(&config.simulator_type)?;
+    let echo = config.echo();
+    let mut state = match checkpoint.load::<SimulationCheckpoint>()? {
+        Some(state) => {
+            state.check(&echo)?;
+            state
         },
+        None => SimulationCheckpoint::new(echo, config.first_game, SimulationStats::new(SEATS)),
+    };
+
+    let start = Instant::now();
+    let end = config.first_game + config.num_games;
+    let resumed_at = state.next_game;
+    let end = state.next_game + control.start_session(end.saturating_sub(state.next_game));
+    while state.next_game < end {
+        let slice_end = (state.next_game + checkpoint.interval).min(end);
+        let slice = config.slice(state.next_game, slice_end - state.next_game);
+        let stats = match simulator.simulate(&slice, control) {
+            Ok(stats) => stats,
+            Err(e) => {
+                control.end_session();
+                return Err(e);
+            },
+        };
+        if (stats.games as usize) < slice.num_games {
+            break;
+        }
+        state.stats.merge(&stats);
+        state.next_game = slice_end;
+        checkpoint.save(&state)?;
+        if control.should_stop() {
+            break;
+        }
+    }
+    control.end_session();
+    control.finish();
+
+    let elapsed = start.elapsed();
+    let mut report = SimulationReport::new(simulator.name(), config, &state.stats, elapsed);
+    let played = (state.next_game - resumed_at) as f64;
+    report.games_per_second = if elapsed.as_secs_f64() > 0.0 { played / elapsed.as_secs_f64() } else { 0.0 };
+    report.stop_reason = control.stop_reason();
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategies::{HighestValueStrategy, RandomStrategy};
+
+    fn config(strategies: Vec<Arc<dyn Strategy>>) -> SimulationConfig {
+        SimulationConfig {
+            mode: GameMode::Base,
+            optimization: OptimizationLevel::Fast,
+            rules: GameRules::default(),
+            strategies,
+            num_games: 200,
+            first_game: 0,
+            seed: 4,
+            simulator_type: SimulatorType::Multithreaded,
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn self_play_reports_each_seat_once() {
+        let config = config(vec![Arc::new(HighestValueStrategy), Arc::new(HighestValueStrategy)]);
+        let report = MultithreadedSimulator::new(2).run(&config, &SimulationControl::new()).unwrap();
+        assert_eq!(report.strategies.len(), 2);
+        for (strategy, seat) in report.strategies.iter().zip(&report.seats) {
+            assert_eq!((strategy.name.as_str(), strategy.games, strategy.wins), ("Highest Value", 200, seat.wins));
+            assert_eq!(strategy.wins + strategy.draws + strategy.losses, 200);
+        }
+        assert_eq!(report.strategies[0].wins, report.strategies[1].losses);
+    }
+
+    #[test]
+    fn simulations_need_two_strategies() {
+        for strategies in [vec![], vec![Arc::new(RandomStrategy::default()) as Arc<dyn Strategy>]] {
+            let config = config(strategies);
+            assert_eq!(config.seat_strategies().err().map(|e| e.kind()), Some(io::ErrorKind::InvalidInput));
+            assert!(MultithreadedSimulator::new(2).run(&config, &SimulationControl::new()).is_err());
+        }
+    }
+}
@@ -1,65 +1,403 @@
-// File: src/distributed_simulator.rs
+// File: src/simulator_distributed.rs
 
-use mpi::traits::*;
-use mpi::topology::Communicator;
-use crate::{GameMode, OptimizationLevel, Strategy, GameResult};
-use crate::game::Game;
-use std::sync::Arc;
-use rand::Rng;
+use crate::Strategy;
+use crate::strategies::create_strategy;
+#[cfg(feature = "parallel")]
+use crate::simulator_paraller::ParallelSimulator;
+#[cfg(not(feature = "parallel"))]
+use crate::simulator_multithreded::MultithreadedSimulator;
+use crate::control::SimulationControl;
+use crate::simulation::{Simulator, SimulationConfig, SimulationStats, SimulatorType, SEATS};
+use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, TcpListener, TcpStream, ToSocketAddrs};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 
+pub const DEFAULT_ADDRESS: SocketAddr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 7878));
+
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+type LostWorkerCallback = Box<dyn Fn(SocketAddr, Range<usize>) + Send + Sync>;
+
+// Coordinator -> worker. Messages are newline-delimited JSON.
+#[derive(Serialize, Deserialize)]
+enum Request {
+    // Play games `first_game..first_game + config.num_games` of the run described by `config`.
+    Task { config: crate::SimulationConfig, first_game: usize },
+    Shutdown,
+}
+
+// Worker -> coordinator.
+#[derive(Serialize, Deserialize)]
+enum Response {
+    Stats(SimulationStats),
+    Error(String),
+}
+
+// Coordinator side of the TCP backend. Listens on `address`, hands each connected worker a slice
+// of `chunk_size` games at a time and merges the returned counts. A worker that disconnects or
+// doesn't answer within `task_timeout` is dropped, its slice goes back on the queue and
+// `on_lost_worker` is told. A worker that reports an error fails the run, as does having no
+// worker connected for `idle_timeout`.
 pub struct DistributedSimulator {
-    world: mpi::topology::SystemCommunicator,
+    pub address: SocketAddr,
+    pub chunk_size: usize,
+    pub task_timeout: Duration,
+    pub idle_timeout: Duration,
+    pub on_lost_worker: Option<LostWorkerCallback>,
 }
 
 impl DistributedSimulator {
-    pub fn new() -> Self {
-        let universe = mpi::initialize().unwrap();
-        let world = universe.world();
-        DistributedSimulator { world }
-    }
-
-    pub fn run_simulations(
-        &self,
-        mode: GameMode,
-        optimization: OptimizationLevel,
-        strategies: &[Arc<dyn Strategy>],
-        total_simulations: usize
-    ) -> Vec<(String, f64)> {
-        let world_size = self.world.size() as usize;
-        let world_rank = self.world.rank() as usize;
-        let local_simulations = total_simulations / world_size;
-
-        let mut rng = rand::thread_rng();
-        let local_results: Vec<GameResult> = (0..local_simulations)
-            .map(|_| {
-                let seed = rng.gen();
-                let mut game = Game::new(mode, optimization, seed);
-                game.play_game(&[strategies[0].as_ref(), strategies[1].as_ref()])
-            })
-            .collect();
+    pub fn new<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let address = address.to_socket_addrs()?.next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no socket address given"))?;
+        Ok(DistributedSimulator { address, ..DistributedSimulator::default() })
+    }
+
+    pub fn on_lost_worker<F: Fn(SocketAddr, Range<usize>) + Send + Sync + 'static>(mut self, callback: F) -> Self {
+        self.on_lost_worker = Some(Box::new(callback));
+        self
+    }
 
-        let mut global_results = if world_rank == 0 {
-            vec![GameResult::default(); total_simulations]
-        } else {
-            vec![]
+    // `simulate` on a listener that is already bound.
+    fn run(&self, listener: TcpListener, config: &SimulationConfig, control: &SimulationControl) -> io::Result<SimulationStats> {
+        listener.set_nonblocking(true)?;
+
+        let num_games = control.start(config.num_games);
+        let job = Job::new(config.first_game..config.first_game + num_games, self.chunk_size);
+
+        thread::scope(|scope| {
+            let mut idle_since = Instant::now();
+            while !job.is_finished(control) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let job = &job;
+                        job.workers.fetch_add(1, Ordering::Relaxed);
+                        scope.spawn(move || {
+                            self.serve(stream, config, job, control);
+                            job.workers.fetch_sub(1, Ordering::Relaxed);
+                        });
+                    },
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        if job.workers.load(Ordering::Relaxed) > 0 {
+                            idle_since = Instant::now();
+                        } else if idle_since.elapsed() >= self.idle_timeout {
+                            job.fail(io::Error::new(io::ErrorKind::TimedOut, format!("no worker connected for {:?}", self.idle_timeout)));
+                        }
+                        thread::sleep(POLL_INTERVAL);
+                    },
+                    // These only concern the one connection.
+                    Err(e) if matches!(e.kind(), io::ErrorKind::ConnectionAborted | io::ErrorKind::ConnectionReset | io::ErrorKind::Interrupted) => {},
+                    Err(e) => job.fail(e),
+                }
+            }
+        });
+
+        match job.error.into_inner().unwrap() {
+            Some(e) => Err(e),
+            None => Ok(job.stats.into_inner().unwrap()),
+        }
+    }
+
+    fn serve(&self, stream: TcpStream, config: &SimulationConfig, job: &Job, control: &SimulationControl) {
+        let Ok(peer) = stream.peer_addr() else { return };
+        let mut writer = stream;
+        let mut reader = match writer.try_clone() {
+            Ok(stream) => BufReader::new(stream),
+            Err(_) => return,
         };
+        if writer.set_nonblocking(false).is_err() || writer.set_read_timeout(Some(self.task_timeout)).is_err() {
+            return;
+        }
+
+        while let Some(range) = job.next_range(control) {
+            let mut task_config = config.echo();
+            task_config.num_games = range.len();
+            let task = Request::Task { config: task_config, first_game: range.start };
 
-        self.world.gather_into_root(&local_results[..], &mut global_results[..]);
+            match send(&mut writer, &task).and_then(|_| receive::<Response>(&mut reader)) {
+                Ok(Response::Stats(stats)) if stats.games as usize == range.len() => {
+                    job.complete(&stats, control);
+                },
+                Ok(Response::Error(message)) => {
+                    job.fail(io::Error::other(format!("worker {} failed: {}", peer, message)));
+                    return;
+                },
+                _ => {
+                    job.requeue(range.clone());
+                    if let Some(callback) = &self.on_lost_worker {
+                        callback(peer, range);
+                    }
+                    return;
+                },
+            }
+        }
+        let _ = send(&mut writer, &Request::Shutdown);
+    }
+}
 
-        if world_rank == 0 {
-            Self::analyze_results(&global_results, strategies)
-        } else {
-            vec![]
+impl Default for DistributedSimulator {
+    fn default() -> Self {
+        DistributedSimulator {
+            address: DEFAULT_ADDRESS,
+            chunk_size: 100_000,
+            task_timeout: Duration::from_secs(300),
+            idle_timeout: Duration::from_secs(600),
+            on_lost_worker: None,
         }
     }
+}
+
+impl Simulator for DistributedSimulator {
+    fn name(&self) -> &'static str { "Distributed" }
+
+    // Runs until every slice has been merged or the control handle stops the run. Workers may
+    // join at any point; slices in flight when a stop is requested are still collected. Fails
+    // when the coordinator can't listen on `address`, a worker reports an error or no worker
+    // is connected for `idle_timeout`.
+    fn simulate(&self, config: &SimulationConfig, control: &SimulationControl) -> io::Result<SimulationStats> {
+        self.run(TcpListener::bind(self.address)?, config, control)
+    }
+}
 
-    fn analyze_results(results: &[GameResult], strategies: &[Arc<dyn Strategy>]) -> Vec<(String, f64)> {
-        let wins: Vec<usize> = (0..strategies.len())
-            .map(|i| results.iter().filter(|r| r.winner == i).count())
+// Work shared by the coordinator's connection threads.
+struct Job {
+    queue: Mutex<VecDeque<Range<usize>>>,
+    stats: Mutex<SimulationStats>,
+    remaining: AtomicUsize,
+    workers: AtomicUsize,
+    error: Mutex<Option<io::Error>>,
+}
+
+impl Job {
+    fn new(games: Range<usize>, chunk_size: usize) -> Self {
+        let chunk_size = chunk_size.max(1);
+        let queue = games.clone().step_by(chunk_size)
+            .map(|start| start..(start + chunk_size).min(games.end))
             .collect();
-        
-        strategies.iter().enumerate()
-            .map(|(i, s)| (s.name().to_string(), wins[i] as f64 / results.len() as f64))
-            .collect()
+        Job {
+            queue: Mutex::new(queue),
+            stats: Mutex::new(SimulationStats::new(SEATS)),
+            remaining: AtomicUsize::new(games.len()),
+            workers: AtomicUsize::new(0),
+            error: Mutex::new(None),
+        }
+    }
+
+    fn is_finished(&self, control: &SimulationControl) -> bool {
+        self.remaining.load(Ordering::Relaxed) == 0 || control.should_stop() || self.error.lock().unwrap().is_some()
+    }
+
+    // Waits while the queue is empty but slices are still in flight, since a lost worker's
+    // slice comes back to the queue.
+    fn next_range(&self, control: &SimulationControl) -> Option<Range<usize>> {
+        loop {
+            if self.is_finished(control) {
+                return None;
+            }
+            if let Some(range) = self.queue.lock().unwrap().pop_front() {
+                return Some(range);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn requeue(&self, range: Range<usize>) {
+        self.queue.lock().unwrap().push_front(range);
+    }
+
+    fn complete(&self, stats: &SimulationStats, control: &SimulationControl) {
+        self.stats.lock().unwrap().merge(stats);
+        self.remaining.fetch_sub(stats.games as usize, Ordering::Relaxed);
+        control.add_games(stats.games as usize);
+    }
+
+    // Ends the run with `error`, keeping the first one.
+    fn fail(&self, error: io::Error) {
+        self.error.lock().unwrap().get_or_insert(error);
+    }
+}
+
+// Worker side: connects to a coordinator and plays the slices it is sent with `ParallelSimulator`
+// (`MultithreadedSimulator` without the `parallel` feature) until told to shut down.
+// Strategies are looked up by name with `create_strategy`.
+pub fn run_worker<A: ToSocketAddrs>(coordinator: A) -> io::Result<()> {
+    let mut writer = TcpStream::connect(coordinator)?;
+    let mut reader = BufReader::new(writer.try_clone()?);
+
+    loop {
+        let (config, first_game) = match receive::<Request>(&mut reader) {
+            Ok(Request::Task { config, first_game }) => (config, first_game),
+            Ok(Request::Shutdown) => return Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        let response = match resolve_strategies(&config.strategies) {
+            Ok(strategies) => {
+                let slice = SimulationConfig {
+                    mode: config.game_mode,
+                    optimization: config.optimization_level,
+                    rules: config.rules,
+                    strategies,
+                    num_games: config.num_games,
+                    first_game,
+                    seed: config.seed,
+                    simulator_type: SimulatorType::Distributed,
+                };
+                #[cfg(feature = "parallel")]
+                let stats = ParallelSimulator.simulate(&slice, &SimulationControl::new());
+                #[cfg(not(feature = "parallel"))]
+                let stats = MultithreadedSimulator::default().simulate(&slice, &SimulationControl::new());
+                match stats {
+                    Ok(stats) => Response::Stats(stats),
+                    Err(e) => Response::Error(e.to_string()),
+                }
+            },
+            Err(message) => Response::Error(message),
+        };
+        send(&mut writer, &response)?;
+    }
+}
+
+fn resolve_strategies(names: &[String]) -> Result<Vec<Arc<dyn Strategy>>, String> {
+    names.iter()
+        .map(|name| {
+            create_strategy(name)
+                .map(Arc::from)
+                .ok_or_else(|| format!("unknown strategy '{}'", name))
+        })
+        .collect()
+}
+
+fn send<T: Serialize>(stream: &mut TcpStream, message: &T) -> io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    stream.write_all(&line)
+}
+
+fn receive<T: DeserializeOwned>(reader: &mut BufReader<TcpStream>) -> io::Result<T> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "connection closed"));
+    }
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator_multithreded::MultithreadedSimulator;
+    use crate::strategies::HighestValueStrategy;
+    use crate::{GameMode, GameState, OptimizationLevel};
+
+    fn config(num_games: usize) -> SimulationConfig {
+        SimulationConfig {
+            mode: GameMode::Base,
+            optimization: OptimizationLevel::Standard,
+            rules: Default::default(),
+            strategies: resolve_strategies(&["Random".to_string(), "Highest Value".to_string()]).unwrap(),
+            num_games,
+            first_game: 0,
+            seed: 5,
+            simulator_type: SimulatorType::Distributed,
+        }
+    }
+
+    // A listener bound before the coordinator starts, so workers can connect straight away.
+    fn listener() -> (TcpListener, SocketAddr) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        (listener, address)
+    }
+
+    fn local_run(config: &SimulationConfig) -> serde_json::Value {
+        let stats = MultithreadedSimulator::new(2).simulate(config, &SimulationControl::new()).unwrap();
+        serde_json::to_value(&stats).unwrap()
+    }
+
+    #[test]
+    fn address_in_use_is_an_error() {
+        let (_taken, address) = listener();
+        let simulator = DistributedSimulator::new(address).unwrap();
+        assert!(simulator.simulate(&config(10), &SimulationControl::new()).is_err());
+    }
+
+    #[test]
+    fn worker_slices_add_up_to_a_local_run() {
+        let (listener, address) = listener();
+        let simulator = DistributedSimulator { chunk_size: 150, ..DistributedSimulator::new(address).unwrap() };
+        let config = config(1_000);
+
+        let stats = thread::scope(|scope| {
+            let coordinator = scope.spawn(|| simulator.run(listener, &config, &SimulationControl::new()));
+            run_worker(address).unwrap();
+            coordinator.join().unwrap().unwrap()
+        });
+        assert_eq!(serde_json::to_value(&stats).unwrap(), local_run(&config));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn a_lost_workers_slice_is_requeued() {
+        let (listener, address) = listener();
+        let lost = Arc::new(Mutex::new(Vec::new()));
+        let seen = lost.clone();
+        let simulator = DistributedSimulator { chunk_size: 150, ..DistributedSimulator::new(address).unwrap() }
+            .on_lost_worker(move |_, range| seen.lock().unwrap().push(range));
+        let config = config(400);
+
+        let stats = thread::scope(|scope| {
+            let coordinator = scope.spawn(|| simulator.run(listener, &config, &SimulationControl::new()));
+            // Takes the first slice and dies without answering.
+            let stream = TcpStream::connect(address).unwrap();
+            let task = receive::<Request>(&mut BufReader::new(stream.try_clone().unwrap())).unwrap();
+            assert!(matches!(task, Request::Task { first_game: 0, .. }));
+            drop(stream);
+            run_worker(address).unwrap();
+            coordinator.join().unwrap().unwrap()
+        });
+        assert_eq!(*lost.lock().unwrap(), vec![0..150]);
+        assert_eq!(serde_json::to_value(&stats).unwrap(), local_run(&config));
+    }
+
+    #[derive(Clone)]
+    struct Unregistered;
+
+    impl Strategy for Unregistered {
+        fn choose_move(&mut self, _state: &GameState, _roll: u8) -> u16 {
+            0
+        }
+
+        fn name(&self) -> &'static str {
+            "Unregistered"
+        }
+    }
+
+    #[test]
+    fn worker_errors_fail_the_run() {
+        let (listener, address) = listener();
+        let simulator = DistributedSimulator::new(address).unwrap();
+        let config = SimulationConfig { strategies: vec![Arc::new(Unregistered), Arc::new(HighestValueStrategy)], ..config(10) };
+
+        let result = thread::scope(|scope| {
+            let coordinator = scope.spawn(|| simulator.run(listener, &config, &SimulationControl::new()));
+            run_worker(address).unwrap();
+            coordinator.join().unwrap()
+        });
+        assert!(result.unwrap_err().to_string().contains("unknown strategy 'Unregistered'"));
+    }
+
+    #[test]
+    fn a_run_without_workers_times_out() {
+        let (listener, address) = listener();
+        let simulator = DistributedSimulator { idle_timeout: Duration::from_millis(100), ..DistributedSimulator::new(address).unwrap() };
+        let error = simulator.run(listener, &config(10), &SimulationControl::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+    }
+}
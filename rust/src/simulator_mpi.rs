@@ -0,0 +1,92 @@
+// File: src/simulator_mpi.rs
+
+use mpi::traits::*;
+use mpi::topology::Communicator;
+use mpi::datatype::PartitionMut;
+use mpi::Count;
+use crate::game::Game;
+use crate::rng::game_seed;
+use crate::control::{SimulationControl, CHECK_BATCH};
+use crate::simulation::{Simulator, SimulationConfig, SimulationStats, SEATS};
+use std::io;
+
+// Runs under `mpirun`. The universe is kept for the lifetime of the simulator, since dropping it
+// finalizes MPI; `new` returns None once MPI has been initialized in this process.
+pub struct MpiSimulator {
+    universe: mpi::environment::Universe,
+}
+
+impl MpiSimulator {
+    pub fn new() -> Option<Self> {
+        mpi::initialize().map(|universe| MpiSimulator { universe })
+    }
+}
+
+impl Simulator for MpiSimulator {
+    fn name(&self) -> &'static str { "MPI" }
+
+    // Every rank plays a contiguous share of the seed range, the first ranks one extra game
+    // each when the games don't divide evenly, and sends its serialized counts to rank 0.
+    // Only rank 0 returns the merged totals; the other ranks return empty stats.
+    // Each rank honours its own control handle, so a stopped rank still joins the gather.
+    fn simulate(&self, config: &SimulationConfig, control: &SimulationControl) -> io::Result<SimulationStats> {
+        let world = self.universe.world();
+        let world_size = world.size() as usize;
+        let world_rank = world.rank() as usize;
+        let num_games = control.start(config.num_games);
+        let games_per_rank = num_games / world_size;
+        let remainder = num_games % world_size;
+        let local_simulations = games_per_rank + usize::from(world_rank < remainder);
+        let first_local = config.first_game + world_rank * games_per_rank + world_rank.min(remainder);
+        let [mut a, mut b] = config.seat_strategies()?;
+
+        let mut local_stats = SimulationStats::new(SEATS);
+        let mut game = Game::from_rules(config.mode, config.optimization, config.rules, config.seed);
+        for i in 0..local_simulations {
+            let index = (first_local + i) as u64;
+            game.reset(game_seed(config.seed, index));
+            let outcome = game.play_pair_observed([a.as_mut(), b.as_mut()], &mut local_stats);
+            local_stats.record_outcome(config.mode, &outcome);
+            if (i + 1) % CHECK_BATCH == 0 {
+                control.add_games(CHECK_BATCH);
+                if control.should_stop() {
+                    break;
+                }
+            }
+        }
+        control.add_games(local_stats.games as usize % CHECK_BATCH);
+
+        let local_bytes = serde_json::to_vec(&local_stats)?;
+        let local_len = local_bytes.len() as Count;
+        let root = world.process_at_rank(0);
+
+        if world_rank == 0 {
+            let mut lengths = vec![0 as Count; world_size];
+            root.gather_into_root(&local_len, &mut lengths[..]);
+
+            let displacements: Vec<Count> = lengths.iter()
+                .scan(0, |offset, &len| {
+                    let start = *offset;
+                    *offset += len;
+                    Some(start)
+                })
+                .collect();
+            let mut buffer = vec![0u8; lengths.iter().sum::<Count>() as usize];
+            {
+                let mut partition = PartitionMut::new(&mut buffer[..], &lengths[..], &displacements[..]);
+                root.gather_varcount_into_root(&local_bytes[..], &mut partition);
+            }
+
+            let mut stats = SimulationStats::new(SEATS);
+            for (&start, &len) in displacements.iter().zip(&lengths) {
+                let chunk = &buffer[start as usize..(start + len) as usize];
+                stats.merge(&serde_json::from_slice(chunk)?);
+            }
+            Ok(stats)
+        } else {
+            root.gather_into(&local_len);
+            root.gather_varcount_into(&local_bytes[..]);
+            Ok(SimulationStats::new(SEATS))
+        }
+    }
+}
@@ -1,67 +1,128 @@
 // File: src/multithreaded_simulator.rs
 
-use std::sync::{Arc, Mutex};
+use std::io;
+use std::ops::Range;
 use std::thread;
-use crate::{GameMode, OptimizationLevel, Strategy, GameResult};
 use crate::game::Game;
-use rand::Rng;
+use crate::rng::game_seed;
+use crate::control::{SimulationControl, CHECK_BATCH};
+use crate::simulation::{Simulator, SimulationConfig, SimulationStats, SEATS};
 
-pub struct MultithreadedSimulator;
+pub struct MultithreadedSimulator {
+    pub num_threads: usize,
+}
 
 impl MultithreadedSimulator {
-    pub fn run_simulations(
-        mode: GameMode,
-        optimization: OptimizationLevel,
-        strategies: &[Arc<dyn Strategy>],
-        total_games: usize,
-        num_threads: usize
-    ) -> Vec<(String, f64)> {
-        let games_per_thread = total_games / num_threads;
-        let results = Arc::new(Mutex::new(Vec::new()));
-        let games_completed = Arc::new(Mutex::new(0));
+    pub fn new(num_threads: usize) -> Self {
+        MultithreadedSimulator {
+            num_threads: num_threads.max(1),
+        }
+    }
+}
 
-        let mut handles = vec![];
+impl Default for MultithreadedSimulator {
+    fn default() -> Self {
+        Self::new(thread::available_parallelism().map_or(1, |n| n.get()))
+    }
+}
 
-        for _ in 0..num_threads {
-            let results = Arc::clone(&results);
-            let games_completed = Arc::clone(&games_completed);
-            let strategies = strategies.to_vec();
-            let handle = thread::spawn(move || {
-                let mut rng = rand::thread_rng();
-                for _ in 0..games_per_thread {
-                    let mut game = Game::new(mode, optimization, seed);
-                    let game_result = game.play_game(&[strategies[0].as_ref(), strategies[1].as_ref()]);
-                    
-                    {
-                        let mut results = results.lock().unwrap();
-                        results.push(game_result);
-                    }
-                    
-                    {
-                        let mut completed = games_completed.lock().unwrap();
-                        *completed += 1;
+// Consecutive game ranges, one per thread, with the games that don't divide evenly going one
+// each to the first threads.
+fn worker_ranges(first_game: usize, num_games: usize, num_threads: usize) -> Vec<Range<usize>> {
+    let games_per_thread = num_games / num_threads;
+    let remainder = num_games % num_threads;
+    let mut start = first_game;
+    (0..num_threads)
+        .map(|thread_index| {
+            let count = games_per_thread + usize::from(thread_index < remainder);
+            start += count;
+            start - count..start
+        })
+        .collect()
+}
+
+impl Simulator for MultithreadedSimulator {
+    fn name(&self) -> &'static str { "Multithreaded" }
+
+    // Each worker owns a contiguous range of game indices and aggregates into its own
+    // `SimulationStats`; the only shared state is the control handle, touched once per batch.
+    fn simulate(&self, config: &SimulationConfig, control: &SimulationControl) -> io::Result<SimulationStats> {
+        let num_games = control.start(config.num_games);
+
+        thread::scope(|scope| {
+            let mut handles = Vec::with_capacity(self.num_threads);
+            for range in worker_ranges(config.first_game, num_games, self.num_threads) {
+                let [mut a, mut b] = config.seat_strategies()?;
+
+                handles.push(scope.spawn(move || {
+                    let mut stats = SimulationStats::new(SEATS);
+                    let mut game = Game::from_rules(config.mode, config.optimization, config.rules, config.seed);
+                    let mut pending = 0;
+                    for index in range {
+                        game.reset(game_seed(config.seed, index as u64));
+                        let outcome = game.play_pair_observed([a.as_mut(), b.as_mut()], &mut stats);
+                        stats.record_outcome(config.mode, &outcome);
+                        pending += 1;
+                        if pending == CHECK_BATCH {
+                            control.add_games(pending);
+                            pending = 0;
+                            if control.should_stop() {
+                                break;
+                            }
+                        }
                     }
-                }
-            });
-            handles.push(handle);
-        }
+                    control.add_games(pending);
+                    stats
+                }));
+            }
 
-        for handle in handles {
-            handle.join().unwrap();
-        }
+            let mut stats = SimulationStats::new(SEATS);
+            for handle in handles {
+                stats.merge(&handle.join().unwrap());
+            }
+            Ok(stats)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let final_results = results.lock().unwrap();
-        Self::analyze_results(&final_results, strategies)
+    #[test]
+    fn the_remainder_goes_to_the_first_threads() {
+        assert_eq!(worker_ranges(100, 10, 4), vec![100..103, 103..106, 106..108, 108..110]);
+        assert_eq!(worker_ranges(0, 2, 3), vec![0..1, 1..2, 2..2]);
+        assert_eq!(worker_ranges(0, 9, 3), vec![0..3, 3..6, 6..9]);
     }
 
-    fn analyze_results(results: &[GameResult], strategies: &[Arc<dyn Strategy>]) -> Vec<(String, f64)> {
-        let mut wins = vec![0; strategies.len()];
-        for result in results {
-            wins[result.winner] += 1;
+    #[cfg(feature = "serde")]
+    #[test]
+    fn thread_count_does_not_change_the_results() {
+        use crate::game::GameRules;
+        use crate::simulation::SimulatorType;
+        use crate::strategies::{HighestValueStrategy, RandomStrategy};
+        use crate::{GameMode, OptimizationLevel};
+        use std::sync::Arc;
+
+        let config = SimulationConfig {
+            mode: GameMode::Base,
+            optimization: OptimizationLevel::Standard,
+            rules: GameRules::default(),
+            strategies: vec![Arc::new(RandomStrategy::default()), Arc::new(HighestValueStrategy)],
+            num_games: 1_001,
+            first_game: 0,
+            seed: 17,
+            simulator_type: SimulatorType::Multithreaded,
+        };
+        let stats = |threads: usize| {
+            let stats = MultithreadedSimulator::new(threads).simulate(&config, &SimulationControl::new()).unwrap();
+            serde_json::to_value(&stats).unwrap()
+        };
+        let single = stats(1);
+        assert_eq!(single["games"], 1_001);
+        for threads in [2, 3, 8] {
+            assert_eq!(stats(threads), single, "{} threads", threads);
         }
-        
-        strategies.iter().enumerate()
-            .map(|(i, s)| (s.name().to_string(), wins[i] as f64 / results.len() as f64))
-            .collect()
     }
-}
\ No newline at end of file
+}
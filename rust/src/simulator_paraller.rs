@@ -1,40 +1,117 @@
 // File: src/parallel_simulator.rs
 
 use rayon::prelude::*;
-use crate::{GameMode, OptimizationLevel, Strategy, GameResult};
-use crate::game::Game;
-use std::sync::Arc;
-use rand::Rng;
+use crate::{GameMode, OptimizationLevel, Strategy, GameOutcome};
+use crate::game::{DiceKind, DiceModel, Game, GameRules, ReseedableRng, WeightedDice};
+use crate::rng::{game_seed, Xoroshiro128Plus};
+use crate::control::{SimulationControl, CHECK_BATCH};
+use crate::simulation::{Simulator, SimulationConfig, SimulationStats, SEATS};
+use std::io;
 
 pub struct ParallelSimulator;
 
 impl ParallelSimulator {
-    pub fn run_simulations(
-        mode: GameMode,
-        optimization: OptimizationLevel,
-        strategies: &[Arc<dyn Strategy>],
-        total_games: usize
-    ) -> Vec<(String, f64)> {
-        let results: Vec<GameResult> = (0..total_games)
-            .into_par_iter()
-            .map(|_| {
-                let mut rng = rand::thread_rng();
-                let seed = rng.gen();
-                let mut game = Game::new(mode, optimization, seed);
-                game.play_game(&[strategies[0].as_ref(), strategies[1].as_ref()])
-            })
-            .collect();
-
-        Self::analyze_results(&results, strategies)
+    // Fast path for callers that know both strategy types at compile time. Moves are chosen through
+    // `Game::play_game_static`, and at the Fast and UltraOptimized levels with weighted dice the
+    // generator and dice are concrete types as well. Produces the same counts as `simulate` for
+    // the same rules and seed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn simulate_static<A: Strategy + Clone, B: Strategy + Clone>(mode: GameMode, optimization: OptimizationLevel, rules: GameRules, a: &A, b: &B, num_games: usize, seed: u64, control: &SimulationControl) -> SimulationStats {
+        match (optimization, rules.dice) {
+            (OptimizationLevel::Fast | OptimizationLevel::UltraOptimized, None | Some(DiceKind::Weighted)) => {
+                play_batches(mode, 0, num_games, seed, control,
+                    || (Game::with_parts(mode, Xoroshiro128Plus::new(seed), WeightedDice::new()).with_rules(rules).with_seed(seed), (a.clone(), b.clone())),
+                    |game, (a, b), stats| game.play_static_observed(a, b, stats))
+            },
+            _ => {
+                play_batches(mode, 0, num_games, seed, control,
+                    || (Game::from_rules(mode, optimization, rules, seed), (a.clone(), b.clone())),
+                    |game, (a, b), stats| game.play_static_observed(a, b, stats))
+            },
+        }
     }
+}
 
-    fn analyze_results(results: &[GameResult], strategies: &[Arc<dyn Strategy>]) -> Vec<(String, f64)> {
-        let wins: Vec<usize> = (0..strategies.len())
-            .map(|i| results.iter().filter(|r| r.winner == i).count())
-            .collect();
-        
-        strategies.iter().enumerate()
-            .map(|(i, s)| (s.name().to_string(), wins[i] as f64 / results.len() as f64))
-            .collect()
+// Splits the seed range into `CHECK_BATCH`-sized batches for rayon. Each rayon job folds its batches
+// into one game, one copy of the strategies and one set of counts, and batches started after the
+// control handle asks to stop are skipped.
+fn play_batches<R, D, W, G, P>(mode: GameMode, first_game: usize, num_games: usize, seed: u64, control: &SimulationControl, make_worker: G, play: P) -> SimulationStats
+where
+    R: ReseedableRng + Send,
+    D: DiceModel + Send,
+    W: Send,
+    G: Fn() -> (Game<R, D>, W) + Sync + Send,
+    P: Fn(&mut Game<R, D>, &mut W, &mut SimulationStats) -> GameOutcome + Sync + Send,
+{
+    let num_games = control.start(num_games);
+    let num_batches = num_games.div_ceil(CHECK_BATCH);
+
+    (0..num_batches)
+        .into_par_iter()
+        .fold(|| (make_worker(), SimulationStats::new(SEATS)), |((mut game, mut players), mut stats), batch| {
+            if control.should_stop() {
+                return ((game, players), stats);
+            }
+            let start = first_game + batch * CHECK_BATCH;
+            let end = (start + CHECK_BATCH).min(first_game + num_games);
+            for i in start..end {
+                game.reset(game_seed(seed, i as u64));
+                let outcome = play(&mut game, &mut players, &mut stats);
+                stats.record_outcome(mode, &outcome);
+            }
+            control.add_games(end - start);
+            ((game, players), stats)
+        })
+        .map(|(_, stats)| stats)
+        .reduce(|| SimulationStats::new(SEATS), |mut a, b| {
+            a.merge(&b);
+            a
+        })
+}
+
+impl Simulator for ParallelSimulator {
+    fn name(&self) -> &'static str { "Parallel" }
+
+    fn simulate(&self, config: &SimulationConfig, control: &SimulationControl) -> io::Result<SimulationStats> {
+        let seats = config.seat_strategies()?;
+        Ok(play_batches(config.mode, config.first_game, config.num_games, config.seed, control,
+            || (Game::from_rules(config.mode, config.optimization, config.rules, config.seed), seats.clone()),
+            |game, [a, b], stats| game.play_pair_observed([a.as_mut(), b.as_mut()], stats)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::SimulatorType;
+    use crate::strategies::{HighestValueStrategy, RandomStrategy};
+    use std::sync::Arc;
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn static_path_matches_simulate() {
+        let small = GameRules { board_size: 9, rounds: 3, dice: None };
+        let cases = [
+            (OptimizationLevel::Fast, GameRules::default()),
+            (OptimizationLevel::UltraOptimized, small),
+            (OptimizationLevel::Fast, GameRules { dice: Some(DiceKind::TwoDice), ..small }),
+            (OptimizationLevel::Standard, small),
+        ];
+        for (optimization, rules) in cases {
+            let (a, b) = (RandomStrategy::default(), HighestValueStrategy);
+            let config = SimulationConfig {
+                mode: GameMode::Base,
+                optimization,
+                rules,
+                strategies: vec![Arc::new(a.clone()), Arc::new(b.clone())],
+                num_games: 600,
+                first_game: 0,
+                seed: 5,
+                simulator_type: SimulatorType::Parallel,
+            };
+            let dynamic = ParallelSimulator.simulate(&config, &SimulationControl::new()).unwrap();
+            let fast = ParallelSimulator::simulate_static(GameMode::Base, optimization, rules, &a, &b, 600, 5, &SimulationControl::new());
+            assert_eq!(serde_json::to_value(&fast).unwrap(), serde_json::to_value(&dynamic).unwrap(), "{:?} {:?}", optimization, rules);
+        }
     }
-}
\ No newline at end of file
+}
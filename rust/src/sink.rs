@@ -0,0 +1,692 @@
+// File: src/sink.rs
+
+use crate::control::{SimulationControl, CHECK_BATCH};
+use crate::experiment::OutputFormat;
+use crate::game::Game;
+use crate::rng::game_seed;
+use crate::simulation::{SimulationConfig, SimulationReport, SimulationStats, SEATS};
+use crate::sweep::SweepRow;
+use crate::tournament::Standing;
+use crate::{GameMode, GameResult, SimulationResult};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldKind {
+    Int,
+    Float,
+    Text,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Column {
+    pub name: String,
+    pub kind: FieldKind,
+}
+
+impl Column {
+    pub fn int(name: &str) -> Self {
+        Column { name: name.to_string(), kind: FieldKind::Int }
+    }
+
+    pub fn float(name: &str) -> Self {
+        Column { name: name.to_string(), kind: FieldKind::Float }
+    }
+
+    pub fn text(name: &str) -> Self {
+        Column { name: name.to_string(), kind: FieldKind::Text }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Field {
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Null,
+}
+
+// A flat row. Every record written to one sink must have the same columns; sinks take them
+// from the first record.
+pub trait Record {
+    fn columns(&self) -> Vec<Column>;
+    fn fields(&self) -> Vec<Field>;
+}
+
+// Writes records as they arrive. `finish` flushes buffered rows and any footer, and must be
+// called once the last record is written.
+pub trait RecordSink {
+    fn write(&mut self, record: &dyn Record) -> io::Result<()>;
+    fn finish(&mut self) -> io::Result<()>;
+}
+
+// Opens a sink on `path` for a tabular format: CSV, JSON (an array of objects), JSON Lines or,
+// with the `parquet` feature, Parquet.
+pub fn create_sink(path: &Path, format: OutputFormat) -> io::Result<Box<dyn RecordSink>> {
+    let file = File::create(path)?;
+    Ok(match format {
+        OutputFormat::Csv => Box::new(CsvSink::new(BufWriter::new(file))),
+        OutputFormat::Json => Box::new(JsonSink::array(BufWriter::new(file))),
+        OutputFormat::Jsonl => Box::new(JsonSink::lines(BufWriter::new(file))),
+        #[cfg(feature = "parquet")]
+        OutputFormat::Parquet => Box::new(ParquetSink::new(file)),
+    })
+}
+
+// Writes every record to each sink in turn.
+impl RecordSink for Vec<Box<dyn RecordSink + '_>> {
+    fn write(&mut self, record: &dyn Record) -> io::Result<()> {
+        self.iter_mut().try_for_each(|sink| sink.write(record))
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.iter_mut().try_for_each(|sink| sink.finish())
+    }
+}
+
+pub struct CsvSink<W: Write> {
+    writer: W,
+    header_written: bool,
+}
+
+impl<W: Write> CsvSink<W> {
+    pub fn new(writer: W) -> Self {
+        CsvSink { writer, header_written: false }
+    }
+}
+
+impl<W: Write> RecordSink for CsvSink<W> {
+    fn write(&mut self, record: &dyn Record) -> io::Result<()> {
+        if !self.header_written {
+            let header: Vec<String> = record.columns().iter().map(|c| csv_field(&c.name)).collect();
+            writeln!(self.writer, "{}", header.join(","))?;
+            self.header_written = true;
+        }
+        let fields: Vec<String> = record.fields().iter()
+            .map(|field| match field {
+                Field::Int(value) => value.to_string(),
+                Field::Float(value) => value.to_string(),
+                Field::Text(value) => csv_field(value),
+                Field::Null => String::new(),
+            })
+            .collect();
+        writeln!(self.writer, "{}", fields.join(","))
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// One JSON object per record, either one per line or wrapped in an array.
+pub struct JsonSink<W: Write> {
+    writer: W,
+    array: bool,
+    columns: Vec<Column>,
+    count: u64,
+}
+
+impl<W: Write> JsonSink<W> {
+    pub fn lines(writer: W) -> Self {
+        JsonSink { writer, array: false, columns: Vec::new(), count: 0 }
+    }
+
+    pub fn array(writer: W) -> Self {
+        JsonSink { writer, array: true, columns: Vec::new(), count: 0 }
+    }
+}
+
+impl<W: Write> RecordSink for JsonSink<W> {
+    fn write(&mut self, record: &dyn Record) -> io::Result<()> {
+        if self.count == 0 {
+            self.columns = record.columns();
+        }
+        if self.array {
+            self.writer.write_all(if self.count == 0 { b"[\n" } else { b",\n" })?;
+        }
+        // Written key by key so the keys keep the column order.
+        self.writer.write_all(b"{")?;
+        for (i, (column, field)) in self.columns.iter().zip(record.fields()).enumerate() {
+            if i > 0 {
+                self.writer.write_all(b",")?;
+            }
+            serde_json::to_writer(&mut self.writer, &column.name)?;
+            self.writer.write_all(b":")?;
+            let value = match field {
+                Field::Int(value) => Value::from(value),
+                Field::Float(value) => Value::from(value),
+                Field::Text(value) => Value::from(value),
+                Field::Null => Value::Null,
+            };
+            serde_json::to_writer(&mut self.writer, &value)?;
+        }
+        self.writer.write_all(b"}")?;
+        if !self.array {
+            self.writer.write_all(b"\n")?;
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if self.array {
+            self.writer.write_all(if self.count == 0 { b"[]\n" } else { b"\n]\n" })?;
+        }
+        self.writer.flush()
+    }
+}
+
+#[cfg(feature = "parquet")]
+pub use parquet_sink::ParquetSink;
+
+#[cfg(feature = "parquet")]
+mod parquet_sink {
+    use super::{Column, Field, FieldKind, Record, RecordSink};
+    use parquet::basic::{ConvertedType, Repetition, Type as PhysicalType};
+    use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::types::Type;
+    use std::fs::File;
+    use std::io;
+    use std::sync::Arc;
+
+    // Rows are buffered and written one row group at a time.
+    const ROW_GROUP_ROWS: usize = 65_536;
+
+    // Every column is optional, so `Field::Null` is stored as a missing value.
+    pub struct ParquetSink {
+        file: Option<File>,
+        writer: Option<SerializedFileWriter<File>>,
+        columns: Vec<Column>,
+        rows: Vec<Vec<Field>>,
+    }
+
+    impl ParquetSink {
+        pub fn new(file: File) -> Self {
+            ParquetSink { file: Some(file), writer: None, columns: Vec::new(), rows: Vec::new() }
+        }
+
+        fn open(&mut self, columns: Vec<Column>) -> io::Result<()> {
+            let fields = columns.iter()
+                .map(|column| {
+                    let builder = match column.kind {
+                        FieldKind::Int => Type::primitive_type_builder(&column.name, PhysicalType::INT64),
+                        FieldKind::Float => Type::primitive_type_builder(&column.name, PhysicalType::DOUBLE),
+                        FieldKind::Text => Type::primitive_type_builder(&column.name, PhysicalType::BYTE_ARRAY)
+                            .with_converted_type(ConvertedType::UTF8),
+                    };
+                    builder.with_repetition(Repetition::OPTIONAL).build().map(Arc::new)
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(io::Error::other)?;
+            let schema = Type::group_type_builder("schema").with_fields(fields).build().map_err(io::Error::other)?;
+            let file = self.file.take().expect("parquet sink opened twice");
+            let writer = SerializedFileWriter::new(file, Arc::new(schema), Arc::new(WriterProperties::builder().build()))
+                .map_err(io::Error::other)?;
+            self.writer = Some(writer);
+            self.columns = columns;
+            Ok(())
+        }
+
+        fn flush_rows(&mut self) -> io::Result<()> {
+            let writer = match &mut self.writer {
+                Some(writer) if !self.rows.is_empty() => writer,
+                _ => return Ok(()),
+            };
+            let mut row_group = writer.next_row_group().map_err(io::Error::other)?;
+            let mut index = 0;
+            while let Some(mut column) = row_group.next_column().map_err(io::Error::other)? {
+                let cells = self.rows.iter().map(|row| &row[index]);
+                let levels: Vec<i16> = cells.clone().map(|f| i16::from(*f != Field::Null)).collect();
+                match self.columns[index].kind {
+                    FieldKind::Int => {
+                        let values: Vec<i64> = cells.filter_map(|f| match f { Field::Int(v) => Some(*v), _ => None }).collect();
+                        column.typed::<Int64Type>().write_batch(&values, Some(&levels), None)
+                    },
+                    FieldKind::Float => {
+                        let values: Vec<f64> = cells.filter_map(|f| match f {
+                            Field::Float(v) => Some(*v),
+                            Field::Int(v) => Some(*v as f64),
+                            _ => None,
+                        }).collect();
+                        column.typed::<DoubleType>().write_batch(&values, Some(&levels), None)
+                    },
+                    FieldKind::Text => {
+                        let values: Vec<ByteArray> = cells.filter_map(|f| match f { Field::Text(v) => Some(v.as_str().into()), _ => None }).collect();
+                        column.typed::<ByteArrayType>().write_batch(&values, Some(&levels), None)
+                    },
+                }.map_err(io::Error::other)?;
+                column.close().map_err(io::Error::other)?;
+                index += 1;
+            }
+            row_group.close().map_err(io::Error::other)?;
+            self.rows.clear();
+            Ok(())
+        }
+    }
+
+    impl RecordSink for ParquetSink {
+        fn write(&mut self, record: &dyn Record) -> io::Result<()> {
+            if self.writer.is_none() {
+                self.open(record.columns())?;
+            }
+            let fields = record.fields();
+            let mismatch = fields.iter().zip(&self.columns).any(|(field, column)| {
+                !matches!((field, column.kind), (Field::Null, _) | (Field::Int(_), FieldKind::Int | FieldKind::Float) | (Field::Float(_), FieldKind::Float) | (Field::Text(_), FieldKind::Text))
+            });
+            if fields.len() != self.columns.len() || mismatch {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "record does not match the parquet schema"));
+            }
+            self.rows.push(fields);
+            if self.rows.len() >= ROW_GROUP_ROWS {
+                self.flush_rows()?;
+            }
+            Ok(())
+        }
+
+        fn finish(&mut self) -> io::Result<()> {
+            self.flush_rows()?;
+            if let Some(writer) = self.writer.take() {
+                writer.close().map_err(io::Error::other)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+// One game of a recorded run. `winner` is None for a draw.
+#[derive(Clone, Debug)]
+pub struct GameRecord {
+    pub game: u64,
+    pub strategies: [&'static str; 2],
+    pub winner: Option<usize>,
+    pub scores: [u32; 2],
+    pub num_rounds: u8,
+}
+
+impl Record for GameRecord {
+    fn columns(&self) -> Vec<Column> {
+        vec![
+            Column::int("game"),
+            Column::text("strategy1"),
+            Column::text("strategy2"),
+            Column::text("winner"),
+            Column::int("score1"),
+            Column::int("score2"),
+            Column::int("num_rounds"),
+        ]
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        vec![
+            Field::Int(self.game as i64),
+            Field::Text(self.strategies[0].to_string()),
+            Field::Text(self.strategies[1].to_string()),
+            self.winner.map_or(Field::Null, |seat| Field::Text(self.strategies[seat].to_string())),
+            Field::Int(self.scores[0] as i64),
+            Field::Int(self.scores[1] as i64),
+            Field::Int(self.num_rounds as i64),
+        ]
+    }
+}
+
+// Plays `config` on one worker per core and streams a `GameRecord` for every game to `sink` as
+// batches finish, so records arrive in batch order rather than game order. Games are seeded like
+// every simulator's, so unless the control handle stops the run early, the returned counts equal
+// those of a `run_simulation` of the same config at every optimization level.
+// The sink is not finished, so several runs can share one.
+pub fn record_games(config: &SimulationConfig, control: &SimulationControl, sink: &mut dyn RecordSink) -> io::Result<SimulationStats> {
+    let num_games = control.start(config.num_games);
+    let end = config.first_game + num_games;
+    let num_threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let next_batch = AtomicUsize::new(0);
+    let seats = config.seat_strategies()?;
+    let names = [seats[0].name(), seats[1].name()];
+
+    let (stats, written) = thread::scope(|scope| {
+        let (sender, receiver) = mpsc::sync_channel::<Vec<GameRecord>>(num_threads * 2);
+        let handles: Vec<_> = (0..num_threads)
+            .map(|_| {
+                let sender = sender.clone();
+                let next_batch = &next_batch;
+                let [mut a, mut b] = seats.clone();
+                scope.spawn(move || {
+                    let mut stats = SimulationStats::new(SEATS);
+                    let mut game = Game::from_rules(config.mode, config.optimization, config.rules, config.seed);
+                    loop {
+                        let start = config.first_game + next_batch.fetch_add(1, Ordering::Relaxed) * CHECK_BATCH;
+                        if start >= end || control.should_stop() {
+                            break;
+                        }
+                        let batch_end = (start + CHECK_BATCH).min(end);
+                        let mut records = Vec::with_capacity(batch_end - start);
+                        for index in start..batch_end {
+                            game.reset(game_seed(config.seed, index as u64));
+                            let outcome = game.play_pair_observed([a.as_mut(), b.as_mut()], &mut stats);
+                            stats.record_outcome(config.mode, &outcome);
+                            let draw = config.mode == GameMode::Base && outcome.scores[0] == outcome.scores[1];
+                            records.push(GameRecord {
+                                game: index as u64,
+                                strategies: names,
+                                winner: if draw { None } else { Some(outcome.winner) },
+                                scores: outcome.scores,
+                                num_rounds: outcome.num_rounds,
+                            });
+                        }
+                        control.add_games(batch_end - start);
+                        // The receiver is gone once writing failed.
+                        if sender.send(records).is_err() {
+                            break;
+                        }
+                    }
+                    stats
+                })
+            })
+            .collect();
+        drop(sender);
+
+        let mut written = Ok(());
+        for records in receiver {
+            written = records.iter().try_for_each(|record| sink.write(record));
+            if written.is_err() {
+                break;
+            }
+        }
+        let stats = handles.into_iter()
+            .map(|handle| handle.join().unwrap())
+            .fold(SimulationStats::new(SEATS), |mut total, stats| {
+                total.merge(&stats);
+                total
+            });
+        (stats, written)
+    });
+    control.finish();
+    written.map(|_| stats)
+}
+
+// One game of a stored `SimulationResult`, under its key in the results map.
+pub struct StoredGame<'a> {
+    pub key: &'a str,
+    pub game: usize,
+    pub result: &'a GameResult,
+}
+
+impl Record for StoredGame<'_> {
+    fn columns(&self) -> Vec<Column> {
+        vec![
+            Column::text("key"),
+            Column::int("game"),
+            Column::int("winner"),
+            Column::int("score1"),
+            Column::int("score2"),
+            Column::int("num_rounds"),
+        ]
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        let score = |seat: usize| self.result.scores.get(seat).map_or(Field::Null, |&s| Field::Int(s as i64));
+        vec![
+            Field::Text(self.key.to_string()),
+            Field::Int(self.game as i64),
+            Field::Int(self.result.winner as i64),
+            score(0),
+            score(1),
+            Field::Int(self.result.num_rounds as i64),
+        ]
+    }
+}
+
+// Converts a stored result to rows, keys in sorted order, without building another copy of it.
+pub fn write_results(result: &SimulationResult, sink: &mut dyn RecordSink) -> io::Result<()> {
+    let mut keys: Vec<&String> = result.results.keys().collect();
+    keys.sort();
+    for key in keys {
+        for (game, game_result) in result.results[key].iter().enumerate() {
+            sink.write(&StoredGame { key, game, result: game_result })?;
+        }
+    }
+    Ok(())
+}
+
+// Aggregate row for one pairing of a simulation report.
+pub struct PairingSummary<'a>(pub &'a SimulationReport);
+
+impl Record for PairingSummary<'_> {
+    fn columns(&self) -> Vec<Column> {
+        vec![
+            Column::text("simulator"),
+            Column::text("strategy1"),
+            Column::text("strategy2"),
+            Column::int("games"),
+            Column::int("draws"),
+            Column::int("wins1"),
+            Column::int("wins2"),
+            Column::float("mean_score1"),
+            Column::float("mean_score2"),
+            Column::float("mean_rounds"),
+        ]
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        let report = self.0;
+        let seat = |i: usize| report.seats.get(i);
+        vec![
+            Field::Text(report.simulator.clone()),
+            Field::Text(report.config.strategies[0].clone()),
+            Field::Text(report.config.strategies[1].clone()),
+            Field::Int(report.games as i64),
+            Field::Int(report.draws as i64),
+            Field::Int(seat(0).map_or(0, |s| s.wins) as i64),
+            Field::Int(seat(1).map_or(0, |s| s.wins) as i64),
+            Field::Float(seat(0).map_or(0.0, |s| s.mean_score)),
+            Field::Float(seat(1).map_or(0.0, |s| s.mean_score)),
+            Field::Float(report.mean_rounds),
+        ]
+    }
+}
+
+// A tournament standing with its 1-based rank.
+pub struct RankedStanding<'a>(pub usize, pub &'a Standing);
+
+impl Record for RankedStanding<'_> {
+    fn columns(&self) -> Vec<Column> {
+        vec![
+            Column::int("rank"),
+            Column::text("strategy"),
+            Column::float("points"),
+            Column::int("match_wins"),
+            Column::int("match_losses"),
+            Column::int("match_draws"),
+            Column::int("game_wins"),
+            Column::int("game_losses"),
+            Column::int("game_draws"),
+        ]
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        let RankedStanding(rank, s) = *self;
+        vec![
+            Field::Int(rank as i64),
+            Field::Text(s.name.clone()),
+            Field::Float(s.points),
+            Field::Int(s.match_wins as i64),
+            Field::Int(s.match_losses as i64),
+            Field::Int(s.match_draws as i64),
+            Field::Int(s.game_wins as i64),
+            Field::Int(s.game_losses as i64),
+            Field::Int(s.game_draws as i64),
+        ]
+    }
+}
+
+// Swept parameters become one column each, named by their path.
+impl Record for SweepRow {
+    fn columns(&self) -> Vec<Column> {
+        let mut columns = vec![Column::int("cell")];
+        columns.extend(self.parameters.iter().map(|(name, value)| match value {
+            Value::Number(n) if n.is_i64() || n.is_u64() => Column::int(name),
+            Value::Number(_) => Column::float(name),
+            _ => Column::text(name),
+        }));
+        columns.extend([
+            Column::text("strategy"),
+            Column::int("rank"),
+            Column::int("games"),
+            Column::int("wins"),
+            Column::int("draws"),
+            Column::int("losses"),
+            Column::float("win_rate"),
+        ]);
+        columns
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        let mut fields = vec![Field::Int(self.cell as i64)];
+        fields.extend(self.parameters.values().map(|value| match value {
+            Value::Number(n) => n.as_i64().map_or_else(|| Field::Float(n.as_f64().unwrap_or(f64::NAN)), Field::Int),
+            Value::String(s) => Field::Text(s.clone()),
+            Value::Null => Field::Null,
+            other => Field::Text(other.to_string()),
+        }));
+        fields.extend([
+            Field::Text(self.strategy.clone()),
+            Field::Int(self.rank as i64),
+            Field::Int(self.games as i64),
+            Field::Int(self.wins as i64),
+            Field::Int(self.draws as i64),
+            Field::Int(self.losses as i64),
+            Field::Float(self.win_rate),
+        ]);
+        fields
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{create_simulator, SimulatorType};
+    use crate::strategies::{HighestValueStrategy, RandomStrategy};
+    use crate::OptimizationLevel;
+    use std::sync::Arc;
+
+    fn config(optimization: OptimizationLevel) -> SimulationConfig {
+        SimulationConfig {
+            mode: GameMode::Base,
+            optimization,
+            rules: Default::default(),
+            strategies: vec![Arc::new(RandomStrategy::default()), Arc::new(HighestValueStrategy)],
+            num_games: 300,
+            first_game: 0,
+            seed: 11,
+            simulator_type: SimulatorType::Multithreaded,
+        }
+    }
+
+    #[test]
+    fn recorded_games_match_the_simulators() {
+        for optimization in [OptimizationLevel::Standard, OptimizationLevel::Fast] {
+            let config = config(optimization);
+            let mut sink = JsonSink::lines(Vec::new());
+            let recorded = record_games(&config, &SimulationControl::new(), &mut sink).unwrap();
+            let simulated = create_simulator(&config.simulator_type).unwrap().simulate(&config, &SimulationControl::new()).unwrap();
+            assert_eq!(serde_json::to_value(&recorded).unwrap(), serde_json::to_value(&simulated).unwrap());
+
+            let rows: Vec<Value> = sink.writer.split(|&b| b == b'\n')
+                .filter(|line| !line.is_empty())
+                .map(|line| serde_json::from_slice(line).unwrap())
+                .collect();
+            assert_eq!(rows.len(), config.num_games);
+            assert_eq!(rows.iter().filter(|row| row["winner"].is_null()).count() as u64, recorded.draws);
+        }
+    }
+
+    #[test]
+    fn sinks_round_trip_their_fields() {
+        let record = GameRecord { game: 7, strategies: ["Highest Value", "Look, \"Ahead\""], winner: None, scores: [12, 12], num_rounds: 9 };
+
+        let mut csv = CsvSink::new(Vec::new());
+        csv.write(&record).unwrap();
+        csv.finish().unwrap();
+        assert_eq!(String::from_utf8(csv.writer).unwrap(), "game,strategy1,strategy2,winner,score1,score2,num_rounds\n7,Highest Value,\"Look, \"\"Ahead\"\"\",,12,12,9\n");
+
+        let mut json = JsonSink::array(Vec::new());
+        json.write(&record).unwrap();
+        json.write(&GameRecord { winner: Some(1), ..record }).unwrap();
+        json.finish().unwrap();
+        let rows: Vec<Value> = serde_json::from_slice(&json.writer).unwrap();
+        assert_eq!(rows[0]["strategy2"], "Look, \"Ahead\"");
+        assert!(rows[0]["winner"].is_null());
+        assert_eq!(rows[1]["winner"], "Look, \"Ahead\"");
+        assert_eq!(rows[1]["score1"], 12);
+
+        let mut empty = JsonSink::array(Vec::new());
+        empty.finish().unwrap();
+        assert_eq!(serde_json::from_slice::<Vec<Value>>(&empty.writer).unwrap().len(), 0);
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_files_read_back() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+        use parquet::record::{Field as ParquetField, RowAccessor};
+
+        struct Sample(i64, Field, Field);
+
+        impl Record for Sample {
+            fn columns(&self) -> Vec<Column> {
+                vec![Column::int("index"), Column::float("value"), Column::text("label")]
+            }
+
+            fn fields(&self) -> Vec<Field> {
+                vec![Field::Int(self.0), self.1.clone(), self.2.clone()]
+            }
+        }
+
+        // Enough rows for two row groups; every third has no label and every fifth an integer value.
+        let count = 70_000;
+        let sample = |i: i64| Sample(
+            i,
+            if i % 5 == 0 { Field::Int(i) } else { Field::Float(i as f64 / 4.0) },
+            if i % 3 == 0 { Field::Null } else { Field::Text(format!("row \"{}\"", i)) },
+        );
+        let path = std::env::temp_dir().join(format!("dice_game_sink_{}.parquet", std::process::id()));
+        let mut sink = create_sink(&path, OutputFormat::Parquet).unwrap();
+        for i in 0..count {
+            sink.write(&sample(i)).unwrap();
+        }
+        assert!(sink.write(&GameRecord { game: 0, strategies: ["A", "B"], winner: None, scores: [0, 0], num_rounds: 0 }).is_err());
+        sink.finish().unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(reader.metadata().num_row_groups(), 2);
+        let mut read = 0;
+        for (i, row) in reader.get_row_iter(None).unwrap().enumerate() {
+            let (row, i) = (row.unwrap(), i as i64);
+            assert_eq!(row.get_long(0).unwrap(), i);
+            let expected = if i % 5 == 0 { i as f64 } else { i as f64 / 4.0 };
+            assert_eq!(row.get_double(1).unwrap(), expected);
+            match row.get_column_iter().nth(2).unwrap().1 {
+                ParquetField::Null => assert_eq!(i % 3, 0),
+                ParquetField::Str(label) => assert_eq!(label, &format!("row \"{}\"", i)),
+                other => panic!("unexpected label {:?}", other),
+            }
+            read += 1;
+        }
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read, count);
+    }
+}
@@ -0,0 +1,397 @@
+// File: src/solver.rs
+
+use crate::GameMode;
+use crate::game::{legal_moves, GameRules, MAX_BOARD_SIZE};
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+pub const MIN_ROLL: u8 = 2;
+pub const MAX_ROLL: u8 = 12;
+const NUM_ROLLS: usize = (MAX_ROLL - MIN_ROLL + 1) as usize;
+const TOLERANCE: f64 = 1e-10;
+const MAX_SWEEPS: usize = 100_000;
+// Values within this of each other count as equal, allowing for value iteration's error.
+const TIE_TOLERANCE: f64 = 1e-8;
+
+// Both dice models produce the two-dice sum.
+pub fn roll_probability(roll: u8) -> f64 {
+    (6 - (roll as i32 - 7).abs()).max(0) as f64 / 36.0
+}
+
+// A decision point before the roll. `stage` is the engine's round counter in base mode and the
+// player to move in extended mode, so a base-mode position is exactly what a strategy sees.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Position {
+    pub board: u16,
+    pub stage: u8,
+}
+
+// What a move leads to, with rewards from the mover's point of view. In base mode the reward is
+// the margin: minus the points the mover scores, so the value of a position is the expected
+// final score difference in the mover's favour from there on. In extended mode it is 1 for a
+// win and -1 for a loss.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    // The mover rolls again from this position.
+    Continue(Position),
+    // The opponent moves next from this position.
+    Pass(f64, Position),
+    End(f64),
+}
+
+// The game's transitions under the `legal_moves` rule, mirroring `Game::play_loop`.
+#[derive(Clone, Debug)]
+pub struct Model {
+    pub mode: GameMode,
+    pub full_board: u16,
+    pub rounds: u8,
+    moves: Vec<Vec<u16>>,
+}
+
+impl Model {
+    pub fn new(mode: GameMode, rules: GameRules) -> Self {
+        let full_board = (1u16 << rules.board_size.clamp(1, MAX_BOARD_SIZE)) - 1;
+        let moves = (0..=full_board)
+            .flat_map(|open| (MIN_ROLL..=MAX_ROLL).map(move |roll| legal_moves(open, roll)))
+            .collect();
+        Model { mode, full_board, rounds: rules.rounds, moves }
+    }
+
+    pub fn start(&self) -> Position {
+        Position { board: self.full_board, stage: 0 }
+    }
+
+    // Stages a decision can be made at: one per turn of a base game, in which each seat plays
+    // `rounds` turns.
+    pub fn stages(&self) -> usize {
+        match self.mode {
+            GameMode::Base => 2 * self.rounds as usize,
+            GameMode::Extended => 2,
+        }
+    }
+
+    pub fn moves(&self, position: Position, roll: u8) -> &[u16] {
+        let open = match self.mode {
+            GameMode::Extended if position.stage == 1 => !position.board & self.full_board,
+            _ => position.board,
+        };
+        &self.moves[open as usize * NUM_ROLLS + (roll - MIN_ROLL) as usize]
+    }
+
+    pub fn apply(&self, position: Position, move_bits: u16) -> Outcome {
+        let Position { board, stage } = position;
+        match self.mode {
+            GameMode::Base => {
+                if move_bits != 0 {
+                    return Outcome::Continue(Position { board: board & !move_bits, stage });
+                }
+                let margin = -(board.count_ones() as f64);
+                if stage as usize + 1 >= self.stages() {
+                    Outcome::End(margin)
+                } else {
+                    Outcome::Pass(margin, Position { board: self.full_board, stage: stage + 1 })
+                }
+            },
+            GameMode::Extended => {
+                if move_bits == 0 {
+                    return Outcome::Pass(0.0, Position { board, stage: 1 - stage });
+                }
+                let board = if stage == 0 { board & !move_bits } else { board | move_bits };
+                match board {
+                    0 => Outcome::End(if stage == 0 { 1.0 } else { -1.0 }),
+                    b if b == self.full_board => Outcome::End(if stage == 1 { 1.0 } else { -1.0 }),
+                    _ => Outcome::Continue(Position { board, stage }),
+                }
+            },
+        }
+    }
+}
+
+struct Edge {
+    reward: f64,
+    sign: f64,
+    next: usize,
+    weight: f64,
+}
+
+// Fixed moves for one side of the game, for evaluating a policy against optimal play.
+pub type Policy<'a> = &'a dyn Fn(Position, u8) -> u16;
+
+// Move probabilities for one side of the game, which must sum to 1 over the legal moves.
+pub type MixedPolicy<'a> = &'a dyn Fn(Position, u8) -> Vec<(u16, f64)>;
+
+// Position values with both players playing optimally, or with either of them following a fixed
+// policy. Players are numbered by who moves first: in extended mode the player to move is the
+// stage, and in base mode the value table is split by player when a policy is in play. Base mode
+// is solved backwards over the round counter, which every turn advances, and upwards over the
+// boards of a turn, since a move only closes tiles; extended mode can revisit positions and is
+// solved by value iteration.
+pub struct Solution {
+    pub model: Model,
+    values: Vec<f64>,
+    players: usize,
+}
+
+impl Solution {
+    pub fn solve(mode: GameMode, rules: GameRules) -> Self {
+        Solution::evaluate_profile(Model::new(mode, rules), [None, None])
+    }
+
+    // Values for the side that moves first at `model.start()` following `policy` against an
+    // opponent that best-responds to it.
+    pub fn evaluate(model: Model, policy: Policy<'_>) -> Self {
+        let mixed = |position, roll| vec![(policy(position, roll), 1.0)];
+        Solution::evaluate_profile(model, [Some(&mixed), None])
+    }
+
+    // Values with player `i` following `policies[i]`, or best-responding where it is None.
+    pub fn evaluate_profile(model: Model, policies: [Option<MixedPolicy<'_>>; 2]) -> Self {
+        let players = if model.mode == GameMode::Base && policies.iter().any(Option::is_some) { 2 } else { 1 };
+        let size = model.stages() * (model.full_board as usize + 1) * players;
+        let mut solution = Solution { model, values: vec![0.0; size], players };
+        solution.update(policies);
+        solution
+    }
+
+    // As `evaluate_profile`, reusing this solution's model. Extended-mode sweeps start from the
+    // current values rather than from zeros, which converges much faster after a small change
+    // to the policies but can settle on different values for games that may never end.
+    pub fn reevaluate(self, policies: [Option<MixedPolicy<'_>>; 2]) -> Self {
+        let players = if self.model.mode == GameMode::Base && policies.iter().any(Option::is_some) { 2 } else { 1 };
+        if players != self.players {
+            return Solution::evaluate_profile(self.model, policies);
+        }
+        let mut solution = self;
+        solution.update(policies);
+        solution
+    }
+
+    fn update(&mut self, policies: [Option<MixedPolicy<'_>>; 2]) {
+        match self.model.mode {
+            GameMode::Base => {
+                for stage in (0..self.model.stages()).rev() {
+                    for board in 0..=self.model.full_board {
+                        for (player, &policy) in policies.iter().enumerate().take(self.players) {
+                            let position = Position { board, stage: stage as u8 };
+                            let value = self.backup(position, player, policy);
+                            let index = self.index(position, player);
+                            self.values[index] = value;
+                        }
+                    }
+                }
+            },
+            GameMode::Extended => self.iterate(policies),
+        }
+    }
+
+    // Synchronous sweeps, so from all zeros after n sweeps every value is that of the game cut
+    // off after n moves with unfinished games drawn, and the limit counts endless games as draws.
+    // The moves of every position are resolved to value-table edges once up front, each range
+    // of edges being either a policy's moves with their probabilities or all moves to maximise
+    // over.
+    fn iterate(&mut self, policies: [Option<MixedPolicy<'_>>; 2]) {
+        let mut edges = Vec::new();
+        let mut starts = Vec::with_capacity(self.values.len() * NUM_ROLLS + 1);
+        let mut fixed = Vec::with_capacity(self.values.len() * NUM_ROLLS);
+        for index in 0..self.values.len() {
+            let position = self.position(index);
+            let player = position.stage as usize;
+            for roll in MIN_ROLL..=MAX_ROLL {
+                starts.push(edges.len());
+                fixed.push(policies[player].is_some());
+                if position.board == 0 {
+                    continue;
+                }
+                let moves = match policies[player] {
+                    Some(policy) => policy(position, roll),
+                    None => self.model.moves(position, roll).iter().map(|&m| (m, 1.0)).collect(),
+                };
+                edges.extend(moves.into_iter().map(|(m, weight)| match self.model.apply(position, m) {
+                    Outcome::Continue(next) => Edge { reward: 0.0, sign: 1.0, next: self.index(next, player), weight },
+                    Outcome::Pass(reward, next) => Edge { reward, sign: -1.0, next: self.index(next, 1 - player), weight },
+                    Outcome::End(reward) => Edge { reward, sign: 0.0, next: 0, weight },
+                }));
+            }
+        }
+        starts.push(edges.len());
+
+        let mut next = self.values.clone();
+        for _ in 0..MAX_SWEEPS {
+            let mut delta: f64 = 0.0;
+            for (index, value) in next.iter_mut().enumerate() {
+                let mut sum = 0.0;
+                for (r, roll) in (MIN_ROLL..=MAX_ROLL).enumerate() {
+                    let range = starts[index * NUM_ROLLS + r]..starts[index * NUM_ROLLS + r + 1];
+                    if range.is_empty() {
+                        continue;
+                    }
+                    let values = edges[range].iter().map(|e| (e.weight, e.reward + e.sign * self.values[e.next]));
+                    let value = match fixed[index * NUM_ROLLS + r] {
+                        true => values.map(|(weight, value)| weight * value).sum(),
+                        false => values.map(|(_, value)| value).fold(f64::NEG_INFINITY, f64::max),
+                    };
+                    sum += roll_probability(roll) * value;
+                }
+                delta = delta.max((sum - self.values[index]).abs());
+                *value = sum;
+            }
+            std::mem::swap(&mut self.values, &mut next);
+            if delta < TOLERANCE {
+                break;
+            }
+        }
+    }
+
+    fn position(&self, index: usize) -> Position {
+        let boards = self.model.full_board as usize + 1;
+        let stages = self.model.stages();
+        Position { board: (index % boards) as u16, stage: (index / boards % stages) as u8 }
+    }
+
+    // Extended-mode positions name their player, and base-mode ones share values between the
+    // players unless a policy tells them apart.
+    fn index(&self, position: Position, player: usize) -> usize {
+        let boards = self.model.full_board as usize + 1;
+        let stages = self.model.stages();
+        let player = if self.players == 2 { player } else { 0 };
+        (player * stages + position.stage as usize) * boards + position.board as usize
+    }
+
+    fn player(&self, position: Position) -> usize {
+        match self.model.mode {
+            GameMode::Base => 0,
+            GameMode::Extended => position.stage as usize,
+        }
+    }
+
+    // Expected value over the rolls for `player` to move.
+    fn backup(&self, position: Position, player: usize, policy: Option<MixedPolicy<'_>>) -> f64 {
+        (MIN_ROLL..=MAX_ROLL).map(|roll| {
+            let value = match policy {
+                Some(policy) => policy(position, roll).into_iter()
+                    .map(|(m, weight)| weight * self.outcome_value(self.model.apply(position, m), player))
+                    .sum(),
+                None => self.model.moves(position, roll).iter()
+                    .map(|&m| self.outcome_value(self.model.apply(position, m), player))
+                    .fold(f64::NEG_INFINITY, f64::max),
+            };
+            roll_probability(roll) * value
+        }).sum()
+    }
+
+    fn outcome_value(&self, outcome: Outcome, player: usize) -> f64 {
+        match outcome {
+            Outcome::Continue(next) => self.values[self.index(next, player)],
+            Outcome::Pass(reward, next) => reward - self.values[self.index(next, 1 - player)],
+            Outcome::End(reward) => reward,
+        }
+    }
+
+    // Value for the side to move before the roll; in base mode with a policy, for the player
+    // who moved first.
+    pub fn value(&self, position: Position) -> f64 {
+        self.values[self.index(position, self.player(position))]
+    }
+
+    pub fn start_value(&self) -> f64 {
+        self.value(self.model.start())
+    }
+
+    // Value of making `move_bits` after `roll`, for the side to move as in `value`.
+    pub fn move_value(&self, position: Position, move_bits: u16) -> f64 {
+        self.outcome_value(self.model.apply(position, move_bits), self.player(position))
+    }
+
+    // Passes only when that is strictly better: in extended mode passing back and forth can be
+    // worth as much as the best move while never ending the game.
+    pub fn best_move(&self, position: Position, roll: u8) -> u16 {
+        let mut best = (0, f64::NEG_INFINITY);
+        for &m in &self.model.moves(position, roll)[1..] {
+            let value = self.move_value(position, m);
+            if value > best.1 {
+                best = (m, value);
+            }
+        }
+        if self.move_value(position, 0) > best.1 + TIE_TOLERANCE {
+            return 0;
+        }
+        best.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::rng::Xoroshiro128Plus;
+    use crate::{GameOutcome, GameState, OptimizationLevel, Strategy};
+    use std::sync::Arc;
+
+    const RULES: GameRules = GameRules { board_size: 4, rounds: 5, dice: None };
+
+    // Expectimax straight from the rules: a pass scores the open tiles, ends the turn and hands a
+    // full board to the opponent, and each seat plays `rounds` turns.
+    fn brute_force(full_board: u16, rounds: u8, board: u16, stage: u8) -> f64 {
+        (MIN_ROLL..=MAX_ROLL).map(|roll| {
+            let best = legal_moves(board, roll).into_iter().map(|m| {
+                if m != 0 {
+                    return brute_force(full_board, rounds, board & !m, stage);
+                }
+                let points = board.count_ones() as f64;
+                if stage + 1 >= 2 * rounds {
+                    -points
+                } else {
+                    -points - brute_force(full_board, rounds, full_board, stage + 1)
+                }
+            }).fold(f64::NEG_INFINITY, f64::max);
+            roll_probability(roll) * best
+        }).sum()
+    }
+
+    #[derive(Clone)]
+    struct Optimal(Arc<Solution>);
+
+    impl Strategy for Optimal {
+        fn choose_move(&mut self, state: &GameState, roll: u8) -> u16 {
+            self.0.best_move(Position { board: state.board, stage: state.round }, roll)
+        }
+
+        fn name(&self) -> &'static str {
+            "Optimal"
+        }
+
+        fn on_game_start(&mut self, _seat: usize, _rng: Xoroshiro128Plus) {}
+    }
+
+    #[test]
+    fn base_values_match_a_brute_force_search() {
+        // One turn each, which keeps the unmemoised search quick.
+        let rules = GameRules { rounds: 1, ..RULES };
+        let solution = Solution::solve(GameMode::Base, rules);
+        let full_board = solution.model.full_board;
+        for stage in 0..2 * rules.rounds {
+            for board in 0..=full_board {
+                let expected = brute_force(full_board, rules.rounds, board, stage);
+                let value = solution.value(Position { board, stage });
+                assert!((value - expected).abs() < 1e-9, "board {:b} stage {}: {} vs {}", board, stage, value, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn optimal_play_scores_the_start_value() {
+        let solution = Arc::new(Solution::solve(GameMode::Base, RULES));
+        let mut game = Game::from_rules(GameMode::Base, OptimizationLevel::Fast, RULES, 3);
+        let (mut a, mut b) = (Optimal(solution.clone()), Optimal(solution.clone()));
+        let games = 4000;
+        let margins: Vec<f64> = (0..games).map(|_| {
+            let GameOutcome { scores, .. } = game.play_pair([&mut a, &mut b]);
+            scores[1] as f64 - scores[0] as f64
+        }).collect();
+        let mean = margins.iter().sum::<f64>() / games as f64;
+        let variance = margins.iter().map(|m| (m - mean).powi(2)).sum::<f64>() / (games - 1) as f64;
+        let error = (variance / games as f64).sqrt();
+        assert!((mean - solution.start_value()).abs() < 4.0 * error, "{} vs {} ± {}", mean, solution.start_value(), error);
+    }
+}
@@ -0,0 +1,649 @@
+// File: src/store.rs
+
+use crate::control::StopReason;
+use crate::experiment::ExperimentConfig;
+use crate::simulation::SimulationReport;
+use crate::sink::{Column, Field, Record, RecordSink};
+use crate::strategies::strategy_names;
+use crate::tournament::{Standing, TournamentResult};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, params_from_iter, Connection, DropBehavior, OptionalExtension, Row, Transaction};
+use serde::{Serialize, Deserialize};
+use std::io;
+use std::path::Path;
+
+// Stored with every experiment, so results can be traced to the engine that produced them
+// without a git checkout.
+pub const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Bumped whenever the tables change; older engines refuse a newer database.
+const SCHEMA_VERSION: i64 = 1;
+
+// Pending game rows are committed in chunks of this many.
+const GAMES_PER_COMMIT: usize = 65_536;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS experiments (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    engine_version TEXT NOT NULL,
+    seed INTEGER NOT NULL,
+    mode TEXT NOT NULL,
+    optimization TEXT NOT NULL,
+    board_size INTEGER NOT NULL,
+    rounds INTEGER NOT NULL,
+    dice TEXT,
+    strategies TEXT NOT NULL,
+    num_games INTEGER NOT NULL,
+    config TEXT NOT NULL,
+    started_at TEXT NOT NULL,
+    finished_at TEXT,
+    stop_reason TEXT
+);
+CREATE TABLE IF NOT EXISTS pairings (
+    experiment_id INTEGER NOT NULL REFERENCES experiments(id) ON DELETE CASCADE,
+    pairing INTEGER NOT NULL,
+    round INTEGER NOT NULL,
+    runner TEXT NOT NULL,
+    strategy1 TEXT NOT NULL,
+    strategy2 TEXT NOT NULL,
+    games INTEGER NOT NULL,
+    draws INTEGER NOT NULL,
+    wins1 INTEGER NOT NULL,
+    wins2 INTEGER NOT NULL,
+    mean_score1 REAL,
+    mean_score2 REAL,
+    mean_rounds REAL,
+    elapsed_secs REAL,
+    PRIMARY KEY (experiment_id, pairing)
+);
+CREATE TABLE IF NOT EXISTS standings (
+    experiment_id INTEGER NOT NULL REFERENCES experiments(id) ON DELETE CASCADE,
+    rank INTEGER NOT NULL,
+    strategy TEXT NOT NULL,
+    points REAL NOT NULL,
+    match_wins INTEGER NOT NULL,
+    match_losses INTEGER NOT NULL,
+    match_draws INTEGER NOT NULL,
+    game_wins INTEGER NOT NULL,
+    game_losses INTEGER NOT NULL,
+    game_draws INTEGER NOT NULL,
+    PRIMARY KEY (experiment_id, rank)
+);
+CREATE TABLE IF NOT EXISTS games (
+    experiment_id INTEGER NOT NULL REFERENCES experiments(id) ON DELETE CASCADE,
+    game INTEGER NOT NULL,
+    strategy1 TEXT NOT NULL,
+    strategy2 TEXT NOT NULL,
+    winner TEXT,
+    score1 INTEGER NOT NULL,
+    score2 INTEGER NOT NULL,
+    num_rounds INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS games_by_pairing ON games (experiment_id, strategy1, strategy2);
+";
+
+// A SQLite file of past experiments: one row per experiment with its config and rules, its
+// per-pairing aggregates (tournament matches count as pairings), tournament standings and,
+// optionally, every game.
+pub struct ResultStore {
+    conn: Connection,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StoredExperiment {
+    pub id: i64,
+    pub name: String,
+    // "simulation" or "tournament".
+    pub kind: String,
+    pub engine_version: String,
+    pub seed: u64,
+    pub mode: String,
+    pub optimization: String,
+    pub board_size: u8,
+    pub rounds: u8,
+    pub dice: Option<String>,
+    pub strategies: Vec<String>,
+    pub num_games: usize,
+    pub started_at: String,
+    // None for a run that never finished.
+    pub finished_at: Option<String>,
+    pub stop_reason: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StoredPairing {
+    pub pairing: usize,
+    pub round: usize,
+    // The simulator for a simulation, the format for a tournament.
+    pub runner: String,
+    pub strategy1: String,
+    pub strategy2: String,
+    pub games: u64,
+    pub draws: u64,
+    pub wins1: u64,
+    pub wins2: u64,
+    pub mean_score1: Option<f64>,
+    pub mean_score2: Option<f64>,
+    pub mean_rounds: Option<f64>,
+    pub elapsed_secs: Option<f64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExperimentDetails {
+    pub experiment: StoredExperiment,
+    pub config: serde_json::Value,
+    pub pairings: Vec<StoredPairing>,
+    pub standings: Vec<Standing>,
+    pub games_stored: u64,
+}
+
+// One strategy's totals over every pairing of one experiment.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Comparison {
+    pub strategy: String,
+    pub experiment: i64,
+    pub name: String,
+    pub games: u64,
+    pub wins: u64,
+    pub draws: u64,
+    pub losses: u64,
+    pub win_rate: f64,
+}
+
+fn db_error(e: rusqlite::Error) -> io::Error {
+    io::Error::other(e)
+}
+
+impl ResultStore {
+    // Opens the database, creating it and its tables as needed.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let conn = Connection::open(path).map_err(db_error)?;
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).map_err(db_error)?;
+        if version > SCHEMA_VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("results database has schema version {}, this engine reads up to {}", version, SCHEMA_VERSION)));
+        }
+        conn.execute_batch(SCHEMA).map_err(db_error)?;
+        conn.execute_batch(&format!("PRAGMA user_version = {}; PRAGMA foreign_keys = ON;", SCHEMA_VERSION)).map_err(db_error)?;
+        Ok(ResultStore { conn })
+    }
+
+    // Adds the experiment's row, stamped with the start time, and returns its id.
+    pub fn begin(&self, config: &ExperimentConfig) -> io::Result<i64> {
+        let strategies = if config.strategies.is_empty() { strategy_names() } else { config.strategies.clone() };
+        let kind = if config.tournament.is_some() { "tournament" } else { "simulation" };
+        self.conn.execute(
+            "INSERT INTO experiments (name, kind, engine_version, seed, mode, optimization, board_size, rounds, dice, strategies, num_games, config, started_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))",
+            params![
+                config.name,
+                kind,
+                ENGINE_VERSION,
+                config.seed as i64,
+                format!("{:?}", config.rules.mode),
+                format!("{:?}", config.rules.optimization),
+                config.rules.board_size,
+                config.rules.rounds,
+                config.rules.dice.map(|dice| format!("{:?}", dice)),
+                serde_json::to_string(&strategies)?,
+                config.num_games as i64,
+                serde_json::to_string(config)?,
+            ],
+        ).map_err(db_error)?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn add_report(&self, experiment: i64, pairing: usize, report: &SimulationReport) -> io::Result<()> {
+        let seat = |i: usize| report.seats.get(i);
+        self.conn.execute(
+            "INSERT INTO pairings VALUES (?1, ?2, 0, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                experiment,
+                pairing as i64,
+                report.simulator,
+                report.config.strategies[0],
+                report.config.strategies[1],
+                report.games as i64,
+                report.draws as i64,
+                seat(0).map_or(0, |s| s.wins) as i64,
+                seat(1).map_or(0, |s| s.wins) as i64,
+                seat(0).map(|s| s.mean_score),
+                seat(1).map(|s| s.mean_score),
+                report.mean_rounds,
+                report.elapsed_secs,
+            ],
+        ).map_err(db_error)?;
+        Ok(())
+    }
+
+    // Stores every match as a pairing, plus the final standings.
+    pub fn add_tournament(&self, experiment: i64, result: &TournamentResult) -> io::Result<()> {
+        let tx = self.conn.unchecked_transaction().map_err(db_error)?;
+        for (pairing, m) in result.matches.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO pairings VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, NULL, NULL, NULL, NULL)",
+                params![experiment, pairing as i64, m.round as i64, result.format, m.strategy1, m.strategy2, m.games() as i64, m.draws as i64, m.wins1 as i64, m.wins2 as i64],
+            ).map_err(db_error)?;
+        }
+        for (rank, s) in result.standings.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO standings VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    experiment,
+                    rank as i64 + 1,
+                    s.name,
+                    s.points,
+                    s.match_wins as i64,
+                    s.match_losses as i64,
+                    s.match_draws as i64,
+                    s.game_wins as i64,
+                    s.game_losses as i64,
+                    s.game_draws as i64,
+                ],
+            ).map_err(db_error)?;
+        }
+        tx.commit().map_err(db_error)
+    }
+
+    pub fn finish(&self, experiment: i64, stop_reason: Option<StopReason>) -> io::Result<()> {
+        self.conn.execute(
+            "UPDATE experiments SET finished_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), stop_reason = ?2 WHERE id = ?1",
+            params![experiment, stop_reason.map(|reason| format!("{:?}", reason))],
+        ).map_err(db_error)?;
+        Ok(())
+    }
+
+    // A sink for `sink::record_games` that adds every game to the experiment.
+    pub fn game_sink(&self, experiment: i64) -> GameRows<'_> {
+        GameRows { store: self, experiment, insert: String::new(), tx: None, pending: 0 }
+    }
+
+    // Newest first, optionally only names containing `name`.
+    pub fn experiments(&self, name: Option<&str>, limit: Option<usize>) -> io::Result<Vec<StoredExperiment>> {
+        let mut statement = self.conn.prepare(
+            "SELECT * FROM experiments WHERE name LIKE '%' || ?1 || '%' ORDER BY id DESC LIMIT ?2",
+        ).map_err(db_error)?;
+        let limit = limit.map_or(-1, |limit| limit as i64);
+        let rows = statement.query_map(params![name.unwrap_or(""), limit], experiment_row).map_err(db_error)?;
+        rows.collect::<Result<_, _>>().map_err(db_error)
+    }
+
+    pub fn experiment(&self, id: i64) -> io::Result<Option<ExperimentDetails>> {
+        let found = self.conn.query_row("SELECT * FROM experiments WHERE id = ?1", [id], |row| {
+            Ok((experiment_row(row)?, row.get::<_, String>("config")?))
+        }).optional().map_err(db_error)?;
+        let (experiment, config) = match found {
+            Some(found) => found,
+            None => return Ok(None),
+        };
+
+        let mut statement = self.conn.prepare("SELECT * FROM pairings WHERE experiment_id = ?1 ORDER BY pairing").map_err(db_error)?;
+        let pairings = statement.query_map([id], |row| Ok(StoredPairing {
+            pairing: row.get::<_, i64>("pairing")? as usize,
+            round: row.get::<_, i64>("round")? as usize,
+            runner: row.get("runner")?,
+            strategy1: row.get("strategy1")?,
+            strategy2: row.get("strategy2")?,
+            games: row.get::<_, i64>("games")? as u64,
+            draws: row.get::<_, i64>("draws")? as u64,
+            wins1: row.get::<_, i64>("wins1")? as u64,
+            wins2: row.get::<_, i64>("wins2")? as u64,
+            mean_score1: row.get("mean_score1")?,
+            mean_score2: row.get("mean_score2")?,
+            mean_rounds: row.get("mean_rounds")?,
+            elapsed_secs: row.get("elapsed_secs")?,
+        })).map_err(db_error)?.collect::<Result<Vec<_>, _>>().map_err(db_error)?;
+
+        let mut statement = self.conn.prepare("SELECT * FROM standings WHERE experiment_id = ?1 ORDER BY rank").map_err(db_error)?;
+        let standings = statement.query_map([id], |row| Ok(Standing {
+            name: row.get("strategy")?,
+            points: row.get("points")?,
+            match_wins: row.get::<_, i64>("match_wins")? as usize,
+            match_losses: row.get::<_, i64>("match_losses")? as usize,
+            match_draws: row.get::<_, i64>("match_draws")? as usize,
+            game_wins: row.get::<_, i64>("game_wins")? as usize,
+            game_losses: row.get::<_, i64>("game_losses")? as usize,
+            game_draws: row.get::<_, i64>("game_draws")? as usize,
+        })).map_err(db_error)?.collect::<Result<Vec<_>, _>>().map_err(db_error)?;
+
+        let games_stored: i64 = self.conn.query_row("SELECT COUNT(*) FROM games WHERE experiment_id = ?1", [id], |row| row.get(0)).map_err(db_error)?;
+        Ok(Some(ExperimentDetails {
+            experiment,
+            config: serde_json::from_str(&config)?,
+            pairings,
+            standings,
+            games_stored: games_stored as u64,
+        }))
+    }
+
+    // Each strategy's totals in each of the given experiments, grouped by strategy.
+    pub fn compare(&self, ids: &[i64]) -> io::Result<Vec<Comparison>> {
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        let mut statement = self.conn.prepare(&format!(
+            "SELECT strategy, e.id, e.name, SUM(games), SUM(wins), SUM(draws), SUM(losses) FROM (
+                 SELECT experiment_id, strategy1 AS strategy, games, wins1 AS wins, draws, wins2 AS losses FROM pairings
+                 UNION ALL
+                 SELECT experiment_id, strategy2, games, wins2, draws, wins1 FROM pairings
+             ) JOIN experiments e ON e.id = experiment_id
+             WHERE experiment_id IN ({})
+             GROUP BY strategy, e.id
+             ORDER BY strategy, e.id",
+            placeholders,
+        )).map_err(db_error)?;
+        let rows = statement.query_map(params_from_iter(ids), |row| {
+            let games = row.get::<_, i64>(3)? as u64;
+            let wins = row.get::<_, i64>(4)? as u64;
+            Ok(Comparison {
+                strategy: row.get(0)?,
+                experiment: row.get(1)?,
+                name: row.get(2)?,
+                games,
+                wins,
+                draws: row.get::<_, i64>(5)? as u64,
+                losses: row.get::<_, i64>(6)? as u64,
+                win_rate: if games > 0 { wins as f64 / games as f64 } else { 0.0 },
+            })
+        }).map_err(db_error)?;
+        rows.collect::<Result<_, _>>().map_err(db_error)
+    }
+}
+
+fn experiment_row(row: &Row) -> rusqlite::Result<StoredExperiment> {
+    let strategies: String = row.get("strategies")?;
+    Ok(StoredExperiment {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        kind: row.get("kind")?,
+        engine_version: row.get("engine_version")?,
+        seed: row.get::<_, i64>("seed")? as u64,
+        mode: row.get("mode")?,
+        optimization: row.get("optimization")?,
+        board_size: row.get("board_size")?,
+        rounds: row.get("rounds")?,
+        dice: row.get("dice")?,
+        strategies: serde_json::from_str(&strategies).unwrap_or_default(),
+        num_games: row.get::<_, i64>("num_games")? as usize,
+        started_at: row.get("started_at")?,
+        finished_at: row.get("finished_at")?,
+        stop_reason: row.get("stop_reason")?,
+    })
+}
+
+// Game rows for one experiment. Rows go in through a transaction committed every
+// `GAMES_PER_COMMIT` rows and by `finish`. A sink dropped without `finish`, as when a run fails
+// part way, still commits the games it has, so the connection is never left inside a transaction.
+pub struct GameRows<'a> {
+    store: &'a ResultStore,
+    experiment: i64,
+    insert: String,
+    tx: Option<Transaction<'a>>,
+    pending: usize,
+}
+
+impl RecordSink for GameRows<'_> {
+    fn write(&mut self, record: &dyn Record) -> io::Result<()> {
+        if self.insert.is_empty() {
+            let columns: Vec<String> = record.columns().into_iter().map(|c| c.name).collect();
+            let placeholders: Vec<String> = (1..=columns.len() + 1).map(|i| format!("?{}", i)).collect();
+            self.insert = format!("INSERT INTO games (experiment_id, {}) VALUES ({})", columns.join(", "), placeholders.join(", "));
+        }
+        if self.tx.is_none() {
+            let mut tx = self.store.conn.unchecked_transaction().map_err(db_error)?;
+            tx.set_drop_behavior(DropBehavior::Commit);
+            self.tx = Some(tx);
+        }
+        let tx = self.tx.as_ref().unwrap();
+        let values = std::iter::once(SqlValue::Integer(self.experiment)).chain(record.fields().into_iter().map(|field| match field {
+            Field::Int(value) => SqlValue::Integer(value),
+            Field::Float(value) => SqlValue::Real(value),
+            Field::Text(value) => SqlValue::Text(value),
+            Field::Null => SqlValue::Null,
+        }));
+        tx.prepare_cached(&self.insert).map_err(db_error)?
+            .execute(params_from_iter(values)).map_err(db_error)?;
+        self.pending += 1;
+        if self.pending == GAMES_PER_COMMIT {
+            self.finish()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.pending = 0;
+        match self.tx.take() {
+            Some(tx) => tx.commit().map_err(db_error),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Record for StoredExperiment {
+    fn columns(&self) -> Vec<Column> {
+        vec![
+            Column::int("id"),
+            Column::text("name"),
+            Column::text("kind"),
+            Column::text("engine_version"),
+            Column::int("seed"),
+            Column::text("mode"),
+            Column::text("optimization"),
+            Column::int("board_size"),
+            Column::int("rounds"),
+            Column::text("dice"),
+            Column::text("strategies"),
+            Column::int("num_games"),
+            Column::text("started_at"),
+            Column::text("finished_at"),
+            Column::text("stop_reason"),
+        ]
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        let text = |value: &Option<String>| value.clone().map_or(Field::Null, Field::Text);
+        vec![
+            Field::Int(self.id),
+            Field::Text(self.name.clone()),
+            Field::Text(self.kind.clone()),
+            Field::Text(self.engine_version.clone()),
+            Field::Int(self.seed as i64),
+            Field::Text(self.mode.clone()),
+            Field::Text(self.optimization.clone()),
+            Field::Int(self.board_size as i64),
+            Field::Int(self.rounds as i64),
+            text(&self.dice),
+            Field::Text(self.strategies.join(";")),
+            Field::Int(self.num_games as i64),
+            Field::Text(self.started_at.clone()),
+            text(&self.finished_at),
+            text(&self.stop_reason),
+        ]
+    }
+}
+
+impl Record for StoredPairing {
+    fn columns(&self) -> Vec<Column> {
+        vec![
+            Column::int("pairing"),
+            Column::int("round"),
+            Column::text("runner"),
+            Column::text("strategy1"),
+            Column::text("strategy2"),
+            Column::int("games"),
+            Column::int("draws"),
+            Column::int("wins1"),
+            Column::int("wins2"),
+            Column::float("mean_score1"),
+            Column::float("mean_score2"),
+            Column::float("mean_rounds"),
+            Column::float("elapsed_secs"),
+        ]
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        let float = |value: Option<f64>| value.map_or(Field::Null, Field::Float);
+        vec![
+            Field::Int(self.pairing as i64),
+            Field::Int(self.round as i64),
+            Field::Text(self.runner.clone()),
+            Field::Text(self.strategy1.clone()),
+            Field::Text(self.strategy2.clone()),
+            Field::Int(self.games as i64),
+            Field::Int(self.draws as i64),
+            Field::Int(self.wins1 as i64),
+            Field::Int(self.wins2 as i64),
+            float(self.mean_score1),
+            float(self.mean_score2),
+            float(self.mean_rounds),
+            float(self.elapsed_secs),
+        ]
+    }
+}
+
+impl Record for Comparison {
+    fn columns(&self) -> Vec<Column> {
+        vec![
+            Column::text("strategy"),
+            Column::int("experiment"),
+            Column::text("name"),
+            Column::int("games"),
+            Column::int("wins"),
+            Column::int("draws"),
+            Column::int("losses"),
+            Column::float("win_rate"),
+        ]
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        vec![
+            Field::Text(self.strategy.clone()),
+            Field::Int(self.experiment),
+            Field::Text(self.name.clone()),
+            Field::Int(self.games as i64),
+            Field::Int(self.wins as i64),
+            Field::Int(self.draws as i64),
+            Field::Int(self.losses as i64),
+            Field::Float(self.win_rate),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::SimulationControl;
+    use crate::sink::record_games;
+    use std::time::Duration;
+
+    fn temp_database(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("dice_game_store_{}_{}.db", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn experiments_round_trip() {
+        let path = temp_database("round_trip");
+        let store = ResultStore::open(&path).unwrap();
+        let text = "name = \"store test\"\nstrategies = [\"Highest Value\", \"Highest Probability\", \"Balanced Value\"]\nnum_games = 30\nseed = 9\n[rules]\noptimization = \"fast\"\n";
+        let config = ExperimentConfig::from_toml(text).unwrap();
+        let id = store.begin(&config).unwrap();
+
+        let mut games = store.game_sink(id);
+        let mut reports = Vec::new();
+        for (pairing, simulation) in config.pairings().unwrap().into_iter().enumerate() {
+            let stats = record_games(&simulation, &SimulationControl::new(), &mut games).unwrap();
+            let report = SimulationReport::new("Recorder", &simulation, &stats, Duration::ZERO);
+            store.add_report(id, pairing, &report).unwrap();
+            reports.push(report);
+        }
+        games.finish().unwrap();
+        drop(games);
+        store.finish(id, None).unwrap();
+        drop(store);
+
+        let store = ResultStore::open(&path).unwrap();
+        let details = store.experiment(id).unwrap().unwrap();
+        assert_eq!(details.experiment.name, "store test");
+        assert_eq!(details.experiment.kind, "simulation");
+        assert_eq!(details.experiment.seed, 9);
+        assert_eq!(details.experiment.strategies, config.strategies);
+        assert!(details.experiment.finished_at.is_some());
+        assert_eq!(details.config, serde_json::to_value(&config).unwrap());
+        assert_eq!(details.games_stored, 3 * 30);
+        for (stored, report) in details.pairings.iter().zip(&reports) {
+            assert_eq!(stored.strategy1, report.seats[0].strategy);
+            assert_eq!((stored.games, stored.draws), (report.games, report.draws));
+            assert_eq!((stored.wins1, stored.wins2), (report.seats[0].wins, report.seats[1].wins));
+            assert_eq!(stored.mean_score1, Some(report.seats[0].mean_score));
+        }
+
+        // Each strategy plays two of the three pairings, and its totals add up over both seats.
+        let comparison = store.compare(&[id]).unwrap();
+        assert_eq!(comparison.len(), 3);
+        for row in &comparison {
+            assert_eq!(row.games, 2 * 30);
+            assert_eq!(row.wins + row.draws + row.losses, row.games);
+        }
+        assert_eq!(store.experiments(Some("store"), None).unwrap()[0].id, id);
+        assert!(store.experiments(Some("missing"), None).unwrap().is_empty());
+        assert!(store.experiment(id + 1).unwrap().is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn tournaments_round_trip() {
+        let path = temp_database("tournament");
+        let store = ResultStore::open(&path).unwrap();
+        let text = "strategies = [\"Highest Value\", \"Balanced Value\", \"Random\"]\nnum_games = 20\n[rules]\noptimization = \"fast\"\n[tournament]\nformat = \"round-robin\"\n";
+        let config = ExperimentConfig::from_toml(text).unwrap();
+        let result = config.tournament.as_ref().unwrap().run(&config, &config.validate().unwrap()).unwrap();
+        let id = store.begin(&config).unwrap();
+        store.add_tournament(id, &result).unwrap();
+
+        let details = store.experiment(id).unwrap().unwrap();
+        assert_eq!(details.experiment.kind, "tournament");
+        assert!(details.experiment.finished_at.is_none());
+        assert_eq!(serde_json::to_value(&details.standings).unwrap(), serde_json::to_value(&result.standings).unwrap());
+        let matches: Vec<_> = details.pairings.iter().map(|p| (p.strategy1.clone(), p.strategy2.clone(), p.wins1, p.wins2, p.draws)).collect();
+        let expected: Vec<_> = result.matches.iter().map(|m| (m.strategy1.clone(), m.strategy2.clone(), m.wins1 as u64, m.wins2 as u64, m.draws as u64)).collect();
+        assert_eq!(matches, expected);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn dropping_the_game_sink_early_commits_its_games() {
+        let path = temp_database("dropped_sink");
+        let store = ResultStore::open(&path).unwrap();
+        let config = ExperimentConfig::from_json(r#"{"strategies": ["Highest Value", "Random"], "num_games": 25}"#).unwrap();
+        let id = store.begin(&config).unwrap();
+        let simulation = config.pairings().unwrap().remove(0);
+        {
+            let mut games = store.game_sink(id);
+            record_games(&simulation, &SimulationControl::new(), &mut games).unwrap();
+        }
+
+        // The connection is out of the sink's transaction: another store, and this one, see the
+        // games and can write again.
+        let other = ResultStore::open(&path).unwrap();
+        assert_eq!(other.experiment(id).unwrap().unwrap().games_stored, 25);
+        other.finish(id, Some(StopReason::Cancelled)).unwrap();
+        drop(other);
+        assert!(store.conn.is_autocommit());
+        let id2 = store.begin(&config).unwrap();
+        let mut games = store.game_sink(id2);
+        record_games(&simulation, &SimulationControl::new(), &mut games).unwrap();
+        games.finish().unwrap();
+        assert_eq!(store.experiment(id2).unwrap().unwrap().games_stored, 25);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn newer_schemas_are_refused() {
+        let path = temp_database("schema");
+        Connection::open(&path).unwrap().execute_batch(&format!("PRAGMA user_version = {}", SCHEMA_VERSION + 1)).unwrap();
+        let error = ResultStore::open(&path).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,9 +1,16 @@
+// File: src/strategies.rs
 
+use crate::{intern, GameState, Strategy};
+use crate::game::legal_moves;
+use crate::rng::{game_seed, Xoroshiro128Plus};
 use rand::Rng;
-use std::collections::HashSet;
-use crate::common::strategy_interface::{Strategy, GameState, Move};
+use std::collections::{BTreeMap, HashMap};
 
-const MAX_LOOK_AHEAD: usize = 3;
+const MAX_LOOK_AHEAD: i32 = 3;
+
+// How far ahead on points (the lower score wins) Score Management plays safe, and how far
+// behind it takes risks.
+const LEAD_MARGIN: i32 = 20;
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 pub enum StrategyComponent {
@@ -19,238 +26,277 @@ pub enum StrategyComponent {
     PatternRecognition,
 }
 
-// Individual strategy implementations
-pub struct RandomStrategy;
+// Every strategy picks one of the legal moves (see `game::legal_moves`) of the tiles it may
+// move, and passes only when there is none.
+
+// Chooses moves randomly, from the generator the engine hands it at the start of each game.
+#[derive(Clone)]
+pub struct RandomStrategy {
+    rng: Xoroshiro128Plus,
+}
+
+// Always takes the highest available tile.
+#[derive(Clone)]
 pub struct HighestValueStrategy;
+
+// Takes the tiles the dice roll most often first.
+#[derive(Clone)]
 pub struct HighestProbabilityStrategy;
+
+// Balances between value and probability.
+#[derive(Clone)]
 pub struct BalancedValueStrategy;
+
+// Highest Value while more than half the tiles are left, Highest Probability after that.
+#[derive(Clone)]
 pub struct AdaptiveStrategy;
-pub struct LookAheadStrategy;
-pub struct ScoreManagementStrategy;
+
+// Scores each legal move with a negamax search `depth` single-tile moves deep.
+#[derive(Clone)]
+pub struct LookAheadStrategy {
+    pub depth: i32,
+    values: HashMap<(u16, i32), i32>,
+}
+
+// Plays safe when ahead on points, takes risks when behind and plays balanced otherwise.
+#[derive(Clone, Default)]
+pub struct ScoreManagementStrategy {
+    scores: Scoreboard,
+}
+
+// Prefers the lowest tiles.
+#[derive(Clone)]
 pub struct RiskAverseStrategy;
+
+// Always goes for the highest value moves.
+#[derive(Clone)]
 pub struct AggressiveStrategy;
+
+// Repeats the moves the opponent has made most often this match.
+#[derive(Clone)]
 pub struct PatternRecognitionStrategy;
 
+// Points scored so far in the current game, kept from the turn-end hooks.
+#[derive(Clone, Copy, Default)]
+struct Scoreboard {
+    seat: usize,
+    scores: [u32; 2],
+}
+
+impl Scoreboard {
+    fn start(&mut self, seat: usize) {
+        *self = Scoreboard { seat: seat.min(1), scores: [0; 2] };
+    }
+
+    fn record(&mut self, player: usize, points: Option<u32>) {
+        if let Some(points) = points {
+            self.scores[player.min(1)] += points;
+        }
+    }
+
+    // Positive when ahead.
+    fn lead(&self) -> i32 {
+        self.scores[1 - self.seat] as i32 - self.scores[self.seat] as i32
+    }
+}
+
+impl Default for RandomStrategy {
+    fn default() -> Self {
+        RandomStrategy { rng: Xoroshiro128Plus::new(game_seed(0, 0)) }
+    }
+}
+
 impl Strategy for RandomStrategy {
-    fn choose_move(&self, game_state: &GameState) -> Move {
-        let mut rng = rand::thread_rng();
-        let available_bits: Vec<u8> = (1..=12)
-            .filter(|&i| game_state.board & (1 << (i - 1)) != 0)
-            .collect();
-        
-        if available_bits.is_empty() {
-            return Move { bits_to_flip: 0 };
+    fn choose_move(&mut self, game_state: &GameState, roll: u8) -> u16 {
+        let moves = possible_moves(game_state, roll);
+        if moves.is_empty() {
+            return 0;
         }
-        
-        let chosen_bit = available_bits[rng.gen_range(0..available_bits.len())];
-        Move { bits_to_flip: 1 << (chosen_bit - 1) }
+        moves[self.rng.gen_range(0..moves.len())]
     }
 
     fn name(&self) -> &'static str { "Random" }
-    fn description(&self) -> &'static str { "Chooses moves randomly" }
+
+    fn on_game_start(&mut self, _seat: usize, rng: Xoroshiro128Plus) {
+        self.rng = rng;
+    }
 }
 
 impl Strategy for HighestValueStrategy {
-    fn choose_move(&self, game_state: &GameState) -> Move {
-        for bit in (1..=12).rev() {
-            if game_state.board & (1 << (bit - 1)) != 0 {
-                return Move { bits_to_flip: 1 << (bit - 1) };
-            }
-        }
-        Move { bits_to_flip: 0 }
+    fn choose_move(&mut self, game_state: &GameState, roll: u8) -> u16 {
+        prefer(&possible_moves(game_state, roll), &[12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1])
     }
 
     fn name(&self) -> &'static str { "Highest Value" }
-    fn description(&self) -> &'static str { "Always chooses the highest available value" }
 }
 
 impl Strategy for HighestProbabilityStrategy {
-    fn choose_move(&self, game_state: &GameState) -> Move {
-        let probabilities = [7, 6, 8, 5, 9, 4, 10, 3, 11, 2, 12];
-        for &bit in &probabilities {
-            if game_state.board & (1 << (bit - 1)) != 0 {
-                return Move { bits_to_flip: 1 << (bit - 1) };
-            }
-        }
-        Move { bits_to_flip: 0 }
+    fn choose_move(&mut self, game_state: &GameState, roll: u8) -> u16 {
+        prefer(&possible_moves(game_state, roll), &[7, 6, 8, 5, 9, 4, 10, 3, 11, 2, 12, 1])
     }
 
     fn name(&self) -> &'static str { "Highest Probability" }
-    fn description(&self) -> &'static str { "Chooses based on dice roll probability" }
 }
 
 impl Strategy for BalancedValueStrategy {
-    fn choose_move(&self, game_state: &GameState) -> Move {
-        let balanced_order = [7, 8, 6, 9, 5, 10, 4, 11, 3, 12, 2];
-        for &bit in &balanced_order {
-            if game_state.board & (1 << (bit - 1)) != 0 {
-                return Move { bits_to_flip: 1 << (bit - 1) };
-            }
-        }
-        Move { bits_to_flip: 0 }
+    fn choose_move(&mut self, game_state: &GameState, roll: u8) -> u16 {
+        prefer(&possible_moves(game_state, roll), &[7, 8, 6, 9, 5, 10, 4, 11, 3, 12, 2, 1])
     }
 
     fn name(&self) -> &'static str { "Balanced Value" }
-    fn description(&self) -> &'static str { "Balances between value and probability" }
 }
 
 impl Strategy for AdaptiveStrategy {
-    fn choose_move(&self, game_state: &GameState) -> Move {
-        let bits_on = game_state.board.count_ones();
+    fn choose_move(&mut self, game_state: &GameState, roll: u8) -> u16 {
+        let bits_on = game_state.movable.count_ones();
         if bits_on > 6 {
-            HighestValueStrategy.choose_move(game_state)
+            HighestValueStrategy.choose_move(game_state, roll)
         } else {
-            HighestProbabilityStrategy.choose_move(game_state)
+            HighestProbabilityStrategy.choose_move(game_state, roll)
         }
     }
 
     fn name(&self) -> &'static str { "Adaptive" }
-    fn description(&self) -> &'static str { "Adapts strategy based on game state" }
 }
 
 impl Strategy for LookAheadStrategy {
-    fn choose_move(&self, game_state: &GameState) -> Move {
-        let mut best_move = Move { bits_to_flip: 0 };
+    fn choose_move(&mut self, game_state: &GameState, roll: u8) -> u16 {
+        let mut best_move = 0;
         let mut best_score = i32::MIN;
 
-        for bit in 1..=12 {
-            if game_state.board & (1 << (bit - 1)) != 0 {
-                let mut new_state = game_state.clone();
-                new_state.board &= !(1 << (bit - 1));
-                
-                let score = self.evaluate_position(&new_state, 3); // Look 3 moves ahead
-                if score > best_score {
-                    best_score = score;
-                    best_move = Move { bits_to_flip: 1 << (bit - 1) };
-                }
+        for m in possible_moves(game_state, roll) {
+            let score = self.evaluate_position(game_state.movable & !m, self.depth);
+            if score > best_score {
+                best_score = score;
+                best_move = m;
             }
         }
 
         best_move
     }
 
-    fn name(&self) -> &'static str { "Look Ahead" }
-    fn description(&self) -> &'static str { "Evaluates future positions" }
+    // Non-default depths are part of the name, so results keep them apart.
+    fn name(&self) -> &'static str {
+        if self.depth == MAX_LOOK_AHEAD {
+            "Look Ahead"
+        } else {
+            intern(format!("Look Ahead (depth {})", self.depth))
+        }
+    }
+}
+
+impl Default for LookAheadStrategy {
+    fn default() -> Self {
+        LookAheadStrategy { depth: MAX_LOOK_AHEAD, values: HashMap::new() }
+    }
 }
 
 impl LookAheadStrategy {
-    fn evaluate_position(&self, state: &GameState, depth: i32) -> i32 {
-        if depth == 0 {
-            return -(state.board.count_ones() as i32);
+    // Negamax over moving one tile at a time for `depth` plies, a position at the last ply
+    // scoring minus its tiles left. Values are cached by position.
+    fn evaluate_position(&mut self, movable: u16, depth: i32) -> i32 {
+        if depth <= 0 || movable == 0 {
+            return -(movable.count_ones() as i32);
+        }
+        if let Some(&value) = self.values.get(&(movable, depth)) {
+            return value;
         }
 
         let mut best_score = i32::MIN;
-        for bit in 1..=12 {
-            if state.board & (1 << (bit - 1)) != 0 {
-                let mut new_state = state.clone();
-                new_state.board &= !(1 << (bit - 1));
-                let score = -self.evaluate_position(&new_state, depth - 1);
-                best_score = best_score.max(score);
+        for bit in 0..12 {
+            if movable & (1 << bit) != 0 {
+                best_score = best_score.max(-self.evaluate_position(movable & !(1 << bit), depth - 1));
             }
         }
+        self.values.insert((movable, depth), best_score);
         best_score
     }
 }
 
 impl Strategy for ScoreManagementStrategy {
-    fn choose_move(&self, game_state: &GameState) -> Move {
-        let score_diff = game_state.total_score as i32 - game_state.opponent_score as i32;
-        if score_diff > 20 {
-            HighestProbabilityStrategy.choose_move(game_state) // Play safe if ahead
-        } else if score_diff < -20 {
-            HighestValueStrategy.choose_move(game_state) // Take risks if behind
+    fn choose_move(&mut self, game_state: &GameState, roll: u8) -> u16 {
+        let lead = self.scores.lead();
+        if lead > LEAD_MARGIN {
+            HighestProbabilityStrategy.choose_move(game_state, roll) // Play safe if ahead
+        } else if lead < -LEAD_MARGIN {
+            HighestValueStrategy.choose_move(game_state, roll) // Take risks if behind
         } else {
-            BalancedValueStrategy.choose_move(game_state) // Play balanced if close
+            BalancedValueStrategy.choose_move(game_state, roll) // Play balanced if close
         }
     }
 
     fn name(&self) -> &'static str { "Score Management" }
-    fn description(&self) -> &'static str { "Adjusts strategy based on score difference" }
+
+    fn on_game_start(&mut self, seat: usize, _rng: Xoroshiro128Plus) {
+        self.scores.start(seat);
+    }
+
+    fn on_turn_end(&mut self, player: usize, points: Option<u32>) {
+        self.scores.record(player, points);
+    }
 }
 
 impl Strategy for RiskAverseStrategy {
-    fn choose_move(&self, game_state: &GameState) -> Move {
-        let mut best_move = Move { bits_to_flip: 0 };
-        let mut min_risk = u32::MAX;
-
-        for bit in 1..=12 {
-            if game_state.board & (1 << (bit - 1)) != 0 {
-                let risk = bit as u32; // Higher numbers are considered riskier
-                if risk < min_risk {
-                    min_risk = risk;
-                    best_move = Move { bits_to_flip: 1 << (bit - 1) };
-                }
-            }
-        }
-
-        best_move
+    fn choose_move(&mut self, game_state: &GameState, roll: u8) -> u16 {
+        prefer(&possible_moves(game_state, roll), &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12])
     }
 
     fn name(&self) -> &'static str { "Risk Averse" }
-    fn description(&self) -> &'static str { "Prefers safer, lower-value moves" }
 }
 
 impl Strategy for AggressiveStrategy {
-    fn choose_move(&self, game_state: &GameState) -> Move {
-        let mut best_move = Move { bits_to_flip: 0 };
-        let mut max_value = 0;
-
-        for bit in 1..=12 {
-            if game_state.board & (1 << (bit - 1)) != 0 {
-                let value = bit;
-                if value > max_value {
-                    max_value = value;
-                    best_move = Move { bits_to_flip: 1 << (bit - 1) };
-                }
-            }
-        }
-
-        best_move
+    fn choose_move(&mut self, game_state: &GameState, roll: u8) -> u16 {
+        possible_moves(game_state, roll).into_iter().max().unwrap_or(0)
     }
 
     fn name(&self) -> &'static str { "Aggressive" }
-    fn description(&self) -> &'static str { "Always goes for the highest value moves" }
 }
 
 impl Strategy for PatternRecognitionStrategy {
-    fn choose_move(&self, game_state: &GameState) -> Move {
-        // This is a simplified pattern recognition.
-        // In a real implementation, you might want to use more sophisticated pattern matching algorithms.
-        let mut move_scores = vec![(0u16, 0f64); 12];
-        
-        for bit in 1..=12 {
-            if game_state.board & (1 << (bit - 1)) != 0 {
-                let score = if game_state.round > 0 && game_state.board & (1 << (bit - 1)) != 0 {
-                    5.0 // Favor moves that were available in the previous round
-                } else {
-                    0.0
-                };
-                move_scores[bit as usize - 1] = (1 << (bit - 1), score);
+    fn choose_move(&mut self, game_state: &GameState, roll: u8) -> u16 {
+        let moves = possible_moves(game_state, roll);
+        let mut counts = vec![0usize; moves.len()];
+        for record in game_state.history.opponent_moves(game_state.player) {
+            if let Some(i) = moves.iter().position(|&m| m == record.move_bits) {
+                counts[i] += 1;
             }
         }
 
-        move_scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-        
-        Move { bits_to_flip: move_scores[0].0 }
+        // Ties go to the highest tiles.
+        moves.iter().zip(&counts).max_by_key(|&(&m, &count)| (count, m)).map_or(0, |(&m, _)| m)
     }
 
     fn name(&self) -> &'static str { "Pattern Recognition" }
-    fn description(&self) -> &'static str { "Recognizes patterns in game progression" }
-}
 
+    fn uses_history(&self) -> bool {
+        true
+    }
+}
 
+// Scores every move with each of its components and plays the best total.
+#[derive(Clone)]
 pub struct CompositeStrategy {
-    components: HashSet<StrategyComponent>,
-    name: String,
-    description: String,
+    components: Vec<StrategyComponent>,
+    name: &'static str,
+    rng: Xoroshiro128Plus,
+    look_ahead: LookAheadStrategy,
+    scores: Scoreboard,
 }
 
 impl CompositeStrategy {
-    pub fn new(components: &[StrategyComponent], name: &str, description: &str) -> Self {
+    pub fn new(components: &[StrategyComponent], name: &str) -> Self {
+        let mut unique = Vec::new();
+        for &component in components {
+            if !unique.contains(&component) {
+                unique.push(component);
+            }
+        }
         CompositeStrategy {
-            components: components.iter().cloned().collect(),
-            name: name.to_string(),
-            description: description.to_string(),
+            components: unique,
+            name: intern(name.to_string()),
+            rng: Xoroshiro128Plus::new(game_seed(0, 0)),
+            look_ahead: LookAheadStrategy::default(),
+            scores: Scoreboard::default(),
         }
     }
 
@@ -260,62 +306,92 @@ impl CompositeStrategy {
 }
 
 impl Strategy for CompositeStrategy {
-    fn choose_move(&self, game_state: &GameState) -> Move {
-        let possible_moves = generate_possible_moves(game_state.board);
-        
+    fn choose_move(&mut self, game_state: &GameState, roll: u8) -> u16 {
+        let possible_moves = possible_moves(game_state, roll);
+
         if possible_moves.is_empty() {
-            return Move { bits_to_flip: 0 };
+            return 0;
         }
-        
-        let chosen_move = self.choose_composite_move(game_state, &possible_moves);
-        Move { bits_to_flip: chosen_move }
+
+        self.choose_composite_move(game_state, &possible_moves)
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn uses_history(&self) -> bool {
+        self.contains(StrategyComponent::PatternRecognition)
     }
 
-    fn name(&self) -> &str {
-        &self.name
+    fn on_game_start(&mut self, seat: usize, rng: Xoroshiro128Plus) {
+        self.rng = rng;
+        self.scores.start(seat);
     }
 
-    fn description(&self) -> &str {
-        &self.description
+    fn on_turn_end(&mut self, player: usize, points: Option<u32>) {
+        self.scores.record(player, points);
     }
 }
 
 impl CompositeStrategy {
-    fn choose_composite_move(&self, state: &GameState, possible_moves: &[u16]) -> u16 {
+    fn choose_composite_move(&mut self, state: &GameState, possible_moves: &[u16]) -> u16 {
         let mut move_scores: Vec<(u16, f64)> = possible_moves.iter().map(|&m| (m, 0.0)).collect();
 
-        for component in &self.components {
-            match component {
-                StrategyComponent::Random => random_score(&mut move_scores),
+        for i in 0..self.components.len() {
+            match self.components[i] {
+                StrategyComponent::Random => random_score(&mut self.rng, &mut move_scores),
                 StrategyComponent::HighestValue => highest_value_score(&mut move_scores),
                 StrategyComponent::HighestProbability => highest_probability_score(&mut move_scores),
                 StrategyComponent::BalancedValue => balanced_value_score(&mut move_scores),
-                StrategyComponent::Adaptive => adaptive_score(state, &mut move_scores),
-                StrategyComponent::LookAhead => look_ahead_score(state, &mut move_scores),
-                StrategyComponent::ScoreManagement => score_management_score(state, &mut move_scores),
+                StrategyComponent::Adaptive => adaptive_score(self.scores.lead(), &mut move_scores),
+                StrategyComponent::LookAhead => look_ahead_score(&mut self.look_ahead, state, &mut move_scores),
+                StrategyComponent::ScoreManagement => score_management_score(self.scores.lead(), &mut move_scores),
                 StrategyComponent::RiskAverse => risk_averse_score(&mut move_scores),
                 StrategyComponent::Aggressive => aggressive_score(&mut move_scores),
                 StrategyComponent::PatternRecognition => pattern_recognition_score(state, &mut move_scores),
             }
         }
 
-        move_scores.iter().max_by(|a, b| a.1.partial_cmp(&b.1).unwrap()).unwrap().0
+        move_scores.iter().max_by(|a, b| a.1.total_cmp(&b.1)).unwrap().0
     }
 }
 
-fn generate_possible_moves(board: u16) -> Vec<u16> {
-    let mut moves = Vec::new();
-    for i in 1..=12 {
-        if board & (1 << (i - 1)) != 0 {
-            moves.push(1 << (i - 1));
+// The legal moves other than passing.
+fn possible_moves(state: &GameState, roll: u8) -> Vec<u16> {
+    let mut moves = legal_moves(state.movable, roll);
+    moves.remove(0);
+    moves
+}
+
+// The move with the first tile of `order` that any move has, taking as few other tiles as
+// possible.
+fn prefer(moves: &[u16], order: &[u8]) -> u16 {
+    for &tile in order {
+        let bit = 1 << (tile - 1);
+        if let Some(&m) = moves.iter().filter(|&&m| m & bit != 0).min_by_key(|m| m.count_ones()) {
+            return m;
         }
     }
-    moves
+    0
+}
+
+// Tile values of a move.
+fn tiles(m: u16) -> impl Iterator<Item = f64> {
+    (0..16).filter(move |i| m & (1 << i) != 0).map(|i| (i + 1) as f64)
+}
+
+fn highest_tile(m: u16) -> f64 {
+    (16 - m.leading_zeros()) as f64
+}
+
+// How likely two dice are to roll a tile's value, from 1 (2 and 12) to 6 (7).
+fn tile_probability(value: f64) -> f64 {
+    (6.0 - (value - 7.0).abs()).max(0.0)
 }
 
 // Implement scoring functions
-fn random_score(move_scores: &mut [(u16, f64)]) {
-    let mut rng = rand::thread_rng();
+fn random_score(rng: &mut Xoroshiro128Plus, move_scores: &mut [(u16, f64)]) {
     for (_, score) in move_scores.iter_mut() {
         *score += rng.gen::<f64>();
     }
@@ -323,46 +399,44 @@ fn random_score(move_scores: &mut [(u16, f64)]) {
 
 fn highest_value_score(move_scores: &mut [(u16, f64)]) {
     for (m, score) in move_scores.iter_mut() {
-        *score += m.trailing_zeros() as f64;
+        *score += highest_tile(*m);
     }
 }
 
 fn highest_probability_score(move_scores: &mut [(u16, f64)]) {
     for (m, score) in move_scores.iter_mut() {
-        let center = (m.trailing_zeros() as i32 - 7).abs();
-        *score += 7.0 - center as f64;
+        *score += tiles(*m).map(tile_probability).sum::<f64>() / m.count_ones() as f64;
     }
 }
 
 fn balanced_value_score(move_scores: &mut [(u16, f64)]) {
     for (m, score) in move_scores.iter_mut() {
-        let value = m.trailing_zeros() as f64;
-        let probability = 7.0 - (value - 7.0).abs();
-        *score += value * probability;
+        *score += tiles(*m).map(|value| value * tile_probability(value)).sum::<f64>() / m.count_ones() as f64;
     }
 }
 
-fn adaptive_score(state: &GameState, move_scores: &mut [(u16, f64)]) {
-    let score_diff = state.total_score as i32 - state.opponent_score as i32;
-    for (m, score) in move_scores.iter_mut() {
-        if score_diff > 0 {
-            // If ahead, prefer safer moves
-            *score += highest_probability_score(&mut [(m, 0.0)])[0].1;
-        } else {
-            // If behind, prefer higher value moves
-            *score += highest_value_score(&mut [(m, 0.0)])[0].1;
-        }
+fn adaptive_score(lead: i32, move_scores: &mut [(u16, f64)]) {
+    if lead > 0 {
+        // If ahead, prefer safer moves
+        highest_probability_score(move_scores);
+    } else {
+        // If behind, prefer higher value moves
+        highest_value_score(move_scores);
     }
 }
 
-fn look_ahead_score(state: &GameState, move_scores: &mut [(u16, f64)]) {
+fn look_ahead_score(look_ahead: &mut LookAheadStrategy, state: &GameState, move_scores: &mut [(u16, f64)]) {
     for (m, score) in move_scores.iter_mut() {
-        *score += evaluate_move(state, *m, MAX_LOOK_AHEAD) as f64;
+        *score += look_ahead.evaluate_position(state.movable & !*m, look_ahead.depth) as f64;
     }
 }
 
-fn score_management_score(state: &GameState, move_scores: &mut [(u16, f64)]) {
-    // Implementation remains the same
+fn score_management_score(lead: i32, move_scores: &mut [(u16, f64)]) {
+    if lead > LEAD_MARGIN {
+        risk_averse_score(move_scores);
+    } else if lead < -LEAD_MARGIN {
+        aggressive_score(move_scores);
+    }
 }
 
 fn risk_averse_score(move_scores: &mut [(u16, f64)]) {
@@ -380,51 +454,76 @@ fn aggressive_score(move_scores: &mut [(u16, f64)]) {
 }
 
 fn pattern_recognition_score(state: &GameState, move_scores: &mut [(u16, f64)]) {
-    // This is a simplified pattern recognition.
     for (m, score) in move_scores.iter_mut() {
-        if state.turn_history.contains(m) {
-            *score += 5.0;  // Favor moves that have been successful in the past
+        if state.history.opponent_moves(state.player).any(|record| record.move_bits == *m) {
+            *score += 5.0;  // Favor moves the opponent has played this match
         }
     }
 }
 
-// Helper functions
-fn evaluate_move(state: &GameState, move_: u16, depth: usize) -> i32 {
-    // Implementation remains the same
-}
-
 pub fn create_strategies() -> Vec<Box<dyn Strategy>> {
     vec![
-        Box::new(RandomStrategy),
+        Box::new(RandomStrategy::default()),
         Box::new(HighestValueStrategy),
         Box::new(HighestProbabilityStrategy),
         Box::new(BalancedValueStrategy),
         Box::new(AdaptiveStrategy),
-        Box::new(LookAheadStrategy),
+        Box::new(LookAheadStrategy::default()),
+        // Adapts strategy based on game state and manages score
         Box::new(CompositeStrategy::new(
             &[StrategyComponent::Adaptive, StrategyComponent::ScoreManagement],
             "Adaptive Score Management",
-            "Adapts strategy based on game state and manages score"
         )),
+        // Looks ahead and manages score
         Box::new(CompositeStrategy::new(
             &[StrategyComponent::LookAhead, StrategyComponent::ScoreManagement],
             "Look Ahead Score Management",
-            "Looks ahead and manages score"
         )),
+        // Advanced strategy combining look-ahead, adaptation, and score management
         Box::new(CompositeStrategy::new(
             &[StrategyComponent::LookAhead, StrategyComponent::Adaptive, StrategyComponent::ScoreManagement],
             "Advanced Adaptive",
-            "Advanced strategy combining look-ahead, adaptation, and score management"
         )),
+        // Balances value while being risk-averse and recognizing patterns
         Box::new(CompositeStrategy::new(
             &[StrategyComponent::BalancedValue, StrategyComponent::RiskAverse, StrategyComponent::PatternRecognition],
             "Cautious Pattern Learner",
-            "Balances value while being risk-averse and recognizing patterns"
         )),
+        // Aggressively plans ahead focusing on highest values
         Box::new(CompositeStrategy::new(
             &[StrategyComponent::HighestValue, StrategyComponent::Aggressive, StrategyComponent::LookAhead],
             "Aggressive Planner",
-            "Aggressively plans ahead focusing on highest values"
         )),
     ]
-}
\ No newline at end of file
+}
+// Looks a strategy up by its display name, ignoring case.
+pub fn create_strategy(name: &str) -> Option<Box<dyn Strategy>> {
+    create_strategies().into_iter().find(|s| s.name().eq_ignore_ascii_case(name.trim()))
+}
+
+pub fn strategy_names() -> Vec<String> {
+    create_strategies().iter().map(|s| s.name().to_string()).collect()
+}
+
+// `create_strategy` with numeric parameters for the strategies that take them: `depth` (1 or
+// more) for Look Ahead. Fails for unknown names, unknown parameters and out-of-range values.
+pub fn create_strategy_with(name: &str, params: &BTreeMap<String, f64>) -> Result<Box<dyn Strategy>, String> {
+    let strategy = create_strategy(name).ok_or_else(|| format!("unknown strategy '{}'", name))?;
+    if params.is_empty() {
+        return Ok(strategy);
+    }
+    match strategy.name() {
+        "Look Ahead" => {
+            let mut look_ahead = LookAheadStrategy::default();
+            for (key, &value) in params {
+                match key.as_str() {
+                    "depth" if value >= 1.0 && value.fract() == 0.0 => look_ahead.depth = value as i32,
+                    "depth" => return Err(format!("Look Ahead depth must be a whole number of at least 1, got {}", value)),
+                    other => return Err(format!("Look Ahead has no parameter '{}'", other)),
+                }
+            }
+            Ok(Box::new(look_ahead))
+        },
+        other => Err(format!("{} takes no parameters", other)),
+    }
+}
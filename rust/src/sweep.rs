@@ -0,0 +1,363 @@
+// File: src/sweep.rs
+
+use crate::control::SimulationControl;
+use crate::experiment::{read_file, validate_outputs, ConfigError, ExperimentConfig, OutputSpec};
+use crate::rng::Xoroshiro128Plus;
+use crate::simulation;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::{Serialize, Deserialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+// A base experiment, the parameters to vary and where the rows go. Parameters are dotted paths
+// into the experiment, e.g. `rules.board_size` or `parameters."Look Ahead".depth`, so anything an
+// experiment file can set can be swept. Every cell reuses the base seed, so game `i` of a pairing
+// starts from the same generator state in every cell. Cells with the same optimization level and
+// dice roll the same sequence; sweeping either changes the generator or the distribution.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SweepConfig {
+    pub experiment: Value,
+    pub parameters: BTreeMap<String, ParameterValues>,
+    #[serde(default)]
+    pub method: SweepMethod,
+    // Cells drawn by a Latin hypercube.
+    #[serde(default)]
+    pub samples: Option<usize>,
+    // Seeds the Latin hypercube draw.
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default)]
+    pub outputs: Vec<OutputSpec>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SweepMethod {
+    // Every combination of the parameter values.
+    #[default]
+    Grid,
+    // `samples` cells, each parameter's values covered as evenly as the sample count allows.
+    LatinHypercube,
+}
+
+// A list of values, or an inclusive integer range.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ParameterValues {
+    Range {
+        from: i64,
+        to: i64,
+        #[serde(default)]
+        step: Option<i64>,
+    },
+    List(Vec<Value>),
+}
+
+#[derive(Clone, Debug)]
+pub struct Cell {
+    pub index: usize,
+    pub values: BTreeMap<String, Value>,
+    pub experiment: ExperimentConfig,
+}
+
+// One strategy in one cell, ranked by its win rate over every pairing in the cell.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SweepRow {
+    pub cell: usize,
+    pub parameters: BTreeMap<String, Value>,
+    pub strategy: String,
+    pub rank: usize,
+    pub games: u64,
+    pub wins: u64,
+    pub draws: u64,
+    pub losses: u64,
+    pub win_rate: f64,
+}
+
+impl ParameterValues {
+    pub fn values(&self) -> Vec<Value> {
+        match self {
+            ParameterValues::Range { from, to, step } => {
+                let step = step.unwrap_or(1).max(1) as usize;
+                (*from..=*to).step_by(step).map(Value::from).collect()
+            },
+            ParameterValues::List(values) => values.clone(),
+        }
+    }
+}
+
+impl SweepConfig {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let config: Self = read_file(path)?;
+        config.validate().map_err(|e| e.in_file(path))?;
+        Ok(config)
+    }
+
+    // Builds and validates every cell, so a bad value fails before anything runs.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.cells()?;
+        validate_outputs(&self.outputs)
+    }
+
+    pub fn cells(&self) -> Result<Vec<Cell>, ConfigError> {
+        let invalid = |message: String| Err(ConfigError::Invalid(message));
+
+        if !self.experiment.is_object() {
+            return invalid("experiment must be a table".to_string());
+        }
+        if self.parameters.is_empty() {
+            return invalid("parameters: a sweep needs at least one parameter".to_string());
+        }
+        let names: Vec<&String> = self.parameters.keys().collect();
+        let levels: Vec<Vec<Value>> = self.parameters.values().map(ParameterValues::values).collect();
+        if let Some(i) = levels.iter().position(Vec::is_empty) {
+            return invalid(format!("parameters.{}: no values to sweep", names[i]));
+        }
+
+        let choices = match self.method {
+            SweepMethod::Grid => grid(&levels),
+            SweepMethod::LatinHypercube => match self.samples {
+                Some(samples) if samples > 0 => latin_hypercube(&levels, samples, self.seed),
+                _ => return invalid("samples must be at least 1 for a latin-hypercube sweep".to_string()),
+            },
+        };
+
+        choices.into_iter().enumerate()
+            .map(|(index, choice)| {
+                let values: BTreeMap<String, Value> = names.iter().zip(&choice).zip(&levels)
+                    .map(|((name, &level), values)| (name.to_string(), values[level].clone()))
+                    .collect();
+                let experiment = build_cell(&self.experiment, &values).map_err(|e| {
+                    let label: Vec<String> = values.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+                    let message = format!("cell {} ({}): {}", index, label.join(", "), e);
+                    match e {
+                        ConfigError::UnknownStrategy(_) => ConfigError::UnknownStrategy(message),
+                        ConfigError::Io(e) => ConfigError::Io(e),
+                        _ => ConfigError::Invalid(message),
+                    }
+                })?;
+                Ok(Cell { index, values, experiment })
+            })
+            .collect()
+    }
+
+    // Plays every pairing of every cell. The control handle's limits cover the whole sweep; a
+    // cell cut short by it is left out, so every returned row comes from a complete cell.
+    pub fn run(&self, control: &SimulationControl) -> Result<Vec<SweepRow>, ConfigError> {
+        let cells = self.cells()?;
+        let mut runs = Vec::with_capacity(cells.len());
+        for cell in &cells {
+            runs.push(cell.experiment.pairings()?);
+        }
+        let requested = runs.iter().flatten().map(|config| config.num_games).sum();
+        let total = control.start_session(requested);
+
+        let mut rows = Vec::new();
+        'cells: for (cell, pairings) in cells.iter().zip(runs) {
+            let mut totals: BTreeMap<String, (u64, u64, u64, u64)> = BTreeMap::new();
+            for config in pairings {
+                if control.should_stop() || control.games_done() + config.num_games > total {
+                    break 'cells;
+                }
+                let num_games = config.num_games;
+                let report = simulation::run_simulation(config, control)?;
+                if (report.games as usize) < num_games {
+                    break 'cells;
+                }
+                for s in &report.strategies {
+                    let entry = totals.entry(s.name.clone()).or_default();
+                    entry.0 += s.games;
+                    entry.1 += s.wins;
+                    entry.2 += s.draws;
+                    entry.3 += s.losses;
+                }
+            }
+
+            let mut cell_rows: Vec<SweepRow> = totals.into_iter()
+                .map(|(strategy, (games, wins, draws, losses))| SweepRow {
+                    cell: cell.index,
+                    parameters: cell.values.clone(),
+                    strategy,
+                    rank: 0,
+                    games,
+                    wins,
+                    draws,
+                    losses,
+                    win_rate: if games > 0 { wins as f64 / games as f64 } else { 0.0 },
+                })
+                .collect();
+            cell_rows.sort_by(|a, b| b.win_rate.partial_cmp(&a.win_rate).unwrap().then_with(|| a.strategy.cmp(&b.strategy)));
+            for (rank, row) in cell_rows.iter_mut().enumerate() {
+                row.rank = rank + 1;
+            }
+            rows.extend(cell_rows);
+        }
+        control.end_session();
+        Ok(rows)
+    }
+}
+
+// Value indices for every combination, the last parameter varying fastest.
+fn grid(levels: &[Vec<Value>]) -> Vec<Vec<usize>> {
+    let mut choices = vec![Vec::new()];
+    for values in levels {
+        choices = choices.into_iter()
+            .flat_map(|choice: Vec<usize>| (0..values.len()).map(move |i| {
+                let mut next = choice.clone();
+                next.push(i);
+                next
+            }))
+            .collect();
+    }
+    choices
+}
+
+// Each parameter gets an independent shuffle of the `samples` strata, and stratum k of n values
+// maps to value floor((k + u) * n / samples) for a uniform jitter u.
+fn latin_hypercube(levels: &[Vec<Value>], samples: usize, seed: u64) -> Vec<Vec<usize>> {
+    let mut rng = Xoroshiro128Plus::new(seed);
+    let mut choices = vec![Vec::with_capacity(levels.len()); samples];
+    for values in levels {
+        let mut strata: Vec<usize> = (0..samples).collect();
+        strata.shuffle(&mut rng);
+        for (choice, stratum) in choices.iter_mut().zip(strata) {
+            let position = (stratum as f64 + rng.gen::<f64>()) / samples as f64;
+            choice.push(((position * values.len() as f64) as usize).min(values.len() - 1));
+        }
+    }
+    choices
+}
+
+fn build_cell(base: &Value, values: &BTreeMap<String, Value>) -> Result<ExperimentConfig, ConfigError> {
+    let mut experiment = base.clone();
+    for (path, value) in values {
+        set_path(&mut experiment, path, value.clone())?;
+    }
+    let experiment: ExperimentConfig = serde_json::from_value(experiment).map_err(|e| ConfigError::Parse(e.to_string()))?;
+    if experiment.tournament.is_some() {
+        return Err(ConfigError::Invalid("experiment.tournament: sweeps play every pairing through the simulator".to_string()));
+    }
+    if experiment.games.is_some() {
+        return Err(ConfigError::Invalid("experiment.games: sweeps don't record per-game rows".to_string()));
+    }
+    if experiment.store.is_some() {
+        return Err(ConfigError::Invalid("experiment.store: sweeps aren't recorded in the results database".to_string()));
+    }
+    if !experiment.outputs.is_empty() {
+        return Err(ConfigError::Invalid("experiment.outputs: set the sweep's outputs at the top level".to_string()));
+    }
+    experiment.validate()?;
+    Ok(experiment)
+}
+
+// Sets a dotted path, creating the tables along it. A key may be quoted, as in TOML, to hold
+// dots or spaces: `parameters."Look Ahead".depth` and `parameters.Look Ahead.depth` are the same.
+fn set_path(root: &mut Value, path: &str, value: Value) -> Result<(), ConfigError> {
+    let keys = split_path(path)?;
+    let mut node = root;
+    for (depth, key) in keys.iter().enumerate() {
+        let table = node.as_object_mut().ok_or_else(|| {
+            ConfigError::Invalid(format!("can't set '{}': '{}' is not a table", path, keys[..depth].join(".")))
+        })?;
+        if depth == keys.len() - 1 {
+            table.insert(key.to_string(), value);
+            return Ok(());
+        }
+        node = table.entry(key.to_string()).or_insert_with(|| Value::Object(Map::new()));
+    }
+    Ok(())
+}
+
+fn split_path(path: &str) -> Result<Vec<String>, ConfigError> {
+    let mut keys = vec![String::new()];
+    let mut quoted = false;
+    for c in path.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '.' if !quoted => keys.push(String::new()),
+            c => keys.last_mut().unwrap().push(c),
+        }
+    }
+    if quoted {
+        return Err(ConfigError::Invalid(format!("can't set '{}': unterminated quote", path)));
+    }
+    if keys.iter().any(|key| key.trim().is_empty()) {
+        return Err(ConfigError::Invalid(format!("can't set '{}': empty key", path)));
+    }
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sweep(parameters: Value) -> SweepConfig {
+        serde_json::from_value(json!({
+            "experiment": {"strategies": ["Random", "Highest Value"], "num_games": 20, "seed": 5},
+            "parameters": parameters,
+        })).unwrap()
+    }
+
+    #[test]
+    fn grid_covers_every_combination_last_parameter_fastest() {
+        let levels = vec![vec![json!(1), json!(2)], vec![json!("a"), json!("b"), json!("c")]];
+        assert_eq!(grid(&levels), vec![vec![0, 0], vec![0, 1], vec![0, 2], vec![1, 0], vec![1, 1], vec![1, 2]]);
+
+        let range = ParameterValues::Range { from: 3, to: 9, step: Some(3) };
+        assert_eq!(range.values(), vec![json!(3), json!(6), json!(9)]);
+        let cells = sweep(json!({"rules.rounds": {"from": 1, "to": 3}, "num_games": [10, 20]})).cells().unwrap();
+        assert_eq!(cells.len(), 6);
+        assert_eq!((cells[5].experiment.rules.rounds, cells[5].experiment.num_games), (3, 20));
+    }
+
+    #[test]
+    fn latin_hypercube_covers_each_value_evenly() {
+        let levels = vec![(0..5).map(Value::from).collect::<Vec<_>>(), (0..10).map(Value::from).collect()];
+        let choices = latin_hypercube(&levels, 10, 3);
+        assert_eq!(choices.len(), 10);
+        // Ten strata over five values is two samples per value, over ten values one each.
+        for (parameter, repeats) in [(0, 2), (1, 1)] {
+            let mut counts = vec![0; levels[parameter].len()];
+            for choice in &choices {
+                counts[choice[parameter]] += 1;
+            }
+            assert!(counts.iter().all(|&count| count == repeats), "{:?}", counts);
+        }
+        assert_eq!(choices, latin_hypercube(&levels, 10, 3));
+        assert_ne!(choices, latin_hypercube(&levels, 10, 4));
+    }
+
+    #[test]
+    fn set_path_creates_nested_tables_and_rejects_bad_keys() {
+        let mut root = json!({"num_games": 10});
+        set_path(&mut root, "parameters.Look Ahead.depth", json!(2)).unwrap();
+        set_path(&mut root, "parameters.\"Look Ahead\".width", json!(3)).unwrap();
+        set_path(&mut root, "notes.\"v1.2\"", json!(true)).unwrap();
+        assert_eq!(root, json!({"num_games": 10, "parameters": {"Look Ahead": {"depth": 2, "width": 3}}, "notes": {"v1.2": true}}));
+
+        assert!(set_path(&mut root, "num_games.limit", json!(1)).is_err());
+        assert!(set_path(&mut root, "rules..rounds", json!(1)).is_err());
+        assert!(set_path(&mut root, "", json!(1)).is_err());
+        assert!(set_path(&mut root, "parameters.\"Look Ahead.depth", json!(1)).is_err());
+        assert!(sweep(json!({"num_games.limit": [1]})).cells().is_err());
+        assert!(sweep(json!({"rules.no_such_rule": [1]})).cells().is_err());
+    }
+
+    #[test]
+    fn run_ranks_the_strategies_of_every_cell() {
+        let rows = sweep(json!({"num_games": [10, 30]})).run(&SimulationControl::new()).unwrap();
+        assert_eq!(rows.len(), 4);
+        for (cell, games) in [(0, 10), (1, 30)] {
+            let cell_rows: Vec<&SweepRow> = rows.iter().filter(|row| row.cell == cell).collect();
+            assert_eq!(cell_rows.iter().map(|row| row.rank).collect::<Vec<_>>(), [1, 2]);
+            assert!(cell_rows.iter().all(|row| row.games == games && row.wins + row.draws + row.losses == games));
+            assert!(cell_rows[0].win_rate >= cell_rows[1].win_rate);
+            assert_eq!(cell_rows[0].parameters["num_games"], json!(games));
+        }
+    }
+}
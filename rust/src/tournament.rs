@@ -1,39 +1,409 @@
 // File: src/tournament.rs
 
-use crate::{GameMode, OptimizationLevel, Strategy, game::Game};
+use crate::{GameMode, GameOutcome, OptimizationLevel, Strategy, game::Game};
+use crate::history::MatchHistory;
+#[cfg(feature = "serde")]
+use crate::checkpoint::{Checkpoint, MatchProgress, TournamentCheckpoint};
+use crate::rng::game_seed;
+use crate::game::GameRules;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
+#[cfg(feature = "serde")]
+use std::io;
+use std::ops::Range;
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
-use rand::Rng;
-
-pub fn run_simulation(mode: GameMode, optimization: OptimizationLevel, strategies: &[Box<dyn Strategy>], num_games: usize) -> HashMap<String, HashMap<String, f64>> {
-    let mut results = HashMap::new();
-
-    for (i, strategy1) in strategies.iter().enumerate() {
-        for strategy2 in strategies.iter().skip(i + 1) {
-            let (wins1, wins2) = (0..num_games).into_par_iter().map(|_| {
-                let mut rng = rand::thread_rng();
-                let seed = rng.gen();
-                let mut game = Game::new(mode, optimization, seed);
-                let game_result = game.play_game(&[strategy1.as_ref(), strategy2.as_ref()]);
-                match game_result.winner {
-                    0 => (1, 0),
-                    1 => (0, 1),
-                    _ => (0, 0),
+
+// Match `k` of a tournament, counted in the order the matches are played, is seeded with
+// `game_seed(seed, k)`, so the same seed replays the whole tournament.
+pub trait Tournament {
+    fn format(&self) -> &'static str;
+    fn run(&self, mode: GameMode, optimization: OptimizationLevel, strategies: &[Box<dyn Strategy>], seed: u64) -> Result<TournamentResult, String>;
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MatchResult {
+    pub round: usize,
+    pub strategy1: String,
+    pub strategy2: String,
+    pub wins1: usize,
+    pub wins2: usize,
+    pub draws: usize,
+}
+
+impl MatchResult {
+    pub fn games(&self) -> usize {
+        self.wins1 + self.wins2 + self.draws
+    }
+
+    // 0 for strategy1, 1 for strategy2 if it won more games, None for an even match.
+    pub fn winner(&self) -> Option<usize> {
+        if self.wins1 > self.wins2 {
+            Some(0)
+        } else if self.wins2 > self.wins1 {
+            Some(1)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Standing {
+    pub name: String,
+    pub points: f64,
+    pub match_wins: usize,
+    pub match_losses: usize,
+    pub match_draws: usize,
+    pub game_wins: usize,
+    pub game_losses: usize,
+    pub game_draws: usize,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TournamentResult {
+    pub format: String,
+    pub matches: Vec<MatchResult>,
+    pub standings: Vec<Standing>,
+}
+
+impl TournamentResult {
+    // Builds standings from the played matches: 1 point per match win, 0.5 per drawn match,
+    // ties broken by game differential.
+    pub fn from_matches(format: &str, strategies: &[Box<dyn Strategy>], matches: Vec<MatchResult>) -> Self {
+        let mut standings: Vec<Standing> = strategies.iter()
+            .map(|s| Standing { name: s.name().to_string(), ..Standing::default() })
+            .collect();
+
+        for m in &matches {
+            for (seat, name) in [&m.strategy1, &m.strategy2].into_iter().enumerate() {
+                let standing = match standings.iter_mut().find(|s| &s.name == name) {
+                    Some(standing) => standing,
+                    None => continue,
+                };
+                let (won, lost) = if seat == 0 { (m.wins1, m.wins2) } else { (m.wins2, m.wins1) };
+                standing.game_wins += won;
+                standing.game_losses += lost;
+                standing.game_draws += m.draws;
+                match m.winner() {
+                    Some(winner) if winner == seat => {
+                        standing.match_wins += 1;
+                        standing.points += 1.0;
+                    },
+                    Some(_) => standing.match_losses += 1,
+                    None => {
+                        standing.match_draws += 1;
+                        standing.points += 0.5;
+                    },
                 }
-            }).reduce(|| (0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+            }
+        }
 
-            let win_rate1 = wins1 as f64 / num_games as f64;
-            let win_rate2 = wins2 as f64 / num_games as f64;
+        standings.sort_by(|a, b| {
+            let diff_a = a.game_wins as i64 - a.game_losses as i64;
+            let diff_b = b.game_wins as i64 - b.game_losses as i64;
+            b.points.partial_cmp(&a.points).unwrap().then(diff_b.cmp(&diff_a))
+        });
 
-            results.entry(strategy1.name().to_string())
-                .or_insert_with(HashMap::new)
-                .insert(strategy2.name().to_string(), win_rate1);
+        TournamentResult { format: format.to_string(), matches, standings }
+    }
 
-            results.entry(strategy2.name().to_string())
+    // Pairwise win rates in the shape `run_simulation` has always returned.
+    pub fn win_rates(&self) -> HashMap<String, HashMap<String, f64>> {
+        let mut results = HashMap::new();
+        for m in &self.matches {
+            let games = m.games().max(1) as f64;
+            results.entry(m.strategy1.clone())
                 .or_insert_with(HashMap::new)
-                .insert(strategy1.name().to_string(), win_rate2);
+                .insert(m.strategy2.clone(), m.wins1 as f64 / games);
+            results.entry(m.strategy2.clone())
+                .or_insert_with(HashMap::new)
+                .insert(m.strategy1.clone(), m.wins2 as f64 / games);
+        }
+        results
+    }
+}
+
+// The seat that won `outcome`, or None for a drawn base game; the engine's own winner breaks
+// base-mode ties towards seat 0.
+pub fn game_winner(mode: GameMode, outcome: &GameOutcome) -> Option<usize> {
+    match mode {
+        GameMode::Base if outcome.scores[0] == outcome.scores[1] => None,
+        _ => Some(outcome.winner),
+    }
+}
+
+// Game `i` of the match is seeded with `game_seed(seed, i)`, and the strategies swap seats
+// every game.
+#[allow(clippy::too_many_arguments)]
+pub fn play_match(mode: GameMode, optimization: OptimizationLevel, rules: GameRules, strategy1: &dyn Strategy, strategy2: &dyn Strategy, num_games: usize, round: usize, seed: u64) -> MatchResult {
+    let (wins1, wins2) = count_wins(mode, optimization, rules, strategy1, strategy2, 0..num_games, |i| game_seed(seed, i as u64));
+
+    MatchResult {
+        round,
+        strategy1: strategy1.name().to_string(),
+        strategy2: strategy2.name().to_string(),
+        wins1,
+        wins2,
+        draws: num_games - wins1 - wins2,
+    }
+}
+
+// Wins for each strategy over the given game indices, with game `i` seeded by `seed_for(i)`.
+// Seats alternate by game index, so strategy1 moves first in the even games and strategy2 in the
+// odd ones.
+fn count_wins<F>(mode: GameMode, optimization: OptimizationLevel, rules: GameRules, strategy1: &dyn Strategy, strategy2: &dyn Strategy, games: Range<usize>, seed_for: F) -> (usize, usize)
+where
+    F: Fn(usize) -> u64 + Sync + Send,
+{
+    let tally = |outcome: &GameOutcome, swapped: bool| match (game_winner(mode, outcome), swapped) {
+        (Some(0), false) | (Some(1), true) => (1, 0),
+        (Some(_), _) => (0, 1),
+        (None, _) => (0, 0),
+    };
+    // Each worker plays its own copies of the strategies.
+    let worker = || (Game::from_rules(mode, optimization, rules, 0), strategy1.clone_strategy(), strategy2.clone_strategy());
+    let play = |(game, a, b): &mut (Game, Box<dyn Strategy>, Box<dyn Strategy>), i: usize| {
+        let swapped = i % 2 == 1;
+        let seats: [&mut dyn Strategy; 2] = if swapped { [b.as_mut(), a.as_mut()] } else { [a.as_mut(), b.as_mut()] };
+        game.reset(seed_for(i));
+        let outcome = game.play_pair(seats);
+        tally(&outcome, swapped)
+    };
+
+    // Strategies that learn from the match see its games in order, each seeded as above.
+    if strategy1.uses_history() || strategy2.uses_history() {
+        let (mut game, mut a, mut b) = worker();
+        let mut history = MatchHistory::new();
+        let mut wins = (0, 0);
+        for i in games {
+            let swapped = i % 2 == 1;
+            let seats: [&mut dyn Strategy; 2] = if swapped { [b.as_mut(), a.as_mut()] } else { [a.as_mut(), b.as_mut()] };
+            game.reset(seed_for(i));
+            history.start_game(swapped as usize);
+            let outcome = game.play_pair_observed(seats, &mut history);
+            history.end_game(&outcome);
+            let (won1, won2) = tally(&outcome, swapped);
+            wins = (wins.0 + won1, wins.1 + won2);
         }
+        return wins;
     }
 
-    results
-}
\ No newline at end of file
+    #[cfg(feature = "parallel")]
+    let wins = games.into_par_iter()
+        .map_init(worker, play)
+        .reduce(|| (0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+
+    #[cfg(not(feature = "parallel"))]
+    let wins = {
+        let mut worker = worker();
+        games.map(|i| play(&mut worker, i)).fold((0, 0), |a, b| (a.0 + b.0, a.1 + b.1))
+    };
+
+    wins
+}
+
+pub struct RoundRobin {
+    pub games_per_match: usize,
+}
+
+impl RoundRobin {
+    // A round robin can't fail, so callers outside the `Tournament` trait use this directly.
+    pub fn play(&self, mode: GameMode, optimization: OptimizationLevel, strategies: &[Box<dyn Strategy>], seed: u64) -> TournamentResult {
+        let mut matches = Vec::new();
+        for (i, strategy1) in strategies.iter().enumerate() {
+            for strategy2 in strategies.iter().skip(i + 1) {
+                let match_seed = game_seed(seed, matches.len() as u64);
+                matches.push(play_match(mode, optimization, GameRules::default(), strategy1.as_ref(), strategy2.as_ref(), self.games_per_match, 0, match_seed));
+            }
+        }
+        TournamentResult::from_matches(self.format(), strategies, matches)
+    }
+}
+
+impl Tournament for RoundRobin {
+    fn format(&self) -> &'static str { "Round Robin" }
+
+    fn run(&self, mode: GameMode, optimization: OptimizationLevel, strategies: &[Box<dyn Strategy>], seed: u64) -> Result<TournamentResult, String> {
+        Ok(self.play(mode, optimization, strategies, seed))
+    }
+}
+
+pub fn run_simulation(mode: GameMode, optimization: OptimizationLevel, strategies: &[Box<dyn Strategy>], num_games: usize, seed: u64) -> HashMap<String, HashMap<String, f64>> {
+    RoundRobin { games_per_match: num_games }
+        .play(mode, optimization, strategies, seed)
+        .win_rates()
+}
+
+// `run_simulation` with a fixed seed that saves its progress to `checkpoint.path` every
+// `checkpoint.interval` games and resumes from an existing checkpoint for the same settings.
+// Games are seeded as in `run_simulation`, so a resumed run returns exactly what an
+// uninterrupted one would, except that strategies using the match history only see the games
+// since the last checkpoint.
+#[cfg(feature = "serde")]
+pub fn run_simulation_checkpointed(mode: GameMode, optimization: OptimizationLevel, rules: GameRules, strategies: &[Box<dyn Strategy>], num_games: usize, seed: u64, checkpoint: &Checkpoint) -> io::Result<HashMap<String, HashMap<String, f64>>> {
+    let config = crate::SimulationConfig {
+        num_games,
+        game_mode: mode,
+        optimization_level: optimization,
+        strategies: strategies.iter().map(|s| s.name().to_string()).collect(),
+        seed,
+        rules,
+    };
+    let mut state = match checkpoint.load::<TournamentCheckpoint>()? {
+        Some(state) => {
+            state.check(&config)?;
+            state
+        },
+        None => TournamentCheckpoint::new(config),
+    };
+
+    let mut pairings = Vec::new();
+    for i in 0..strategies.len() {
+        for j in i + 1..strategies.len() {
+            pairings.push((i, j));
+        }
+    }
+
+    for (k, &(i, j)) in pairings.iter().enumerate().skip(state.completed.len()) {
+        let (strategy1, strategy2) = (strategies[i].as_ref(), strategies[j].as_ref());
+        let match_seed = game_seed(seed, k as u64);
+        let mut progress = state.current.take().unwrap_or_else(|| MatchProgress {
+            result: MatchResult {
+                round: 0,
+                strategy1: strategy1.name().to_string(),
+                strategy2: strategy2.name().to_string(),
+                wins1: 0,
+                wins2: 0,
+                draws: 0,
+            },
+            next_game: 0,
+        });
+
+        while progress.next_game < num_games {
+            let end = (progress.next_game + checkpoint.interval).min(num_games);
+            let (wins1, wins2) = count_wins(mode, optimization, rules, strategy1, strategy2, progress.next_game..end, |g| game_seed(match_seed, g as u64));
+            progress.result.wins1 += wins1;
+            progress.result.wins2 += wins2;
+            progress.result.draws += end - progress.next_game - wins1 - wins2;
+            progress.next_game = end;
+            if end < num_games {
+                state.current = Some(progress.clone());
+                checkpoint.save(&state)?;
+            }
+        }
+
+        state.current = None;
+        state.completed.push(progress.result);
+        checkpoint.save(&state)?;
+    }
+
+    Ok(TournamentResult::from_matches("Round Robin", strategies, state.completed).win_rates())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategies::{AggressiveStrategy, HighestProbabilityStrategy, HighestValueStrategy, RandomStrategy};
+    use crate::tournament_elimination::Elimination;
+    use crate::tournament_gauntlet::Gauntlet;
+    use crate::tournament_swiss::Swiss;
+
+    fn field() -> Vec<Box<dyn Strategy>> {
+        vec![Box::new(HighestValueStrategy), Box::new(HighestProbabilityStrategy), Box::new(AggressiveStrategy)]
+    }
+
+    fn counts(result: &TournamentResult) -> Vec<(usize, usize, usize)> {
+        result.matches.iter().map(|m| (m.wins1, m.wins2, m.draws)).collect()
+    }
+
+    #[test]
+    fn every_format_replays_from_its_seed() {
+        let formats: Vec<Box<dyn Tournament>> = vec![
+            Box::new(RoundRobin { games_per_match: 40 }),
+            Box::new(Swiss { rounds: 3, games_per_match: 40 }),
+            Box::new(Elimination::double(3)),
+            Box::new(Gauntlet { candidate: "Highest Value".to_string(), games_per_match: 40 }),
+        ];
+        for format in formats {
+            let run = |seed| format.run(GameMode::Base, OptimizationLevel::Fast, &field(), seed).unwrap();
+            assert_eq!(counts(&run(1)), counts(&run(1)), "{}", format.format());
+        }
+        let round_robin = RoundRobin { games_per_match: 40 };
+        let first = round_robin.play(GameMode::Base, OptimizationLevel::Fast, &field(), 1);
+        assert_ne!(counts(&first), counts(&round_robin.play(GameMode::Base, OptimizationLevel::Fast, &field(), 2)));
+    }
+
+    #[test]
+    fn tied_base_games_are_draws() {
+        let result = play_match(GameMode::Base, OptimizationLevel::Fast, GameRules::default(), &HighestValueStrategy, &HighestProbabilityStrategy, 300, 0, 5);
+        let mut game = Game::new(GameMode::Base, OptimizationLevel::Fast, 0);
+        let ties = (0..300u64)
+            .filter(|&i| {
+                game.reset(game_seed(5, i));
+                let outcome = if i % 2 == 0 {
+                    game.play_pair([&mut HighestValueStrategy, &mut HighestProbabilityStrategy])
+                } else {
+                    game.play_pair([&mut HighestProbabilityStrategy, &mut HighestValueStrategy])
+                };
+                outcome.scores[0] == outcome.scores[1]
+            })
+            .count();
+        assert!(ties > 0);
+        assert_eq!(result.draws, ties);
+    }
+
+    #[test]
+    fn ranking_ignores_the_field_order() {
+        let ranking = |strategies: &[Box<dyn Strategy>]| {
+            RoundRobin { games_per_match: 2000 }
+                .play(GameMode::Base, OptimizationLevel::Fast, strategies, 8)
+                .standings
+                .iter()
+                .map(|s| s.name.clone())
+                .collect::<Vec<_>>()
+        };
+        // Highest Value and Aggressive play alike, so their match is a coin toss.
+        let field = || -> Vec<Box<dyn Strategy>> { vec![Box::new(RandomStrategy::default()), Box::new(HighestValueStrategy), Box::new(HighestProbabilityStrategy)] };
+        let mut reversed = field();
+        reversed.reverse();
+        assert_eq!(ranking(&field()), ranking(&reversed));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn checkpointed_round_robin_matches_run_simulation() {
+        let path = std::env::temp_dir().join(format!("dice_game_round_robin_{}.json", std::process::id()));
+        let checkpoint = Checkpoint::new(&path, 25);
+        let checkpointed = run_simulation_checkpointed(GameMode::Base, OptimizationLevel::Fast, GameRules::default(), &field(), 60, 3, &checkpoint).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(checkpointed, run_simulation(GameMode::Base, OptimizationLevel::Fast, &field(), 60, 3));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn checkpointed_round_robin_plays_by_the_rules() {
+        let rules = GameRules { board_size: 6, rounds: 1, dice: None };
+        let pair = || -> Vec<Box<dyn Strategy>> { vec![Box::new(HighestValueStrategy), Box::new(HighestProbabilityStrategy)] };
+        let path = std::env::temp_dir().join(format!("dice_game_round_robin_rules_{}.json", std::process::id()));
+        let checkpoint = Checkpoint::new(&path, 25);
+        let checkpointed = run_simulation_checkpointed(GameMode::Base, OptimizationLevel::Fast, rules, &pair(), 60, 3, &checkpoint).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let played = play_match(GameMode::Base, OptimizationLevel::Fast, rules, &HighestValueStrategy, &HighestProbabilityStrategy, 60, 0, game_seed(3, 0));
+        assert_eq!(checkpointed["Highest Value"]["Highest Probability"], played.wins1 as f64 / 60.0);
+        assert_eq!(checkpointed["Highest Probability"]["Highest Value"], played.wins2 as f64 / 60.0);
+        let standard = play_match(GameMode::Base, OptimizationLevel::Fast, GameRules::default(), &HighestValueStrategy, &HighestProbabilityStrategy, 60, 0, game_seed(3, 0));
+        assert_ne!((played.wins1, played.wins2), (standard.wins1, standard.wins2));
+    }
+
+    #[test]
+    fn gauntlet_candidate_must_be_in_the_field() {
+        let gauntlet = Gauntlet { candidate: "Look Ahead".to_string(), games_per_match: 10 };
+        assert!(gauntlet.run(GameMode::Base, OptimizationLevel::Fast, &field(), 0).is_err());
+    }
+}
@@ -0,0 +1,104 @@
+// File: src/tournament_elimination.rs
+
+use crate::{GameMode, OptimizationLevel, Strategy, game::Game};
+use crate::history::MatchHistory;
+use crate::rng::game_seed;
+use crate::tournament::{Tournament, TournamentResult, MatchResult, game_winner};
+
+pub struct Elimination {
+    pub best_of: usize,
+    pub max_losses: usize,
+}
+
+impl Elimination {
+    pub fn single(best_of: usize) -> Self {
+        Elimination { best_of, max_losses: 1 }
+    }
+
+    pub fn double(best_of: usize) -> Self {
+        Elimination { best_of, max_losses: 2 }
+    }
+
+    // Plays games until one side takes a majority of `best_of`, alternating seats each game;
+    // drawn games are replayed. Game `i` of the series is seeded with `game_seed(seed, i)`.
+    // The series is one match, so both strategies see its history, and each side is one
+    // instance for the whole series.
+    fn play_series(&self, mode: GameMode, optimization: OptimizationLevel, strategy1: &dyn Strategy, strategy2: &dyn Strategy, round: usize, seed: u64) -> MatchResult {
+        let needed = self.best_of / 2 + 1;
+        let mut game = Game::new(mode, optimization, 0);
+        let mut history = MatchHistory::new();
+        let (mut first, mut second) = (strategy1.clone_strategy(), strategy2.clone_strategy());
+        let (mut wins1, mut wins2, mut draws) = (0, 0, 0);
+
+        while wins1 < needed && wins2 < needed {
+            let played = wins1 + wins2 + draws;
+            let swapped = played % 2 == 1;
+            let seats: [&mut dyn Strategy; 2] = if swapped { [second.as_mut(), first.as_mut()] } else { [first.as_mut(), second.as_mut()] };
+            game.reset(game_seed(seed, played as u64));
+            history.start_game(swapped as usize);
+            let outcome = game.play_pair_observed(seats, &mut history);
+            history.end_game(&outcome);
+            match (game_winner(mode, &outcome), swapped) {
+                (Some(0), false) | (Some(1), true) => wins1 += 1,
+                (Some(_), _) => wins2 += 1,
+                (None, _) => draws += 1,
+            }
+        }
+
+        MatchResult {
+            round,
+            strategy1: strategy1.name().to_string(),
+            strategy2: strategy2.name().to_string(),
+            wins1,
+            wins2,
+            draws,
+        }
+    }
+}
+
+impl Tournament for Elimination {
+    fn format(&self) -> &'static str {
+        if self.max_losses == 1 { "Single Elimination" } else { "Double Elimination" }
+    }
+
+    // Players are bracketed by their number of losses. Each round the players in every bracket
+    // are paired top seed against bottom seed, an odd player out receives a bye, and anyone
+    // reaching `max_losses` is eliminated.
+    fn run(&self, mode: GameMode, optimization: OptimizationLevel, strategies: &[Box<dyn Strategy>], seed: u64) -> Result<TournamentResult, String> {
+        let mut losses = vec![0; strategies.len()];
+        let mut matches = Vec::new();
+        let mut round = 0;
+
+        loop {
+            let alive: Vec<usize> = (0..strategies.len()).filter(|&i| losses[i] < self.max_losses).collect();
+            if alive.len() <= 1 {
+                break;
+            }
+
+            let mut pairs = Vec::new();
+            for bracket in 0..self.max_losses {
+                let players: Vec<usize> = alive.iter().copied().filter(|&i| losses[i] == bracket).collect();
+                for k in 0..players.len() / 2 {
+                    pairs.push((players[k], players[players.len() - 1 - k]));
+                }
+            }
+            // Grand final: the last unbeaten player meets the last survivor of the lower bracket.
+            if pairs.is_empty() {
+                pairs.push((alive[0], alive[1]));
+            }
+
+            for (a, b) in pairs {
+                let series_seed = game_seed(seed, matches.len() as u64);
+                let result = self.play_series(mode, optimization, strategies[a].as_ref(), strategies[b].as_ref(), round, series_seed);
+                match result.winner() {
+                    Some(1) => losses[a] += 1,
+                    _ => losses[b] += 1,
+                }
+                matches.push(result);
+            }
+            round += 1;
+        }
+
+        Ok(TournamentResult::from_matches(self.format(), strategies, matches))
+    }
+}
@@ -0,0 +1,30 @@
+// File: src/tournament_gauntlet.rs
+
+use crate::{GameMode, OptimizationLevel, Strategy};
+use crate::game::GameRules;
+use crate::rng::game_seed;
+use crate::tournament::{Tournament, TournamentResult, play_match};
+
+// One candidate plays every other strategy in the field; the field never plays itself.
+pub struct Gauntlet {
+    pub candidate: String,
+    pub games_per_match: usize,
+}
+
+impl Tournament for Gauntlet {
+    fn format(&self) -> &'static str { "Gauntlet" }
+
+    fn run(&self, mode: GameMode, optimization: OptimizationLevel, strategies: &[Box<dyn Strategy>], seed: u64) -> Result<TournamentResult, String> {
+        let candidate = strategies.iter()
+            .find(|s| s.name() == self.candidate)
+            .ok_or_else(|| format!("gauntlet candidate '{}' is not in the field", self.candidate))?;
+
+        let matches = strategies.iter()
+            .filter(|s| s.name() != self.candidate)
+            .enumerate()
+            .map(|(round, opponent)| play_match(mode, optimization, GameRules::default(), candidate.as_ref(), opponent.as_ref(), self.games_per_match, round, game_seed(seed, round as u64)))
+            .collect();
+
+        Ok(TournamentResult::from_matches(self.format(), strategies, matches))
+    }
+}
@@ -0,0 +1,98 @@
+// File: src/tournament_swiss.rs
+
+use crate::{GameMode, OptimizationLevel, Strategy};
+use crate::game::GameRules;
+use crate::rng::game_seed;
+use crate::tournament::{Tournament, TournamentResult, MatchResult, play_match};
+use std::collections::HashSet;
+
+pub struct Swiss {
+    pub rounds: usize,
+    pub games_per_match: usize,
+}
+
+impl Swiss {
+    // Pairs players with equal or nearest scores, avoiding rematches where possible.
+    // With an odd field the lowest-ranked of the players with the fewest byes sits out and
+    // scores a full point.
+    fn pair_round(points: &[f64], played: &HashSet<(usize, usize)>, byes: &[usize]) -> (Vec<(usize, usize)>, Option<usize>) {
+        let mut order: Vec<usize> = (0..points.len()).collect();
+        order.sort_by(|&a, &b| points[b].partial_cmp(&points[a]).unwrap().then(a.cmp(&b)));
+
+        let mut bye = None;
+        if order.len() % 2 == 1 {
+            let fewest = order.iter().map(|&i| byes[i]).min().unwrap_or(0);
+            let position = order.iter().rposition(|&i| byes[i] == fewest).unwrap_or(order.len() - 1);
+            bye = Some(order.remove(position));
+        }
+
+        let mut pairs = Vec::new();
+        while let Some(first) = order.first().copied() {
+            order.remove(0);
+            let position = order.iter()
+                .position(|&other| !played.contains(&(first.min(other), first.max(other))))
+                .unwrap_or(0);
+            let second = order.remove(position);
+            pairs.push((first, second));
+        }
+
+        (pairs, bye)
+    }
+}
+
+impl Tournament for Swiss {
+    fn format(&self) -> &'static str { "Swiss" }
+
+    fn run(&self, mode: GameMode, optimization: OptimizationLevel, strategies: &[Box<dyn Strategy>], seed: u64) -> Result<TournamentResult, String> {
+        let mut points = vec![0.0; strategies.len()];
+        let mut played = HashSet::new();
+        let mut byes = vec![0; strategies.len()];
+        let mut matches: Vec<MatchResult> = Vec::new();
+
+        for round in 0..self.rounds {
+            let (pairs, bye) = Self::pair_round(&points, &played, &byes);
+            if let Some(i) = bye {
+                points[i] += 1.0;
+                byes[i] += 1;
+            }
+
+            for (a, b) in pairs {
+                let match_seed = game_seed(seed, matches.len() as u64);
+                let result = play_match(mode, optimization, GameRules::default(), strategies[a].as_ref(), strategies[b].as_ref(), self.games_per_match, round, match_seed);
+                match result.winner() {
+                    Some(0) => points[a] += 1.0,
+                    Some(_) => points[b] += 1.0,
+                    None => {
+                        points[a] += 0.5;
+                        points[b] += 0.5;
+                    },
+                }
+                played.insert((a.min(b), a.max(b)));
+                matches.push(result);
+            }
+        }
+
+        let mut result = TournamentResult::from_matches(self.format(), strategies, matches);
+        // Byes are not matches, so credit their points directly.
+        for (i, &count) in byes.iter().enumerate() {
+            if let Some(standing) = result.standings.iter_mut().find(|s| s.name == strategies[i].name()) {
+                standing.points += count as f64;
+            }
+        }
+        result.standings.sort_by(|a, b| b.points.partial_cmp(&a.points).unwrap());
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byes_go_to_the_players_with_the_fewest() {
+        let points = [2.0, 1.0, 0.0];
+        assert_eq!(Swiss::pair_round(&points, &HashSet::new(), &[0, 0, 0]).1, Some(2));
+        assert_eq!(Swiss::pair_round(&points, &HashSet::new(), &[0, 0, 1]).1, Some(1));
+        assert_eq!(Swiss::pair_round(&points, &HashSet::new(), &[1, 1, 1]).1, Some(2));
+    }
+}
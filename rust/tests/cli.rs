@@ -0,0 +1,46 @@
+// File: tests/cli.rs
+
+use assert_cmd::Command;
+use std::process::Stdio;
+
+fn dice_game() -> Command {
+    Command::cargo_bin("dice_game").unwrap()
+}
+
+#[test]
+fn unknown_strategies_exit_with_3() {
+    let output = dice_game().args(["simulate", "-s", "Random,Nope", "-n", "1"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown strategy 'Nope'"));
+}
+
+#[test]
+fn invalid_configurations_exit_with_4() {
+    let output = dice_game().args(["replay", "-s", "Random"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("exactly two strategies"));
+}
+
+#[test]
+fn io_failures_exit_with_1() {
+    let missing = std::env::temp_dir().join("dice_game_cli_test_missing.toml");
+    let output = dice_game().arg("run").arg(&missing).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn malformed_command_lines_exit_with_2() {
+    let output = dice_game().args(["simulate", "--num-games", "many"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn list_strategies_survives_a_closed_pipe() {
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("dice_game"))
+        .arg("list-strategies")
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    drop(child.stdout.take());
+    assert!(child.wait().unwrap().success());
+}